@@ -3,29 +3,105 @@
 //! This application creates an animated city scene with moving vehicles, animated building windows,
 //! and configurable weather effects displayed as a screensaver in the terminal.
 
-use clap::Parser;
+use clap::{ArgAction, Parser, ValueEnum};
 use crossterm::{
     cursor::{self, Hide, Show},
-    event::{self, Event},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, MouseButton, MouseEventKind,
+    },
     style::{self, Color, Print},
     terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand, QueueableCommand,
 };
-use rand::{rngs::ThreadRng, Rng};
+use rand::{
+    Rng, RngCore, SeedableRng,
+    rngs::StdRng,
+    seq::IteratorRandom,
+};
+#[cfg(unix)]
+use signal_hook::consts::{SIGCONT, SIGSTOP, SIGTSTP};
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::{self, stdout, Write};
+use std::ops::{Range, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+#[cfg(unix)]
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 use std::time::{Duration, Instant};
 
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+/// `--version` output: the crate version plus the commit and date it was built from, as
+/// stamped by `build.rs`
+const LONG_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("CITY_GIT_HASH"),
+    ", built ",
+    env!("CITY_BUILD_DATE"),
+    ")"
+);
+
+#[derive(Parser, Debug, Clone)]
+#[command(
+    author,
+    version,
+    long_version = LONG_VERSION,
+    about,
+    long_about = None,
+    args_override_self = true
+)]
 /// Command-line arguments for configuring the city screensaver
+///
+/// Settings are resolved with explicit flags taking precedence over the chosen `--preset`,
+/// which in turn takes precedence over the built-in defaults.
 struct Args {
+    /// Start from a bundle of settings for a given mood; explicit flags still override it
+    #[arg(long, value_enum)]
+    preset: Option<Preset>,
+
+    /// Color theme of the sky, buildings, and windows
+    #[arg(long, value_enum, default_value_t = Theme::Night)]
+    theme: Theme,
+
+    /// Whether the terminal's background is dark or light, asked of the terminal when not
+    /// given; on a light one the night theme switches to a daylight palette that stands out
+    #[arg(long, value_enum)]
+    bg: Option<Background>,
+
+    /// Shorthand for `--theme high-contrast`: stark colors and heavy glyphs for low vision
+    #[arg(long, default_value_t = false)]
+    high_contrast: bool,
+
+    /// Run the animation at half speed
+    #[arg(long, default_value_t = false)]
+    slow: bool,
+
+    /// Just a calm lit skyline: no traffic, weather, clouds or anything else moving, only
+    /// twinkling stars and flickering windows, redrawn less often to save power; overrides
+    /// the flags that would add motion
+    #[arg(long, default_value_t = false)]
+    skyline_only: bool,
+
     /// Number of stars to display
     #[arg(long, default_value_t = 50)]
     stars: u16,
 
-    /// Number of raindrops to display
-    #[arg(long, default_value_t = 100)]
-    raindrops: u16,
+    /// Where in the sky stars gather: evenly, overhead, or low over the skyline
+    #[arg(long, value_enum, default_value_t = StarDistribution::Uniform)]
+    star_distribution: StarDistribution,
+
+    /// Number of raindrops to display, overriding the rain intensity's count
+    #[arg(long)]
+    raindrops: Option<u16>,
+
+    /// How hard the rain falls
+    #[arg(long, value_enum, default_value_t = RainIntensity::Medium)]
+    rain_intensity: RainIntensity,
 
     /// Number of snowflakes to display
     #[arg(long, default_value_t = 50)]
@@ -35,17 +111,724 @@ struct Args {
     #[arg(long, default_value_t = 5)]
     clouds: u16,
 
-    /// Update interval in milliseconds
+    /// Size of the cloud sprites
+    #[arg(long, value_enum, default_value_t = CloudSize::Small)]
+    cloud_size: CloudSize,
+
+    /// Let rain fall from beneath the clouds rather than anywhere along the top
+    #[arg(long, default_value_t = false)]
+    cloud_rain: bool,
+
+    /// Update interval in milliseconds, from 5 to 10000
     #[arg(long, default_value_t = 50)]
     interval: u64,
 
-    /// Enable rain effect
-    #[arg(long, default_value_t = true)]
+    /// Most frames to draw per second; with it set, input is polled every few milliseconds
+    /// and the scene kept moving between frames instead of waiting out `--interval`
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    max_fps: Option<u32>,
+
+    /// Enable rain effect (`--rain=false` turns it off)
+    #[arg(long, default_value_t = true, num_args = 0..=1, default_missing_value = "true", action = ArgAction::Set)]
     rain: bool,
 
     /// Enable snow effect
     #[arg(long, default_value_t = false)]
     snow: bool,
+
+    /// Fill the air with drifting cherry-blossom petals instead of snow
+    #[arg(long, default_value_t = false, conflicts_with = "snow")]
+    petals: bool,
+
+    /// Number of hot-air balloons to display
+    #[arg(long, default_value_t = 0)]
+    balloons: u16,
+
+    /// Probability of spawning a vehicle each frame (0.0 to 1.0)
+    #[arg(long, default_value_t = DEFAULT_SPAWN_RATE)]
+    spawn_rate: f32,
+
+    /// Column of a cross street where some vehicles turn off and others turn on
+    #[arg(long)]
+    intersection_x: Option<u16>,
+
+    /// Probability that a vehicle crossing the intersection turns off (0.0 to 1.0)
+    #[arg(long, default_value_t = DEFAULT_TURN_CHANCE, requires = "intersection_x")]
+    turn_chance: f32,
+
+    /// File of custom vehicles, one `glyph,color,speed` per line
+    #[arg(long)]
+    vehicles_file: Option<PathBuf>,
+
+    /// Fog intensity from 0.0 (clear) to 1.0 (thick haze)
+    #[arg(long, default_value_t = 0.0)]
+    fog: f32,
+
+    /// Trace each building's walls and roof in a darker shade of it, so neighbours of the
+    /// same color stand apart
+    #[arg(long, default_value_t = false)]
+    building_outlines: bool,
+
+    /// Warm light pollution over the skyline from 0.0 (none) to 1.0 (strong)
+    #[arg(long, default_value_t = 0.0)]
+    glow: f32,
+
+    /// Strongest wind gust in columns per frame; 0 keeps the air still
+    #[arg(long, default_value_t = 0.0)]
+    wind: f32,
+
+    /// Hour of the day (0-24) the scene starts at
+    #[arg(long, default_value_t = 21.0)]
+    start_hour: f32,
+
+    /// Seconds for a full simulated day; 0 keeps the clock fixed at the start hour
+    #[arg(long, default_value_t = 0)]
+    day_length: u64,
+
+    /// Busier traffic around 8am and 6pm and a near-empty road overnight, following the
+    /// clock
+    #[arg(long, default_value_t = false)]
+    rush_hour: bool,
+
+    /// Narrowest a building may be, in columns
+    #[arg(long, default_value_t = DEFAULT_BUILDING_MIN_WIDTH)]
+    building_min_width: u16,
+
+    /// Widest a building may be, in columns
+    #[arg(long, default_value_t = DEFAULT_BUILDING_MAX_WIDTH)]
+    building_max_width: u16,
+
+    /// Width of the city in columns; when wider than the terminal the view slowly pans
+    /// across it, wrapping around at the end
+    #[arg(long)]
+    scene_width: Option<u16>,
+
+    /// Number of buildings to replace with latticed radio towers, a red beacon blinking at
+    /// the tip of each
+    #[arg(long, default_value_t = 0)]
+    radio_towers: u16,
+
+    /// Widest gap between neighbouring buildings, in columns; 0 packs them wall to wall
+    #[arg(long, default_value_t = DEFAULT_BUILDING_GAP_MAX)]
+    building_gap_max: u16,
+
+    /// Rows of buildings, counting the one along the road; each row further back is darker,
+    /// shorter and stands still
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u8).range(1..=MAX_DEPTH_LAYERS)
+    )]
+    depth_layers: u8,
+
+    /// Scene updates run per drawn frame while fast-forward is toggled on with `>`
+    #[arg(
+        long,
+        default_value_t = DEFAULT_FAST_FORWARD_STEPS,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    fast_forward_steps: u32,
+
+    /// Add a construction site with a tower crane
+    #[arg(long, default_value_t = false)]
+    crane: bool,
+
+    /// Add a slowly turning ferris wheel behind the skyline
+    #[arg(long, default_value_t = false)]
+    ferris: bool,
+
+    /// Column the ferris wheel is centered on (defaults to a quarter of the way across)
+    #[arg(long, requires = "ferris")]
+    ferris_x: Option<u16>,
+
+    /// Add manholes on the road that let off drifting puffs of steam
+    #[arg(long, default_value_t = false)]
+    steam: bool,
+
+    /// Start in a storm: dark, fast clouds, gusting wind, heavy rain and lightning
+    #[arg(long, default_value_t = false)]
+    storm: bool,
+
+    /// Guard against burn-in by drifting the whole picture a few cells about over minutes
+    #[arg(long, default_value_t = false)]
+    drift: bool,
+
+    /// Add the shimmering bands of an aurora to the upper sky
+    #[arg(long, default_value_t = false)]
+    aurora: bool,
+
+    /// Mount a rotating airport beacon on the tallest building, sweeping its beam across
+    /// the sky
+    #[arg(long, default_value_t = false)]
+    beacon: bool,
+
+    /// Celebrate: launch fireworks from the skyline, their bursts lighting nearby windows
+    #[arg(long, default_value_t = false)]
+    fireworks: bool,
+
+    /// Rain streams of green glyphs down the screen, over the city
+    #[arg(long, default_value_t = false)]
+    matrix: bool,
+
+    /// Add a river with boats along the bottom of the screen
+    #[arg(long, default_value_t = false)]
+    river: bool,
+
+    /// Click the road to spawn vehicles and the sky to add clouds
+    #[arg(long, default_value_t = false)]
+    mouse: bool,
+
+    /// How quickly stars twinkle, in radians of phase per frame
+    #[arg(long, default_value_t = 0.1)]
+    twinkle_speed: f32,
+
+    /// Slowly turn the star field about a point on the horizon, as the night sky does
+    #[arg(long, default_value_t = false)]
+    star_drift: bool,
+
+    /// Draw a few constellations among the stars
+    #[arg(long, default_value_t = false)]
+    constellations: bool,
+
+    /// Let satellites occasionally cross the night sky
+    #[arg(long, default_value_t = false)]
+    satellites: bool,
+
+    /// Number of birds in the flocks that now and then fly across the sky; 0 for none
+    #[arg(long, default_value_t = 0)]
+    flock_size: u16,
+
+    /// Number of airplanes crossing high in the sky, trailing contrails
+    #[arg(long, default_value_t = 0)]
+    planes: u16,
+
+    /// Leave a fading trail behind fast vehicles
+    #[arg(long, default_value_t = false)]
+    motion_blur: bool,
+
+    /// Nudge each vehicle up or down a row from its lane, for traffic less neatly lined up
+    #[arg(long, default_value_t = false)]
+    vehicle_jitter: bool,
+
+    /// Leave vehicles off the road entirely
+    #[arg(long, default_value_t = false)]
+    no_vehicles: bool,
+
+    /// Leave clouds out of the sky
+    #[arg(long, default_value_t = false)]
+    no_clouds: bool,
+
+    /// Leave stars out of the sky
+    #[arg(long, default_value_t = false)]
+    no_stars: bool,
+
+    /// Strip of ground drawn between the road and the bottom of the screen
+    #[arg(long, value_enum, default_value_t = Ground::None)]
+    ground: Ground,
+
+    /// Row the buildings stand on, counted up from the bottom of the screen; raising it above
+    /// the street's five rows leaves a strip of `--ground` below the road
+    #[arg(long, value_name = "ROW-FROM-BOTTOM", default_value_t = STREET_ROWS)]
+    horizon: u16,
+
+    /// Lanes of traffic, from 1 to 4, heading alternate ways; past two, each extra lane
+    /// widens the street by two rows
+    #[arg(
+        long,
+        default_value_t = DEFAULT_LANES,
+        value_parser = clap::value_parser!(u8).range(1..=MAX_LANES as i64)
+    )]
+    lanes: u8,
+
+    /// Glyph and spacing of lit windows; big buildings get 2x2 windows in block style
+    #[arg(long, value_enum, default_value_t = WindowStyle::Block)]
+    window_style: WindowStyle,
+
+    /// Exit on its own after this many seconds; 0 runs until a key is pressed
+    #[arg(long, default_value_t = 0)]
+    duration: u64,
+
+    /// After restoring the terminal, print how many frames were drawn and vehicles spawned,
+    /// the average frame rate and how long the session ran
+    #[arg(long, default_value_t = false)]
+    stats_on_exit: bool,
+
+    /// Print nothing to stderr but fatal errors: no seed on exit and no restore warnings
+    #[arg(long, default_value_t = false)]
+    quiet: bool,
+
+    /// Write the frames to this file or named pipe instead of the terminal, one every
+    /// `--interval` until `--duration` is up
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Let the city settle for a while off-screen, print a single frame of it to stdout (or
+    /// `--output`), and exit; pair with `--seed` to get the same picture again
+    #[arg(long, default_value_t = false)]
+    snapshot: bool,
+
+    /// Letterbox the scene to this width:height ratio of cells, e.g. `2:1`
+    #[arg(long, value_name = "W:H", value_parser = parse_aspect)]
+    aspect: Option<Aspect>,
+
+    /// Cycle the weather through `kind:seconds` steps, e.g. `clear:120,rain:60,fog:30`,
+    /// starting over after the last; kinds are clear, rain, snow and fog
+    #[arg(long, value_name = "SCHEDULE", value_parser = parse_weather_schedule)]
+    weather_schedule: Option<WeatherSchedule>,
+
+    /// Seed for the scene's randomness, to recreate a session; a random one is picked and
+    /// printed on exit otherwise
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Simulate and render this many frames off-screen as fast as possible, print timings,
+    /// and exit
+    #[arg(long, value_name = "FRAMES")]
+    bench: Option<u32>,
+
+    /// Render this many frames of the `--bench` scene off-screen, print a hash of the last
+    /// one, and exit
+    #[arg(long, value_name = "FRAMES")]
+    frame_hash: Option<u32>,
+
+    /// Print the available presets, one per line, and exit
+    #[arg(long)]
+    list_presets: bool,
+
+    /// Print the available themes, one per line, and exit
+    #[arg(long)]
+    list_themes: bool,
+}
+
+/// The names a value enum accepts on the command line, one per line
+fn value_listing<T: ValueEnum>() -> String {
+    T::value_variants()
+        .iter()
+        .filter_map(|v| v.to_possible_value())
+        .map(|v| format!("{}\n", v.get_name()))
+        .collect()
+}
+
+/// A named bundle of settings that sets the mood of the scene
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Preset {
+    RainyNight,
+    ClearDusk,
+    SnowyMorning,
+    Cyberpunk,
+}
+
+impl Preset {
+    /// The command-line flags this preset stands for
+    fn args(self) -> &'static [&'static str] {
+        match self {
+            Preset::RainyNight => &[
+                "--theme=night",
+                "--rain=true",
+                "--rain-intensity=heavy",
+                "--clouds=8",
+                "--cloud-size=medium",
+                "--start-hour=23",
+                "--day-length=0",
+            ],
+            Preset::ClearDusk => &[
+                "--theme=dusk",
+                "--rain=false",
+                "--clouds=2",
+                "--stars=20",
+                "--start-hour=19",
+                "--day-length=600",
+            ],
+            Preset::SnowyMorning => &[
+                "--theme=dawn",
+                "--rain=false",
+                "--snow",
+                "--snowflakes=120",
+                "--start-hour=7",
+                "--day-length=0",
+            ],
+            Preset::Cyberpunk => &[
+                "--theme=neon",
+                "--rain=true",
+                "--rain-intensity=medium",
+                "--fog=0.3",
+                "--start-hour=1",
+                "--day-length=0",
+            ],
+        }
+    }
+}
+
+/// A color theme for the scene
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Theme {
+    Night,
+    Dawn,
+    Dusk,
+    Neon,
+    HighContrast,
+}
+
+impl Theme {
+    /// The colors this theme paints the scene with
+    fn palette(self) -> Palette {
+        match self {
+            Theme::Night => Palette {
+                sky: Color::Reset,
+                buildings: BUILDING_COLORS,
+                window_on: WINDOW_ON_COLOR,
+                outline: None,
+                bold: false,
+            },
+            Theme::Dawn => Palette {
+                sky: Color::Rgb {
+                    r: 60,
+                    g: 70,
+                    b: 100,
+                },
+                buildings: DAWN_BUILDING_COLORS,
+                window_on: Color::Rgb {
+                    r: 230,
+                    g: 220,
+                    b: 150,
+                },
+                outline: None,
+                bold: false,
+            },
+            Theme::Dusk => Palette {
+                sky: Color::Rgb {
+                    r: 40,
+                    g: 20,
+                    b: 50,
+                },
+                buildings: DUSK_BUILDING_COLORS,
+                window_on: Color::Rgb {
+                    r: 255,
+                    g: 170,
+                    b: 60,
+                },
+                outline: None,
+                bold: false,
+            },
+            Theme::Neon => Palette {
+                sky: Color::Rgb { r: 5, g: 0, b: 15 },
+                buildings: NEON_BUILDING_COLORS,
+                window_on: Color::Rgb {
+                    r: 0,
+                    g: 255,
+                    b: 200,
+                },
+                outline: None,
+                bold: false,
+            },
+            Theme::HighContrast => Palette {
+                sky: Color::Rgb { r: 0, g: 0, b: 0 },
+                buildings: [HIGH_CONTRAST_BUILDING_COLOR; 4],
+                window_on: Color::Rgb {
+                    r: 255,
+                    g: 255,
+                    b: 0,
+                },
+                outline: Some(Color::Rgb {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                }),
+                bold: true,
+            },
+        }
+    }
+}
+
+/// The colors a theme paints the scene with
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Palette {
+    sky: Color, // `Color::Reset` keeps the terminal's own background
+    buildings: [Color; 4],
+    window_on: Color,
+    outline: Option<Color>, // Traced around each building's walls and roof
+    bold: bool,             // Windows drawn as solid blocks whatever their style
+}
+
+/// Shade of the terminal's own background, which the night theme's sky leaves showing
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Background {
+    Dark,
+    Light,
+}
+
+/// The night theme's colors for a terminal with the given background: on a light one the
+/// sky is painted a pale blue under dark buildings, rather than left to the terminal
+fn palette_for_background(bg: Background) -> Palette {
+    match bg {
+        Background::Dark => Theme::Night.palette(),
+        Background::Light => Palette {
+            sky: LIGHT_SKY_COLOR,
+            buildings: LIGHT_BUILDING_COLORS,
+            window_on: WINDOW_ON_COLOR,
+            outline: None,
+            bold: false,
+        },
+    }
+}
+
+/// Works out the terminal's background from its reply to an OSC 11 query, such as
+/// `ESC ] 11 ; rgb:ffff/ffff/dddd ESC \`, or `None` when there is no reply in it
+fn parse_background_reply(reply: &[u8]) -> Option<Background> {
+    const PREFIX: &[u8] = b"]11;rgb:";
+    let start = reply.windows(PREFIX.len()).position(|w| w == PREFIX)? + PREFIX.len();
+    let end = reply[start..]
+        .iter()
+        .position(|&b| b == 0x1b || b == 0x07)
+        .map_or(reply.len(), |i| start + i);
+    let body = std::str::from_utf8(&reply[start..end]).ok()?;
+    // Each channel has one to four hex digits, scaled to its own range
+    let channels: Vec<f32> = body
+        .split('/')
+        .map(|hex| {
+            let value = u16::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| hex.len() <= 4)?;
+            Some(value as f32 / ((1u32 << (4 * hex.len())) - 1) as f32)
+        })
+        .collect::<Option<_>>()?;
+    let [r, g, b] = channels[..] else {
+        return None;
+    };
+    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    Some(if luminance > 0.5 {
+        Background::Light
+    } else {
+        Background::Dark
+    })
+}
+
+/// What is falling from the sky; rain and snow never fall together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WeatherState {
+    Clear,
+    Rain,
+    Snow,
+}
+
+impl WeatherState {
+    /// Weather picked by the flags, with snow replacing rain rather than mixing with it,
+    /// e.g. a rainy preset plus `--snow`
+    fn from_args(args: &Args) -> Self {
+        if args.snow {
+            WeatherState::Snow
+        } else if args.rain {
+            WeatherState::Rain
+        } else {
+            WeatherState::Clear
+        }
+    }
+
+    /// Weather after toggling rain: it starts raining, or clears if it already was
+    fn toggle_rain(self) -> Self {
+        if self == WeatherState::Rain {
+            WeatherState::Clear
+        } else {
+            WeatherState::Rain
+        }
+    }
+
+    /// Weather after toggling snow: it starts snowing, or clears if it already was
+    fn toggle_snow(self) -> Self {
+        if self == WeatherState::Snow {
+            WeatherState::Clear
+        } else {
+            WeatherState::Snow
+        }
+    }
+}
+
+/// A step of the `--weather-schedule`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Weather {
+    Clear,
+    Rain,
+    Snow,
+    /// Clear skies under at least `SCHEDULED_FOG` fog
+    Fog,
+}
+
+impl Weather {
+    /// What falls from the sky during this step
+    fn falling(self) -> WeatherState {
+        match self {
+            Weather::Clear | Weather::Fog => WeatherState::Clear,
+            Weather::Rain => WeatherState::Rain,
+            Weather::Snow => WeatherState::Snow,
+        }
+    }
+}
+
+/// The weather steps to cycle through and how long each lasts
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WeatherSchedule(Vec<(Weather, Duration)>);
+
+/// How hard the rain falls, from a light drizzle to a downpour
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum RainIntensity {
+    Light,
+    Medium,
+    Heavy,
+}
+
+impl RainIntensity {
+    /// The next intensity when cycling through them with a key
+    fn next(self) -> Self {
+        match self {
+            RainIntensity::Light => RainIntensity::Medium,
+            RainIntensity::Medium => RainIntensity::Heavy,
+            RainIntensity::Heavy => RainIntensity::Light,
+        }
+    }
+
+    /// Columns each drop is blown sideways per frame
+    fn slant(self) -> u16 {
+        match self {
+            RainIntensity::Heavy => 1,
+            _ => 0,
+        }
+    }
+
+    /// Glyph a drop is drawn with, matching its weight and slant
+    fn glyph(self) -> char {
+        match self {
+            RainIntensity::Light => '.',
+            RainIntensity::Medium => '|',
+            RainIntensity::Heavy => '\\',
+        }
+    }
+}
+
+/// Keys the screensaver responds to and what they do, as listed in the `?` help overlay
+const KEY_BINDINGS: [(&str, &str); 9] = [
+    ("r", "toggle rain"),
+    ("n", "toggle snow"),
+    ("c", "clear the weather"),
+    ("s", "toggle the storm"),
+    ("1-9", "send out that vehicle style"),
+    ("i", "cycle rain intensity"),
+    (">", "toggle fast-forward"),
+    ("?", "show or hide this help"),
+    ("any other key", "quit"),
+];
+const HELP_BORDER_COLOR: Color = Color::Rgb {
+    r: 120,
+    g: 120,
+    b: 160,
+};
+const HELP_TEXT_COLOR: Color = Color::Rgb {
+    r: 230,
+    g: 230,
+    b: 230,
+};
+
+/// Which pool of cloud sprites to draw clouds from
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum CloudSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl CloudSize {
+    /// The sprites clouds of this size are picked from
+    fn sprites(self) -> &'static [&'static [&'static str]] {
+        match self {
+            CloudSize::Small => &SMALL_CLOUD_SPRITES,
+            CloudSize::Medium => &MEDIUM_CLOUD_SPRITES,
+            CloudSize::Large => &LARGE_CLOUD_SPRITES,
+        }
+    }
+}
+
+/// Surface of the strip between the road and the bottom of the screen
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Ground {
+    Grass,
+    Concrete,
+    None,
+}
+
+impl Ground {
+    /// The characters the strip is textured with and their color, or `None` for no strip
+    fn texture(self) -> Option<(&'static [char], Color)> {
+        match self {
+            Ground::Grass => Some((&GRASS_TEXTURE, GRASS_COLOR)),
+            Ground::Concrete => Some((&CONCRETE_TEXTURE, CONCRETE_COLOR)),
+            Ground::None => None,
+        }
+    }
+}
+
+/// How stars are spread up and down the sky
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum StarDistribution {
+    Uniform,
+    Zenith,
+    Horizon,
+}
+
+/// How windows are drawn and spaced across a building's face
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum WindowStyle {
+    Block,
+    Dot,
+    Grid,
+}
+
+impl WindowStyle {
+    /// Window placement for a building of the given size
+    fn layout(self, width: u16, height: u16) -> WindowLayout {
+        match self {
+            WindowStyle::Block
+                if width >= LARGE_WINDOW_MIN_SIZE && height >= LARGE_WINDOW_MIN_SIZE =>
+            {
+                LARGE_BLOCK_WINDOWS
+            }
+            WindowStyle::Block => BLOCK_WINDOWS,
+            WindowStyle::Dot => DOT_WINDOWS,
+            WindowStyle::Grid => GRID_WINDOWS,
+        }
+    }
+}
+
+/// Glyph, size and spacing of the windows on one building
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WindowLayout {
+    glyph: char,
+    size: u16,     // Windows are size x size cells
+    col_step: u16, // Columns from one window to the next
+    row_step: u16, // Rows from one window to the next
+}
+
+impl WindowLayout {
+    /// How many windows fit across a building `width` columns wide, leaving a wall column each side
+    fn cols(&self, width: u16) -> u16 {
+        Self::count(width, self.col_step, self.size)
+    }
+
+    /// How many window rows fit in a building `height` rows tall, leaving a wall row above and below
+    fn rows(&self, height: u16) -> u16 {
+        Self::count(height, self.row_step, self.size)
+    }
+
+    fn count(extent: u16, step: u16, size: u16) -> u16 {
+        if extent < size + 2 {
+            0
+        } else {
+            (extent - size - 2) / step + 1
+        }
+    }
+
+    /// Top left cell of window (`wx`, `wy`), relative to the building's top left corner
+    fn origin(&self, wx: u16, wy: u16) -> (u16, u16) {
+        (1 + wx * self.col_step, 1 + wy * self.row_step)
+    }
 }
 
 /// Color constants for different elements in the city scene
@@ -54,6 +837,54 @@ const WINDOW_ON_COLOR: Color = Color::Rgb {
     g: 255,
     b: 0,
 };
+const TV_DIM_COLOR: Color = Color::Rgb {
+    r: 30,
+    g: 40,
+    b: 90,
+};
+const TV_BRIGHT_COLOR: Color = Color::Rgb {
+    r: 140,
+    g: 170,
+    b: 255,
+};
+const HEADLIGHT_COLOR: Color = Color::Rgb {
+    r: 140,
+    g: 130,
+    b: 50,
+};
+const TAILLIGHT_COLOR: Color = Color::Rgb {
+    r: 200,
+    g: 20,
+    b: 20,
+};
+const WATER_COLOR: Color = Color::Rgb {
+    r: 30,
+    g: 60,
+    b: 130,
+};
+const BOAT_COLOR: Color = Color::Rgb {
+    r: 200,
+    g: 200,
+    b: 180,
+};
+/// Dusk backdrop: the glowing sky and the near-black the buildings fade to against it
+const DUSK_SKY_COLOR: Color = Color::Rgb {
+    r: 235,
+    g: 120,
+    b: 50,
+};
+const SILHOUETTE_COLOR: Color = Color::Rgb {
+    r: 14,
+    g: 10,
+    b: 16,
+};
+/// How far toward black `--building-outlines` darkens a building's edges
+const OUTLINE_SHADE: f32 = 0.4;
+const LETTERBOX_COLOR: Color = Color::Rgb {
+    r: 30,
+    g: 30,
+    b: 30,
+};
 const WINDOW_OFF_COLOR: Color = Color::Rgb {
     r: 40,
     g: 40,
@@ -64,6 +895,46 @@ const ROAD_COLOR: Color = Color::Rgb {
     g: 20,
     b: 20,
 };
+const WET_ROAD_COLOR: Color = Color::Rgb { r: 8, g: 8, b: 14 };
+const SIDEWALK_COLOR: Color = Color::Rgb {
+    r: 90,
+    g: 90,
+    b: 85,
+};
+const CENTER_LINE_COLOR: Color = Color::Rgb {
+    r: 170,
+    g: 150,
+    b: 40,
+};
+const BUS_STOP_COLOR: Color = Color::Rgb {
+    r: 60,
+    g: 130,
+    b: 200,
+};
+const PUDDLE_COLOR: Color = Color::Rgb {
+    r: 70,
+    g: 80,
+    b: 120,
+};
+/// Frames a raindrop's splash holds each size before spreading, and its glyphs from the
+/// first speck to the widest ring, which sits either side of where the drop landed
+const RIPPLE_STAGE_FRAMES: u8 = 2;
+const RIPPLE_GLYPHS: [&str; 3] = [".", "o", "()"];
+const STEAM_COLOR: Color = Color::Rgb {
+    r: 210,
+    g: 210,
+    b: 215,
+};
+const STEAM_FADED_COLOR: Color = Color::Rgb {
+    r: 90,
+    g: 90,
+    b: 100,
+};
+const MANHOLE_COLOR: Color = Color::Rgb {
+    r: 60,
+    g: 60,
+    b: 60,
+};
 const MOON_COLOR: Color = Color::Rgb {
     r: 240,
     g: 240,
@@ -74,6 +945,26 @@ const STAR_COLOR: Color = Color::Rgb {
     g: 255,
     b: 255,
 };
+const STAR_DIM_COLOR: Color = Color::Rgb {
+    r: 90,
+    g: 90,
+    b: 90,
+};
+const CONSTELLATION_LINE_COLOR: Color = Color::Rgb {
+    r: 50,
+    g: 55,
+    b: 80,
+};
+const BIRD_COLOR: Color = Color::Rgb {
+    r: 40,
+    g: 40,
+    b: 50,
+};
+const SATELLITE_COLOR: Color = Color::Rgb {
+    r: 190,
+    g: 200,
+    b: 255,
+};
 const RAIN_COLOR: Color = Color::Rgb {
     r: 100,
     g: 100,
@@ -84,21 +975,591 @@ const SNOW_COLOR: Color = Color::Rgb {
     g: 200,
     b: 200,
 };
+/// `--matrix` streams: the glyphs they are made of, their speeds in rows per frame and trail
+/// lengths, and their leading, trailing and fully faded colors
+const MATRIX_GLYPHS: &str = "ｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝ0123456789:=*+<>";
+const MATRIX_SPEEDS: Range<f32> = 0.3..1.0;
+const MATRIX_TRAILS: Range<u16> = 4..14;
+const MATRIX_HEAD_COLOR: Color = Color::Rgb {
+    r: 200,
+    g: 255,
+    b: 200,
+};
+const MATRIX_COLOR: Color = Color::Rgb {
+    r: 0,
+    g: 230,
+    b: 70,
+};
+const MATRIX_FADED_COLOR: Color = Color::Rgb { r: 0, g: 40, b: 10 };
 const CLOUD_COLOR: Color = Color::Rgb {
     r: 150,
     g: 150,
     b: 150,
 };
+/// Storm clouds, darkened toward gray-black, and the sky while lightning flashes
+const STORM_CLOUD_COLOR: Color = Color::Rgb {
+    r: 55,
+    g: 55,
+    b: 60,
+};
+const LIGHTNING_COLOR: Color = Color::Rgb {
+    r: 210,
+    g: 210,
+    b: 230,
+};
+const BASKET_COLOR: Color = Color::Rgb {
+    r: 139,
+    g: 90,
+    b: 43,
+};
 
-const STAR_CHARS: [char; 4] = ['.', '*', '+', '\''];
-const SNOWFLAKE_CHARS: [char; 3] = ['*', '.', 'o'];
-const CLOUD_SHAPES: [&str; 3] = ["_.-^-._", " ~~~", "(-.-)"];
-const ANTENNA_CHARS: [char; 3] = ['|', 'Y', 'i'];
-const BUILDING_COLORS: [Color; 4] = [
+/// Per-frame vehicle spawn probability and the cap on vehicles on the road at once
+const DEFAULT_SPAWN_RATE: f32 = 0.1;
+const MAX_VEHICLES: usize = 30;
+/// Chance of a vehicle turning off at the intersection, when there is one
+const DEFAULT_TURN_CHANCE: f32 = 0.3;
+
+/// Hours the sunset glows behind the skyline
+const DUSK_HOURS: Range<f32> = 18.0..19.5;
+
+/// Shortest and longest frame intervals accepted, in milliseconds
+const MIN_INTERVAL_MS: u64 = 5;
+const MAX_INTERVAL_MS: u64 = 10_000;
+/// How far a spawned vehicle's cruising speed strays from its style's, as a fraction either
+/// way, the fraction of it a vehicle enters the screen at, and how much of the remaining
+/// gap it closes each frame
+const VEHICLE_SPEED_JITTER: f32 = 0.2;
+const VEHICLE_ENTRY_SPEED: f32 = 0.5;
+const VEHICLE_ACCELERATION: f32 = 0.05;
+/// Shape of `--rush-hour` traffic: the hours it peaks at and how many hours each peak
+/// lasts, the multiplier outside the peaks, how much each peak adds to it, and how much
+/// the overnight lull around `OVERNIGHT_HOUR` takes away
+const RUSH_HOURS: [f32; 2] = [8.0, 18.0];
+const RUSH_HOUR_WIDTH: f32 = 1.5;
+const OVERNIGHT_HOUR: f32 = 3.0;
+const OVERNIGHT_WIDTH: f32 = 3.0;
+const TRAFFIC_BASE: f32 = 0.8;
+const TRAFFIC_PEAK: f32 = 1.5;
+const TRAFFIC_OVERNIGHT_DIP: f32 = 0.6;
+
+/// Draw order of the built-in entity kinds; entities from `FOREGROUND_Z` up are drawn over
+/// the buildings and street rather than among the sky layers
+const CLOUD_Z: u8 = 10;
+const STAR_Z: u8 = 20;
+const PLANE_Z: u8 = 30;
+const BALLOON_Z: u8 = 40;
+const FOREGROUND_Z: u8 = 128;
+const VEHICLE_Z: u8 = 200;
+
+/// Smallest terminal the scene can be laid out in
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 15;
+/// Rows from the far sidewalk the buildings stand on down to the near one
+const STREET_ROWS: u16 = 5;
+/// Lanes on the street by default, which fill its five rows, and the most it can have
+const DEFAULT_LANES: u8 = 2;
+const MAX_LANES: u8 = 4;
+
+/// Scene size for `--output` when there's no terminal to take it from, and what starts
+/// each frame written there: clear the screen and home the cursor
+const OUTPUT_SIZE: (u16, u16) = (80, 24);
+const FRAME_SEPARATOR: &str = "\x1b[2J\x1b[H";
+/// Frames a `--snapshot` is simulated for before it is taken, long enough for lights to come
+/// on and traffic to fill the road
+const SNAPSHOT_WARMUP_FRAMES: u32 = 300;
+
+/// Fixed scene size and seed used by `--bench`, so runs are comparable
+const BENCH_WIDTH: u16 = 160;
+const BENCH_HEIGHT: u16 = 48;
+const BENCH_SEED: u64 = 0x5eed;
+
+/// FNV-1a parameters used by `frame_hash`, which must hash the same on every build
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Rainbow bands from the outside in, and how long the arc takes to fade away
+const RAINBOW_COLORS: [Color; 7] = [
+    Color::Rgb { r: 255, g: 0, b: 0 },
     Color::Rgb {
-        r: 60,
-        g: 60,
-        b: 60,
+        r: 255,
+        g: 127,
+        b: 0,
+    },
+    Color::Rgb {
+        r: 255,
+        g: 255,
+        b: 0,
+    },
+    Color::Rgb { r: 0, g: 255, b: 0 },
+    Color::Rgb { r: 0, g: 0, b: 255 },
+    Color::Rgb {
+        r: 75,
+        g: 0,
+        b: 130,
+    },
+    Color::Rgb {
+        r: 148,
+        g: 0,
+        b: 211,
+    },
+];
+const RAINBOW_FADE_SECS: f32 = 6.0;
+
+/// Seconds between wind gusts, how quickly the wind eases toward each gust, and the most it
+/// may change in a single frame
+const GUST_INTERVAL_SECS: std::ops::Range<f32> = 3.0..10.0;
+const WIND_EASE_RATE: f32 = 0.05;
+const WIND_MAX_DELTA: f32 = 0.1;
+/// How strongly the wind pushes clouds compared to rain and snow
+const CLOUD_WIND_FACTOR: f32 = 0.5;
+/// Burn-in drift: the most the picture strays in columns and rows, and the seconds each
+/// axis takes for a full swing; the periods differ so the path traces a slow Lissajous figure
+const DRIFT_RANGE: (f32, f32) = (2.0, 1.0);
+const DRIFT_PERIOD_SECS: (f32, f32) = (300.0, 420.0);
+
+/// Columns per frame the view pans across a city wider than the screen
+const PAN_SPEED: f32 = 0.05;
+
+/// How far, in rows, clouds bob above and below where they float, and how quickly
+const CLOUD_BOB_AMPLITUDE: f32 = 1.0;
+const CLOUD_BOB_SPEED: f32 = 0.03;
+
+/// Rows of the upper quarter clouds are spread across, and how many spots a new cloud tries
+/// before settling for one that overlaps a neighbour on its row
+const CLOUD_BANDS: u16 = 3;
+const CLOUD_PLACEMENT_TRIES: u32 = 8;
+
+/// In a storm: how much faster clouds move, how many more roll in, the strongest gust the
+/// wind reaches at the least, and the per-frame chance of lightning and how long it flashes
+const STORM_CLOUD_SPEEDUP: f32 = 3.0;
+const STORM_EXTRA_CLOUDS: u16 = 4;
+const STORM_WIND: f32 = 2.5;
+const LIGHTNING_CHANCE: f64 = 0.01;
+const LIGHTNING_FRAMES: u8 = 2;
+
+/// Wind strength above which tumbleweeds may blow across the road, the chance per frame of
+/// one appearing, and how fast the wind rolls them
+const TUMBLEWEED_WIND: f32 = 1.0;
+const TUMBLEWEED_CHANCE: f64 = 0.01;
+const TUMBLEWEED_WIND_FACTOR: f32 = 1.5;
+/// Glyphs a tumbleweed cycles through as it spins, one per spin step
+const TUMBLEWEED_FRAMES: [char; 3] = ['@', 'o', '*'];
+const TUMBLEWEED_COLOR: Color = Color::Rgb {
+    r: 160,
+    g: 130,
+    b: 80,
+};
+
+/// Fraction of windows lit at given hours of the day, interpolated in between
+const LIT_FRACTION_KEYFRAMES: [(f32, f32); 7] = [
+    (0.0, 0.3),
+    (5.0, 0.05),
+    (7.0, 0.15),
+    (9.0, 0.02),
+    (16.0, 0.02),
+    (20.0, 0.7),
+    (24.0, 0.3),
+];
+
+/// Vehicles that get flashing lights, and how long each flash lasts
+const EMERGENCY_GLYPHS: [&str; 2] = ["🚓", "🚑"];
+const FLASH_PERIOD: Duration = Duration::from_millis(200);
+/// Longest step the scene is advanced by at once, so waking from a suspend doesn't leap ahead
+const MAX_FRAME_DT: Duration = Duration::from_secs(1);
+/// Frame length the per-frame speeds are tuned for; a longer frame moves things further
+const NOMINAL_FRAME: Duration = Duration::from_millis(50);
+/// Updates run per drawn frame while fast-forwarding, unless `--fast-forward-steps` says otherwise
+const DEFAULT_FAST_FORWARD_STEPS: u32 = 8;
+/// Fog intensity of a foggy `--weather-schedule` step, unless `--fog` asks for more
+const SCHEDULED_FOG: f32 = 0.6;
+
+/// Buses pull up at the stop for this long to let passengers on and off
+const BUS_GLYPH: &str = "🚌";
+const BUS_STOP_TIME: Duration = Duration::from_secs(3);
+const BUS_STOP_SIGN: &str = "[B]";
+
+/// Snowplow glyphs with the blade facing right and left, its color and cruising speed,
+/// and the chance per frame one sets out while snow lies on the road
+const PLOW_GLYPHS: [&str; 2] = ["[##]=/", "\\=[##]"];
+const PLOW_COLOR: Color = Color::Rgb {
+    r: 240,
+    g: 150,
+    b: 20,
+};
+const PLOW_SPEED: f32 = 2.0;
+const PLOW_CHANCE: f64 = 0.005;
+
+/// Speed above which a vehicle leaves a motion trail, and the fading intensity of each trail
+/// cell moving away from the vehicle; the faster vehicles get the full trail
+const MOTION_BLUR_SPEED: f32 = 3.5;
+const MOTION_BLUR_FULL_SPEED: f32 = 4.5;
+const MOTION_TRAIL_FADE: [f32; 2] = [0.6, 0.3];
+
+/// Vehicles cast a shadow on the row below them, shifted this many columns left, away from
+/// the moon, and darkening what's there this much toward `SHADOW_COLOR`
+const VEHICLE_SHADOW_OFFSET: u16 = 1;
+const VEHICLE_SHADOW_STRENGTH: f32 = 0.6;
+const SHADOW_COLOR: Color = Color::Rgb {
+    r: 10,
+    g: 10,
+    b: 12,
+};
+
+/// Crane jib reach in columns, and how fast the jib turns and the hook travels per frame
+const CRANE_JIB_LENGTH: f32 = 12.0;
+const CRANE_TURN_SPEED: f32 = 0.01;
+const CRANE_HOOK_SPEED: f32 = 0.1;
+const CRANE_COLOR: Color = Color::Rgb {
+    r: 230,
+    g: 180,
+    b: 30,
+};
+
+/// Ferris wheel radius in rows, number of cabins, and turning speed in radians per frame
+///
+/// Terminal cells are about twice as tall as wide, so columns are stretched by
+/// `FERRIS_ASPECT` to keep the wheel round.
+const FERRIS_RADIUS: f32 = 6.0;
+const FERRIS_ASPECT: f32 = 2.0;
+const FERRIS_CABINS: usize = 8;
+const FERRIS_SPEED: f32 = 0.01;
+const FERRIS_COLOR: Color = Color::Rgb {
+    r: 200,
+    g: 200,
+    b: 210,
+};
+const FERRIS_CABIN_COLORS: [Color; 4] = [Color::Red, Color::Yellow, Color::Cyan, Color::Magenta];
+
+/// Rotating beacon: radians its beam turns per second, radians either side of its heading
+/// the beam spreads, how many columns it reaches, and its color and brightest strength
+const SWEEP_SPEED: f32 = 1.2;
+const SWEEP_SPREAD: f32 = 0.12;
+const SWEEP_REACH: f32 = 30.0;
+const SWEEP_COLOR: Color = Color::Rgb {
+    r: 255,
+    g: 250,
+    b: 210,
+};
+const SWEEP_STRENGTH: f32 = 0.7;
+
+/// Row the aurora's band is centered on, how many rows it spans, and how far its shimmer
+/// phase advances per frame
+const AURORA_ROW: f32 = 4.0;
+const AURORA_THICKNESS: u16 = 3;
+const AURORA_SPEED: f32 = 0.04;
+/// Summed sine waves shaping the band, as (amplitude, rows per column, phase multiplier)
+const AURORA_WAVES: [(f32, f32, f32); 3] = [(1.5, 0.15, 1.0), (0.8, 0.07, -0.6), (0.4, 0.31, 1.7)];
+const AURORA_GREEN: Color = Color::Rgb {
+    r: 60,
+    g: 220,
+    b: 120,
+};
+const AURORA_PURPLE: Color = Color::Rgb {
+    r: 150,
+    g: 70,
+    b: 200,
+};
+
+/// Ground strip textures, picked per cell by a fixed scatter so the strip doesn't shimmer
+const GRASS_TEXTURE: [char; 3] = ['.', ',', '\''];
+const CONCRETE_TEXTURE: [char; 3] = ['.', '_', '.'];
+const GRASS_COLOR: Color = Color::Rgb {
+    r: 40,
+    g: 110,
+    b: 40,
+};
+const CONCRETE_COLOR: Color = Color::Rgb {
+    r: 110,
+    g: 110,
+    b: 110,
+};
+/// Repeating ripple pattern of the river, shifted by one cell every `WATER_PERIOD`
+const WATER_PATTERN: [char; 4] = ['~', '-', '~', '≈'];
+const WATER_PERIOD: Duration = Duration::from_millis(200);
+/// How far reflections on the river sway either side of their source in columns, how fast
+/// they sway, and how bright a star must be to be reflected at all
+const REFLECTION_JITTER: f32 = 1.0;
+const REFLECTION_SWAY_SPEED: f32 = 0.15;
+const REFLECTION_MIN_BRIGHTNESS: f32 = 0.8;
+const BOAT_GLYPHS: [&str; 2] = ["⛵", "[___]"];
+/// Symbols below the emoji planes that terminals still draw two cells wide
+const WIDE_SYMBOLS: [char; 1] = ['⛵'];
+
+/// Constellation template: star offsets in cells, and the pairs of stars joined by lines
+type ConstellationTemplate = (&'static [(u16, u16)], &'static [(usize, usize)]);
+
+const CONSTELLATIONS: [ConstellationTemplate; 3] = [
+    // Big Dipper
+    (
+        &[(0, 0), (4, 1), (8, 1), (12, 2), (13, 4), (18, 4), (19, 1)],
+        &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 3)],
+    ),
+    // Cassiopeia
+    (
+        &[(0, 0), (3, 3), (6, 1), (9, 3), (12, 0)],
+        &[(0, 1), (1, 2), (2, 3), (3, 4)],
+    ),
+    // Orion
+    (
+        &[(0, 0), (8, 0), (3, 3), (4, 3), (5, 3), (0, 6), (8, 6)],
+        &[(0, 2), (1, 4), (2, 3), (3, 4), (2, 5), (4, 6)],
+    ),
+];
+
+/// Per-frame chance of a flock setting off across the sky, and how fast its birds fly
+const FLOCK_CHANCE: f64 = 0.005;
+const BIRD_SPEED: f32 = 0.5;
+const BIRD_MAX_SPEED: f32 = 0.8;
+/// Boid rules: how far a bird sees its neighbors, how close it lets them come, and how
+/// strongly it steers toward their center, matches their velocity, and keeps its distance
+const BIRD_NEIGHBOR_RADIUS: f32 = 6.0;
+const BIRD_SEPARATION: f32 = 1.5;
+const BIRD_COHESION: f32 = 0.01;
+const BIRD_ALIGNMENT: f32 = 0.05;
+const BIRD_AVOIDANCE: f32 = 0.2;
+/// How hard a bird straying out of the flock's band of sky is steered back into it
+const BIRD_BOUNDS_PULL: f32 = 0.05;
+/// Frames per wing beat, and the wing glyphs it alternates between
+const BIRD_FLAP_FRAMES: u32 = 4;
+const BIRD_GLYPHS: [char; 2] = ['v', '-'];
+
+/// Per-frame chance of a satellite pass starting, and its horizontal speed
+const SATELLITE_CHANCE: f64 = 0.002;
+const SATELLITE_SPEED: f32 = 0.15;
+
+/// Per-frame chance of a firework launching while fewer than the most at once are up, the
+/// pull bringing shells and sparks back down, and how many sparks a burst throws out, how
+/// fast and for how many frames
+const FIREWORK_CHANCE: f64 = 0.04;
+const MAX_FIREWORKS: usize = 3;
+const FIREWORK_GRAVITY: f32 = 0.02;
+const FIREWORK_SPARKS: usize = 24;
+const FIREWORK_SPARK_SPEED: std::ops::Range<f32> = 0.3..0.6;
+const FIREWORK_SPARK_LIFE: u8 = 20;
+const FIREWORK_COLORS: [Color; 5] = [
+    Color::Rgb {
+        r: 255,
+        g: 80,
+        b: 80,
+    },
+    Color::Rgb {
+        r: 255,
+        g: 210,
+        b: 80,
+    },
+    Color::Rgb {
+        r: 120,
+        g: 255,
+        b: 140,
+    },
+    Color::Rgb {
+        r: 120,
+        g: 170,
+        b: 255,
+    },
+    Color::Rgb {
+        r: 230,
+        g: 120,
+        b: 255,
+    },
+];
+/// How many columns either side of a burst its light reaches lit windows, and how strongly
+/// it tints the nearest
+const FIREWORK_GLOW_RADIUS: f32 = 12.0;
+const FIREWORK_GLOW: f32 = 0.35;
+
+/// Airplane speed range, frames per nav light blink, and how many frames each contrail
+/// cell lingers before dissipating; a plane lays at most one cell a frame, so this also
+/// caps a contrail's length
+const PLANE_SPEEDS: std::ops::Range<f32> = 0.3..0.7;
+const PLANE_BLINK_FRAMES: u8 = 6;
+const CONTRAIL_MAX_LEN: usize = 16;
+const PLANE_GLYPH_WIDTH: u16 = 3;
+const PLANE_COLOR: Color = Color::Rgb {
+    r: 210,
+    g: 210,
+    b: 220,
+};
+const CONTRAIL_COLOR: Color = Color::Rgb {
+    r: 170,
+    g: 170,
+    b: 180,
+};
+
+/// Fraction of the remaining distance a window's brightness covers each frame
+const WINDOW_FADE_RATE: f32 = 0.3;
+
+/// How long to wait for input between frames while nothing in the scene is moving
+const IDLE_POLL_MS: u64 = 500;
+/// How long to wait for the terminal to say what its background is
+const BACKGROUND_QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+/// How long to wait for input between updates when `--max-fps` paces the drawing instead
+const INPUT_POLL_MS: u64 = 10;
+
+/// Chance that a window is lit by a television
+const TV_WINDOW_CHANCE: f64 = 0.05;
+/// Most each RGB channel of a building's or window lamp's color strays from the palette's,
+/// so no two look quite alike
+const COLOR_JITTER: u8 = 10;
+
+/// Ripple glyphs a puddle cycles through, and how long a soaked road takes to dry
+const PUDDLE_CHARS: [char; 4] = ['~', '≈', '~', '-'];
+const ROAD_DRY_SECS: f32 = 5.0;
+
+/// Steam glyphs from thinnest to densest, the chance per frame that a manhole lets off a
+/// puff, and how far a puff rises, drifts and fades each frame
+const STEAM_CHARS: [char; 3] = ['·', '∘', '░'];
+const STEAM_EMIT_CHANCE: f64 = 0.3;
+const STEAM_RISE: f32 = 0.3;
+const STEAM_DRIFT: f32 = 0.2;
+const STEAM_FADE: f32 = 0.06;
+
+/// How deep snow can pile on a roof in cells, how fast it settles and melts in cells per
+/// second, and how deep it must be before it also lines the window sills
+const ROOF_SNOW_MAX: f32 = 2.0;
+const ROOF_SNOW_RATE: f32 = 0.05;
+const ROOF_SNOW_MELT: f32 = 0.1;
+const SILL_SNOW_DEPTH: f32 = 1.0;
+/// How fast snow settles on the road and melts off it, as a fraction of full cover per
+/// second, and the glyphs for light through deep cover
+const ROAD_SNOW_RATE: f32 = 0.02;
+const ROAD_SNOW_MELT: f32 = 0.05;
+const ROAD_SNOW_CHARS: [char; 3] = ['.', '░', '▒'];
+
+const STAR_CHARS: [char; 4] = ['.', '*', '+', '\''];
+/// How fast `--star-drift` turns the sky, in radians per second: once round an hour
+const STAR_DRIFT_SPEED: f32 = std::f32::consts::TAU / 3600.0;
+const SNOWFLAKE_CHARS: [char; 3] = ['*', '.', 'o'];
+/// Petal glyphs and pink shades, how many petals drift at once, and the shape of their
+/// flutter: sway in columns per frame and how fast they swirl through it
+const PETAL_CHARS: [char; 2] = ['*', '.'];
+const PETAL_COLORS: [Color; 3] = [
+    Color::Rgb {
+        r: 255,
+        g: 183,
+        b: 197,
+    },
+    Color::Rgb {
+        r: 255,
+        g: 145,
+        b: 175,
+    },
+    Color::Rgb {
+        r: 240,
+        g: 200,
+        b: 215,
+    },
+];
+const PETAL_COUNT: u16 = 60;
+const PETAL_SWAY: f32 = 0.4;
+const PETAL_SWIRL_SPEED: f32 = 0.15;
+/// Cloud sprites, one string per row
+const SMALL_CLOUD_SPRITES: [&[&str]; 3] = [&["_.-^-._"], &[" ~~~"], &["(-.-)"]];
+const MEDIUM_CLOUD_SPRITES: [&[&str]; 2] = [
+    &["  .--.", ".(    ).", "(___.__)"],
+    &[" _  _", "( )( )_", "(______)"],
+];
+const LARGE_CLOUD_SPRITES: [&[&str]; 2] = [
+    &[
+        "      _  _",
+        "  .-(  )(  )-.",
+        " (            )",
+        "(______________)",
+    ],
+    &[
+        "       .--.   .-.",
+        "   .-(    ).-(   ).",
+        " (                  )",
+        "(____________________)",
+    ],
+];
+const FOG_WISP_SHAPES: [&[&str]; 3] = [&["~~~~~~"], &["-~~-~~-"], &["~ ~~~ ~"]];
+/// Color everything is blended toward as fog thickens
+const FOG_COLOR: Color = CLOUD_COLOR;
+const ANTENNA_CHARS: [char; 3] = ['|', 'Y', 'i'];
+/// Chance an antenna carries a blinking light, and how long a beacon stays on or off
+const ANTENNA_BEACON_CHANCE: f64 = 0.5;
+const BEACON_PERIOD: Duration = Duration::from_millis(800);
+const BEACON_CHAR: char = '●';
+const BEACON_COLOR: Color = Color::Rgb {
+    r: 255,
+    g: 40,
+    b: 40,
+};
+/// Radio tower mast: its width, the lattice of alternate rows, and the rows of sky left
+/// above its beacon
+const RADIO_TOWER_WIDTH: u16 = 3;
+const RADIO_TOWER_LATTICE: [[char; 3]; 2] = [['|', '\\', '|'], ['|', '/', '|']];
+const RADIO_TOWER_CLEARANCE: u16 = 2;
+/// Height ranges for short houses, and the width ranges for wide houses and narrow towers
+const HOUSE_HEIGHTS: std::ops::Range<u16> = 5..8;
+const HOUSE_WIDTHS: std::ops::Range<u16> = 9..15;
+const SKYSCRAPER_WIDTHS: std::ops::Range<u16> = 5..8;
+const SPIRE_HEIGHT: u16 = 2;
+/// Default width range of buildings and widest gap between them, and the narrowest a
+/// building can be while still fitting a window
+const DEFAULT_BUILDING_MIN_WIDTH: u16 = 5;
+const DEFAULT_BUILDING_MAX_WIDTH: u16 = 14;
+const DEFAULT_BUILDING_GAP_MAX: u16 = 4;
+/// Window layouts for each `--window-style`
+const BLOCK_WINDOWS: WindowLayout = WindowLayout {
+    glyph: '■',
+    size: 1,
+    col_step: 2,
+    row_step: 2,
+};
+const LARGE_BLOCK_WINDOWS: WindowLayout = WindowLayout {
+    glyph: '█',
+    size: 2,
+    col_step: 3,
+    row_step: 3,
+};
+const DOT_WINDOWS: WindowLayout = WindowLayout {
+    glyph: '•',
+    size: 1,
+    col_step: 3,
+    row_step: 2,
+};
+const GRID_WINDOWS: WindowLayout = WindowLayout {
+    glyph: '▪',
+    size: 1,
+    col_step: 2,
+    row_step: 1,
+};
+/// Buildings at least this wide and tall get 2x2 windows in block style
+const LARGE_WINDOW_MIN_SIZE: u16 = 10;
+const MIN_BUILDING_WIDTH: u16 = 3;
+/// Most rows of buildings `--depth-layers` stacks up, how much darker each row back is, and
+/// how many rows shorter its tallest buildings are
+const MAX_DEPTH_LAYERS: i64 = 4;
+const DEPTH_DIMMING: f32 = 0.3;
+const DEPTH_SHRINK: u16 = 4;
+/// Crescent moon sprite, drawn near the top right corner
+const MOON_SPRITE: [&str; 3] = ["  ,'.'.", " ,'. ..'.", ".' .. '. '."];
+/// Rows the moon's glow reaches from its center, and how bright the glow is right beside it
+/// compared to the moon itself
+const MOON_HALO_RADIUS: u16 = 4;
+const MOON_HALO_STRENGTH: f32 = 0.35;
+/// Light pollution: its color, how many rows it rises above a roof, how far a building's
+/// light spreads sideways, the lit-window height that saturates it, and the faintest drawn
+const GLOW_COLOR: Color = Color::Rgb {
+    r: 255,
+    g: 150,
+    b: 60,
+};
+const GLOW_ROWS: u16 = 4;
+const GLOW_REACH: f32 = 6.0;
+const GLOW_SATURATION: f32 = 150.0;
+const GLOW_MIN: f32 = 0.05;
+/// Hot-air balloon sprite: every row but the last is the envelope, the last is the basket
+const BALLOON_SPRITE: [&str; 4] = [" .-. ", "(   )", " \\ / ", "  #  "];
+const BALLOON_COLORS: [Color; 4] = [Color::Red, Color::Magenta, Color::Cyan, Color::Yellow];
+const BALLOON_SPEED: f32 = 0.3;
+const BUILDING_COLORS: [Color; 4] = [
+    Color::Rgb {
+        r: 60,
+        g: 60,
+        b: 60,
     },
     Color::Rgb {
         r: 70,
@@ -116,16 +1577,122 @@ const BUILDING_COLORS: [Color; 4] = [
         b: 90,
     },
 ];
-const VEHICLE_STYLES: [(&str, Color, f32); 9] = [
-    ("─=≡(°o°)", Color::Yellow, 5.0),
-    ("[\\__\\_]", Color::Green, -3.0),
-    ("o-o-o", Color::Cyan, 4.0),
-    ("[##-##]", Color::Magenta, -2.5),
-    ("<(o.o)>", Color::Red, 2.0),
-    ("🚚", Color::Blue, -2.0),
-    ("🚓", Color::White, 3.5),
-    ("🚑", Color::Red, -4.0),
-    ("🚌", Color::Green, 2.8),
+/// Colors of the night theme on a light terminal background: a pale daytime sky and
+/// buildings dark enough to stand out against it
+const LIGHT_SKY_COLOR: Color = Color::Rgb {
+    r: 170,
+    g: 205,
+    b: 240,
+};
+const LIGHT_BUILDING_COLORS: [Color; 4] = [
+    Color::Rgb {
+        r: 35,
+        g: 40,
+        b: 55,
+    },
+    Color::Rgb {
+        r: 45,
+        g: 50,
+        b: 65,
+    },
+    Color::Rgb {
+        r: 55,
+        g: 60,
+        b: 75,
+    },
+    Color::Rgb {
+        r: 65,
+        g: 70,
+        b: 85,
+    },
+];
+const DAWN_BUILDING_COLORS: [Color; 4] = [
+    Color::Rgb {
+        r: 90,
+        g: 95,
+        b: 110,
+    },
+    Color::Rgb {
+        r: 100,
+        g: 105,
+        b: 120,
+    },
+    Color::Rgb {
+        r: 110,
+        g: 115,
+        b: 130,
+    },
+    Color::Rgb {
+        r: 120,
+        g: 125,
+        b: 140,
+    },
+];
+const DUSK_BUILDING_COLORS: [Color; 4] = [
+    Color::Rgb {
+        r: 50,
+        g: 35,
+        b: 55,
+    },
+    Color::Rgb {
+        r: 60,
+        g: 40,
+        b: 60,
+    },
+    Color::Rgb {
+        r: 70,
+        g: 45,
+        b: 65,
+    },
+    Color::Rgb {
+        r: 80,
+        g: 50,
+        b: 70,
+    },
+];
+const NEON_BUILDING_COLORS: [Color; 4] = [
+    Color::Rgb { r: 30, g: 0, b: 50 },
+    Color::Rgb { r: 0, g: 30, b: 50 },
+    Color::Rgb { r: 40, g: 0, b: 40 },
+    Color::Rgb {
+        r: 10,
+        g: 10,
+        b: 40,
+    },
+];
+/// Body of every building in the high-contrast theme, dark enough to set off its white
+/// outline and yellow windows
+const HIGH_CONTRAST_BUILDING_COLOR: Color = Color::Rgb {
+    r: 20,
+    g: 20,
+    b: 90,
+};
+/// How many times longer each frame lasts with `--slow`
+const SLOW_FACTOR: u64 = 2;
+/// Shortest frame interval with `--skyline-only`, where nothing moves fast enough to need more
+const SKYLINE_INTERVAL_MS: u64 = 200;
+/// Styles drawn differently depending on which way they drive: the style, then how it looks
+/// heading right and heading left. Emoji can't be mirrored, so they keep one look.
+const DIRECTIONAL_GLYPHS: [(&str, &str, &str); 3] = [
+    ("─=≡(°o°)", "─=≡(°o°)", "(°o°)≡=─"),
+    ("[\\__\\_]", "[_/__/]", "[\\__\\_]"),
+    ("o-o-o", "o-o-o", "<o-o-o"),
+];
+/// A vehicle glyph together with its color and signed speed
+///
+/// The built-in glyphs are borrowed; those loaded from a vehicles file are owned.
+type VehicleStyle = (Cow<'static, str>, Color, f32);
+
+const VEHICLE_STYLES: [VehicleStyle; 9] = [
+    (Cow::Borrowed("─=≡(°o°)"), Color::Yellow, 5.0),
+    (Cow::Borrowed("[\\__\\_]"), Color::Green, -3.0),
+    (Cow::Borrowed("o-o-o"), Color::Cyan, 4.0),
+    (Cow::Borrowed("[##-##]"), Color::Magenta, -2.5),
+    (Cow::Borrowed("<(o.o)>"), Color::Red, 2.0),
+    (Cow::Borrowed("🚚"), Color::Blue, -2.0),
+    (Cow::Borrowed("🚓"), Color::White, 3.5),
+    (Cow::Borrowed("🚑"), Color::Red, -4.0),
+    (Cow::Borrowed("🚌"), Color::Green, 2.8),
 ];
 
 /// Represents a star in the night sky
@@ -133,6 +1700,73 @@ struct Star {
     x: u16,
     y: u16,
     char: char,
+    brightness: f32, // 0.0 (dim gray) to 1.0 (full white)
+    phase: f32,      // Position in the twinkle cycle, in radians
+    exact: (f32, f32), // Unrounded position, so a slow `--star-drift` adds up
+}
+
+/// Represents a constellation: bright anchor stars joined by faint lines
+struct Constellation {
+    stars: Vec<(u16, u16)>,
+    segments: &'static [(usize, usize)],
+}
+
+/// Represents a bird flying with its flock
+struct Bird {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+}
+
+/// Whether a firework shell is still climbing or has burst into sparks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FireworkState {
+    Rising,
+    Bursting,
+}
+
+/// A firework shell launched from the skyline, and the sparks it bursts into
+struct Firework {
+    x: f32,
+    y: f32,
+    vy: f32, // Negative while climbing; it bursts at the top of its arc
+    state: FireworkState,
+    color: Color,
+    sparks: Vec<Spark>,
+}
+
+/// One spark flying out of a firework burst
+struct Spark {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    life: u8, // Frames left before it burns out
+}
+
+/// Represents a satellite slowly crossing the sky in a straight line
+struct Satellite {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+}
+
+/// Represents an airplane crossing the top of the sky, trailing a contrail
+struct Plane {
+    x: f32,
+    y: u16,
+    speed: f32, // Negative when flying left
+    blink: f32, // Frames elapsed, driving the nav light
+    trail: ParticlePool<ContrailCell>,
+}
+
+/// Represents one cell of a plane's contrail, dimming as it ages
+struct ContrailCell {
+    x: u16,
+    y: u16,
+    age: u8, // Frames since it was laid
 }
 
 /// Represents a raindrop falling down the screen
@@ -151,663 +1785,8172 @@ struct Snowflake {
     char: char,
 }
 
+/// A column of `--matrix` glyphs streaming down, brightest at its head
+struct MatrixStream {
+    x: u16,
+    head: f32,         // Row of the leading glyph, negative while still above the screen
+    speed: f32,        // Rows per frame
+    trail: u16,        // Glyphs following the head
+    glyphs: Vec<char>, // Glyph showing at each row, one re-rolled every frame
+}
+
+/// Represents a cherry-blossom petal fluttering down on a swirling path
+struct Petal {
+    x: f32,
+    y: f32,
+    fall_speed: f32,
+    phase: f32, // Position in the swirl, in radians
+    char: char,
+    color: Color,
+}
+
 /// Represents a cloud moving across the sky
 struct Cloud {
     x: f32,
-    y: u16,
-    shape: &'static str,
+    y: u16,                         // Row it bobs about
+    shape: &'static [&'static str], // One string per row
     speed: f32,
+    phase: f32, // Position in its bob, in radians
+}
+
+/// Represents a hot-air balloon drifting across the upper sky
+struct Balloon {
+    x: f32,
+    y: u16,
+    color: Color,
+    frame: f32, // Frames elapsed, driving the vertical bob
+}
+
+/// Tracks the rainbow that appears briefly once rain stops
+struct Rainbow {
+    was_raining: bool,
+    alpha: f32, // 1.0 just after the rain stopped, fading to 0.0
+}
+
+/// Gusting wind shared by the rain, snow, and clouds, positive blowing to the right
+struct Wind {
+    current: f32,
+    target: f32,
+    change_timer: f32, // Seconds until the next gust picks a new target
+}
+
+/// A tumbleweed rolling along the road in a strong wind
+struct Tumbleweed {
+    x: f32,
+    y: u16, // Road row it rolls along, bouncing one row up now and then
+    spin: f32, // Frames it has rolled for
+}
+
+/// Fixed set of slots for short-lived particles, refilling the slots of dead particles
+/// before growing so steady spawning and dying doesn't allocate every frame
+struct ParticlePool<T> {
+    slots: Vec<Option<T>>,
+    free: Vec<usize>, // Indices of empty slots
+}
+
+impl<T> Default for ParticlePool<T> {
+    fn default() -> Self {
+        ParticlePool {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+}
+
+impl<T> ParticlePool<T> {
+    /// Adds a particle, in a freed slot if there is one
+    fn spawn(&mut self, particle: T) {
+        match self.free.pop() {
+            Some(i) => self.slots[i] = Some(particle),
+            None => self.slots.push(Some(particle)),
+        }
+    }
+
+    /// Keeps only the particles `keep` returns true for, freeing the others' slots
+    fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            if slot.as_ref().is_some_and(|p| !keep(p)) {
+                *slot = None;
+                self.free.push(i);
+            }
+        }
+    }
+
+    /// Removes every particle, keeping the slots for reuse
+    fn clear(&mut self) {
+        self.retain(|_| false);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().flatten()
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().flatten()
+    }
+}
+
+/// Represents a puff of steam rising from a manhole and fading as it goes
+struct SteamPuff {
+    x: f32,
+    y: f32,
+    life: f32, // Fades from 1.0 when emitted to 0.0 when it has dissipated
+}
+
+/// A splash spreading out where a raindrop hit the road
+struct Ripple {
+    x: u16,
+    y: u16,
+    age: u8, // Frames since the drop landed
+}
+
+/// Represents a rain puddle on the road that ripples while rain falls
+struct Puddle {
+    x: u16,
+    phase: u8,
 }
 
 /// Represents a window in a building that can be on or off
 struct Window {
     on: bool,
+    tv: bool,        // Lit by a flickering television rather than a lamp
+    flicker: f32,    // Current TV brightness in 0.0..=1.0
+    brightness: f32, // Eases toward 1.0 when on and 0.0 when off
+    lamp: Color,     // Color when fully lit by its lamp
+}
+
+/// The architectural style of a building, which decides its proportions and roof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BuildingKind {
+    /// Short, wide building with a pitched roof
+    House,
+    /// Ordinary block, optionally topped with an antenna
+    MidRise,
+    /// Tall, narrow tower with a spire
+    Skyscraper,
+    /// Latticed mast with a beacon at its tip
+    RadioTower,
 }
 
 /// Represents a building with windows and optional antenna
 struct Building {
+    kind: BuildingKind,
     x: u16,
     width: u16,
     height: u16,
     color: Color,
     windows: Vec<Vec<Window>>,
+    window_layout: WindowLayout,
     has_antenna: bool,
     antenna_char: char,
+    roof_snow: f32,              // Depth of snow settled on the roof, in cells
+    beacon: Option<BeaconState>, // Blinking light at the top of the mast or antenna
+}
+
+/// A warning light blinking at the top of a mast
+struct BeaconState {
+    timer: BlinkTimer,
+}
+
+impl BeaconState {
+    /// A lit beacon, `phase` into its first period so neighbouring beacons don't blink
+    /// in step
+    fn new(phase: Duration) -> Self {
+        let mut timer = BlinkTimer::new(BEACON_PERIOD);
+        timer.elapsed = phase.min(BEACON_PERIOD - Duration::from_millis(1));
+        BeaconState { timer }
+    }
+
+    fn lit(&self) -> bool {
+        self.timer.toggles.is_multiple_of(2)
+    }
+}
+
+/// How wide buildings are and how far apart they stand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BuildingLayout {
+    min_width: u16,
+    max_width: u16,
+    gap_max: u16,
+    window_style: WindowStyle,
+}
+
+impl Default for BuildingLayout {
+    fn default() -> Self {
+        BuildingLayout {
+            min_width: DEFAULT_BUILDING_MIN_WIDTH,
+            max_width: DEFAULT_BUILDING_MAX_WIDTH,
+            gap_max: DEFAULT_BUILDING_GAP_MAX,
+            window_style: WindowStyle::Block,
+        }
+    }
+}
+
+impl BuildingLayout {
+    fn from_args(args: &Args) -> Self {
+        BuildingLayout {
+            min_width: args.building_min_width,
+            max_width: args.building_max_width,
+            gap_max: args.building_gap_max,
+            window_style: args.window_style,
+        }
+    }
 }
 
 /// Represents a vehicle moving along the road
 struct Vehicle {
     x: f32,
     y: u16,
-    style: &'static str,
+    style: Cow<'static, str>,
     color: Color,
-    speed: f32,
+    speed: f32,        // Current speed, negative when driving left
+    target_speed: f32, // Cruising speed it accelerates toward
+    flashing: bool,    // Emergency vehicles flash red/blue lights
+    is_plow: bool,     // Snowplows clear the road snow they pass over
+    stop_timer: Duration, // Time a bus has left waiting at the stop
 }
 
-/// Sets up the terminal for the screensaver by enabling raw mode and switching to alternate screen
-fn setup_terminal() -> io::Result<std::io::Stdout> {
-    let mut stdout = stdout();
-    stdout.execute(EnterAlternateScreen).map_err(|e| {
-        io::Error::other(
-            format!("Failed to enter alternate screen: {}", e),
-        )
-    })?;
-    stdout.execute(Hide).map_err(|e| {
-        io::Error::other(
-            format!("Failed to hide cursor: {}", e),
-        )
-    })?;
-    terminal::enable_raw_mode().map_err(|e| {
-        io::Error::other(
-            format!("Failed to enable raw mode: {}", e),
-        )
-    })?;
-    Ok(stdout)
+impl Vehicle {
+    /// What the vehicle looks like heading the way it's going
+    fn glyph(&self) -> &str {
+        directional_glyph(&self.style, self.target_speed)
+    }
 }
 
-/// Restores the terminal to its original state after the screensaver exits
-fn restore_terminal(stdout: &mut std::io::Stdout) -> io::Result<()> {
-    terminal::disable_raw_mode().map_err(|e| {
-        io::Error::other(
-            format!("Failed to disable raw mode: {}", e),
+/// The look of `style` facing the way a vehicle at `speed` drives, or the style itself when
+/// it has no directional variants
+fn directional_glyph(style: &str, speed: f32) -> &str {
+    DIRECTIONAL_GLYPHS
+        .iter()
+        .find(|(s, _, _)| *s == style)
+        .map_or(
+            style,
+            |&(_, right, left)| if speed < 0.0 { left } else { right },
         )
-    })?;
-    stdout.execute(Show).map_err(|e| {
-        io::Error::other(
-            format!("Failed to show cursor: {}", e),
-        )
-    })?;
-    stdout.execute(LeaveAlternateScreen).map_err(|e| {
-        io::Error::other(
-            format!("Failed to leave alternate screen: {}", e),
-        )
-    })?;
-    Ok(())
 }
 
-fn main() -> io::Result<()> {
-    let args = Args::parse();
+/// Rows of the street along the bottom of the scene, top to bottom: the sidewalk the
+/// buildings stand on, the far lane, the center line, the near lane and the sidewalk at the
+/// bottom of the screen
+///
+/// With more than two lanes, the rest lie between the far and near lanes, a dashed line
+/// between each pair, and the center line is the middle row of them all.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RoadLayout {
+    far_sidewalk: u16,
+    far_lane: u16,
+    center_line: u16,
+    near_lane: u16,
+    near_sidewalk: u16,
+    lanes: u8,
+}
+
+impl RoadLayout {
+    /// Row of lane `i`, counting from the far side
+    fn lane(&self, i: u8) -> u16 {
+        self.far_lane + 2 * i as u16
+    }
+
+    /// Which way traffic in lane `i` heads: right in the near lane, and each lane further
+    /// out the opposite way to the one before it
+    fn lane_direction(&self, i: u8) -> f32 {
+        if (self.lanes - 1 - i).is_multiple_of(2) {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+
+    /// The lane running along a row, if one does
+    fn lane_at(&self, row: u16) -> Option<u8> {
+        (0..self.lanes).find(|&i| self.lane(i) == row)
+    }
+
+    /// Rows of the dashed lines between neighbouring lanes
+    fn dividers(&self) -> impl Iterator<Item = u16> {
+        (1..self.lanes).map(|i| self.lane(i) - 1)
+    }
+}
+
+/// Represents a tower crane on a construction site between buildings
+struct Crane {
+    x: u16,
+    height: u16,
+    jib_angle: f32, // Rotation of the jib about the mast, in 0.0..TAU
+    hook_y: f32,
+    hook_dir: f32, // +1.0 while lowering the load, -1.0 while raising it
+}
+
+/// Represents a ferris wheel turning on its stand behind the buildings
+struct FerrisWheel {
+    x: u16,
+    angle: f32, // Rotation of the wheel, in 0.0..TAU
+}
+
+/// Represents a boat drifting along the river
+struct Boat {
+    x: f32,
+    speed: f32,
+    glyph: &'static str,
+}
+
+/// A width:height ratio, in cells, to letterbox the scene to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Aspect {
+    width: u16,
+    height: u16,
+}
+
+/// A rectangle of terminal cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+/// What a mouse click at a given row spawns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClickTarget {
+    Road,
+    Sky,
+    Nothing,
+}
+
+/// Scene-wide state an entity may consult while updating or drawing
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SceneCtx {
+    width: u16,
+    height: u16,
+    wind: f32,
+    fog: f32,
+    flash_phase: u32, // Emergency light flashes so far
+    dt: Duration,     // Length of the frame being stepped
+    twinkle_speed: f32,
+    motion_blur: bool,
+    storm: bool,
+    bus_stop: u16, // Column buses stop at
+}
+
+impl SceneCtx {
+    /// How many frames of `NOMINAL_FRAME` this one is worth, to scale per-frame motion by
+    fn steps(&self) -> f32 {
+        self.dt.as_secs_f32() / NOMINAL_FRAME.as_secs_f32()
+    }
+}
+
+/// Spaces redraws out to at most `--max-fps` a second, however often it is asked
+struct RenderPacer {
+    period: Duration,
+    next: Option<Instant>, // Earliest the next frame may be drawn
+}
+
+impl RenderPacer {
+    fn new(max_fps: u32) -> Self {
+        RenderPacer {
+            period: Duration::from_secs(1) / max_fps,
+            next: None,
+        }
+    }
+
+    /// Whether a frame may be drawn at `now`, booking the next slot when it may
+    ///
+    /// Slots follow on from the last deadline so the rate holds steady, unless drawing
+    /// fell a whole period behind, when they start again from now.
+    fn due(&mut self, now: Instant) -> bool {
+        if self.next.is_some_and(|next| now < next) {
+            return false;
+        }
+        self.next = Some(match self.next {
+            Some(next) if now < next + self.period => next + self.period,
+            _ => now + self.period,
+        });
+        true
+    }
+}
+
+/// Meters out scene updates of a fixed `step` from the wall-clock time that passes, so
+/// with `--max-fps` polling input every few milliseconds the scene still moves at the pace
+/// `--interval` sets
+struct StepClock {
+    step: Duration,
+    owed: Duration, // Time passed that hasn't been stepped through yet
+}
+
+impl StepClock {
+    fn new(step: Duration) -> Self {
+        StepClock {
+            step,
+            owed: Duration::ZERO,
+        }
+    }
+
+    /// How many steps are due now that `elapsed` more has passed, carrying the remainder
+    /// over to next time
+    fn steps(&mut self, elapsed: Duration) -> u32 {
+        self.owed += elapsed.min(MAX_FRAME_DT);
+        let steps = (self.owed.as_nanos() / self.step.as_nanos()) as u32;
+        self.owed -= self.step * steps;
+        steps
+    }
+}
+
+/// Toggles a light on and off every `period` of scene time, however it is stepped
+struct BlinkTimer {
+    period: Duration,
+    elapsed: Duration, // Time since the last toggle
+    toggles: u32,
+}
+
+impl BlinkTimer {
+    fn new(period: Duration) -> Self {
+        BlinkTimer {
+            period,
+            elapsed: Duration::ZERO,
+            toggles: 0,
+        }
+    }
+
+    /// Moves the timer on by `dt`, toggling once for every full period that passes
+    fn advance(&mut self, dt: Duration) {
+        self.elapsed += dt;
+        let periods = self.elapsed.as_nanos() / self.period.as_nanos();
+        self.toggles = self.toggles.wrapping_add(periods as u32);
+        self.elapsed -= self.period * periods as u32;
+    }
+}
+
+/// Something living in the scene that moves on its own and draws itself
+///
+/// Entities are updated and drawn in order of `z`, lowest first. Those below `FOREGROUND_Z`
+/// are drawn among the sky layers, hidden behind the buildings; the rest are drawn last,
+/// over everything else.
+trait Entity {
+    fn update(&mut self, ctx: &SceneCtx, rng: &mut dyn RngCore);
+    fn draw(&self, frame: &mut Frame, ctx: &SceneCtx);
+    fn z(&self) -> u8;
+}
+
+/// Something that happened in the scene during an update, worth a sound effect or a log line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SceneEvent {
+    /// A vehicle drove onto the road
+    VehicleSpawned,
+    /// Lightning lit up the sky
+    Lightning,
+    /// A bus pulled in at the stop
+    BusStopped,
+}
+
+/// Holds every entity in the city scene so it can be drawn as a whole
+struct Scene {
+    buildings: Vec<Building>,
+    vehicles: Vec<Vehicle>,
+    stars: Vec<Star>,
+    constellations: Vec<Constellation>,
+    satellite: Option<Satellite>,
+    birds: Vec<Bird>, // The flock crossing the sky, if one is
+    planes: Vec<Plane>,
+    raindrops: Vec<RainDrop>,
+    snowflakes: Vec<Snowflake>,
+    petals: Vec<Petal>,
+    clouds: Vec<Cloud>,
+    balloons: Vec<Balloon>,
+    fog_wisps: Vec<Cloud>,
+    weather_clock: Duration,    // Time into the `--weather-schedule`
+    scheduled: Option<Weather>, // Schedule step last applied
+    rainbow: Rainbow,
+    puddles: Vec<Puddle>,
+    manholes: Vec<u16>, // Columns of the steaming manholes, empty without `--steam`
+    steam: ParticlePool<SteamPuff>,
+    ripples: ParticlePool<Ripple>,
+    road_wetness: f32,
+    road_snow: Vec<f32>, // Snow cover of each road column, from 0.0 (bare) to 1.0
+    crane: Option<Crane>,
+    ferris: Option<FerrisWheel>,
+    aurora: Option<f32>,              // Shimmer phase of the aurora, when enabled
+    sweep: Option<f32>, // Heading of the rotating beacon's beam in radians, with `--beacon`
+    fireworks: Option<Vec<Firework>>, // Shells and bursts in the sky, with `--fireworks`
+    matrix: Option<Vec<MatrixStream>>, // Glyph streams over everything, with `--matrix`
+    drift: Option<f32>,  // Seconds the burn-in drift has run, when enabled
+    river: bool,
+    boats: Vec<Boat>,
+    tumbleweeds: Vec<Tumbleweed>,
+    water_phase: u8,
+    width: u16,
+    height: u16,      // Rows down to the near sidewalk; the ground strip lies below them
+    horizon: u16,     // Requested rows from the bottom of the screen up to the horizon
+    lanes: u8,        // Requested lanes of traffic
+    ground_rows: u16, // Rows of ground below the street lifting it to the horizon
+    weather: WeatherState,
+    rain_intensity: RainIntensity,
+    storm: bool,
+    lightning: u8, // Frames the current lightning flash has left
+    fog: f32,
+    glow: f32,      // Strength of the light pollution above the skyline
+    outlines: bool, // Whether buildings are traced in a darker shade of themselves
+    wind: Wind,
+    time_of_day: f32, // Hours since midnight, in 0.0..24.0
+    tick: u32,        // Frames elapsed, driving blinking lights
+    dt: Duration,     // Length of the last step
+    flash: BlinkTimer,
+    water: BlinkTimer, // Shifts the river's ripples along
+    palette: Palette,
+    motion_blur: bool,
+    ground: Ground,
+    building_layout: BuildingLayout,
+    backdrops: Vec<Vec<Building>>, // Rows of buildings behind the street's, back to front
+    scene_width: Option<u16>,      // Requested width of the city, when set
+    radio_towers: u16,             // Buildings replaced with radio towers
+    city_width: u16,               // Width the buildings are laid out across
+    camera_x: f32,                 // Column of the city at the left edge of the screen
+    show_help: bool,               // Key binding overlay toggled with `?`
+    fast_forward: bool,            // Running several updates per drawn frame, toggled with `>`
+    viewport: Option<Rect>,        // Letterboxed part of the terminal, or all of it when `None`
+    screen: (u16, u16),            // Size of the terminal the scene is shown on
+    twinkle_speed: f32,
+    entities: Vec<Box<dyn Entity>>, // Extra entities beyond the built-in kinds, sorted by `z`
+    vehicles_spawned: u64,          // Vehicles sent onto the road so far, for `--stats-on-exit`
+    args: Rc<Args>,
+    vehicle_styles: Rc<[VehicleStyle]>, // Pool new vehicles are drawn from
+}
+
+impl Scene {
+    /// Builds the scene's scenery and entities for a terminal of the given size, sending out
+    /// vehicles from `vehicle_styles`
+    fn new(
+        args: &Args,
+        vehicle_styles: &[VehicleStyle],
+        width: u16,
+        height: u16,
+        rng: &mut impl Rng,
+    ) -> Scene {
+        let ground_rows = ground_rows(args.horizon, args.lanes, height);
+        let height = height - ground_rows;
+        let palette = match args.theme {
+            Theme::Night => palette_for_background(args.bg.unwrap_or(Background::Dark)),
+            theme => theme.palette(),
+        };
+        let weather = WeatherState::from_args(args);
+        let raining = weather == WeatherState::Rain;
+        let building_layout = BuildingLayout::from_args(args);
+        let city_width = args.scene_width.map_or(width, |w| w.max(width));
+        let mut buildings = create_buildings(
+            city_width,
+            height,
+            &palette.buildings,
+            palette.window_on,
+            &building_layout,
+            rng,
+        );
+        place_radio_towers(&mut buildings, args.radio_towers, height, rng);
+        let crane = args
+            .crane
+            .then(|| create_crane(&buildings, city_width, height, rng));
+        let backdrops = create_backdrops(
+            width,
+            height,
+            &palette,
+            &building_layout,
+            args.depth_layers,
+            rng,
+        );
+        let mut scene = Scene {
+            buildings,
+            vehicles: if args.no_vehicles {
+                Vec::new()
+            } else {
+                create_vehicles(height)
+            },
+            stars: if args.no_stars {
+                Vec::new()
+            } else {
+                create_stars_with_count(width, height, rng, args.stars, args.star_distribution)
+            },
+            constellations: if args.constellations {
+                create_constellations(width, height, rng)
+            } else {
+                Vec::new()
+            },
+            satellite: None,
+            birds: Vec::new(),
+            planes: create_planes(width, height, rng, args.planes),
+            raindrops: if raining {
+                create_raindrops(width, height, rng, args.rain_intensity, args.raindrops)
+            } else {
+                Vec::new()
+            },
+            snowflakes: if weather == WeatherState::Snow {
+                create_snowflakes_with_count(width, height, rng, args.snowflakes)
+            } else {
+                Vec::new()
+            },
+            petals: if args.petals {
+                create_petals(width, height, rng, PETAL_COUNT)
+            } else {
+                Vec::new()
+            },
+            clouds: if args.no_clouds {
+                Vec::new()
+            } else {
+                create_clouds_with_count(width, height, rng, args.clouds, args.cloud_size)
+            },
+            balloons: create_balloons(width, height, rng, args.balloons),
+            fog_wisps: create_fog_wisps(width, height, rng, args.fog),
+            weather_clock: Duration::ZERO,
+            scheduled: None,
+            rainbow: Rainbow {
+                was_raining: raining,
+                alpha: 0.0,
+            },
+            puddles: Vec::new(),
+            road_wetness: if raining { 1.0 } else { 0.0 },
+            road_snow: vec![0.0; width as usize],
+            crane,
+            ferris: args.ferris.then(|| FerrisWheel {
+                x: args.ferris_x.unwrap_or(width / 4),
+                angle: 0.0,
+            }),
+            aurora: args.aurora.then_some(0.0),
+            sweep: args.beacon.then_some(0.0),
+            fireworks: args.fireworks.then(Vec::new),
+            matrix: args
+                .matrix
+                .then(|| create_matrix_streams(width, height + ground_rows, rng)),
+            drift: args.drift.then_some(0.0),
+            manholes: if args.steam {
+                vec![width / 3, width * 2 / 3]
+            } else {
+                Vec::new()
+            },
+            steam: ParticlePool::default(),
+            ripples: ParticlePool::default(),
+            river: args.river,
+            boats: if args.river {
+                create_boats(width, rng)
+            } else {
+                Vec::new()
+            },
+            water_phase: 0,
+            horizon: args.horizon,
+            lanes: args.lanes,
+            ground_rows,
+            tumbleweeds: Vec::new(),
+            width,
+            height,
+            weather,
+            rain_intensity: args.rain_intensity,
+            fog: args.fog,
+            glow: args.glow,
+            outlines: args.building_outlines,
+            wind: Wind {
+                current: 0.0,
+                target: 0.0,
+                change_timer: 0.0,
+            },
+            time_of_day: args.start_hour.rem_euclid(24.0),
+            tick: 0,
+            dt: NOMINAL_FRAME,
+            flash: BlinkTimer::new(FLASH_PERIOD),
+            water: BlinkTimer::new(WATER_PERIOD),
+            palette,
+            motion_blur: args.motion_blur,
+            ground: args.ground,
+            building_layout,
+            backdrops,
+            scene_width: args.scene_width,
+            radio_towers: args.radio_towers,
+            city_width,
+            camera_x: 0.0,
+            show_help: false,
+            fast_forward: false,
+            viewport: None,
+            screen: (width, height + ground_rows),
+            twinkle_speed: args.twinkle_speed,
+            entities: Vec::new(),
+            storm: false,
+            lightning: 0,
+            vehicles_spawned: 0,
+            args: Rc::new(args.clone()),
+            vehicle_styles: vehicle_styles.into(),
+        };
+        if args.storm {
+            scene.set_storm(true, args, rng);
+        }
+        scene
+    }
+
+    /// Sends a new vehicle onto the road, counting it towards the session's total
+    fn add_vehicle(&mut self, vehicle: Vehicle) {
+        self.vehicles.push(vehicle);
+        self.vehicles_spawned += 1;
+    }
+
+    /// Advances the scene by one drawn frame lasting `dt`, or by `--fast-forward-steps`
+    /// such frames while fast-forwarding, returning whether any of them changed it
+    fn advance(&mut self, dt: Duration, rng: &mut impl Rng) -> bool {
+        let steps = if self.fast_forward {
+            self.args.fast_forward_steps
+        } else {
+            1
+        };
+        let mut changed = false;
+        for _ in 0..steps {
+            changed |= self.update(dt, rng);
+        }
+        changed
+    }
+
+    /// Advances everything in the scene by one frame lasting `dt`
+    ///
+    /// Motion tuned per frame is scaled by how `dt` compares with `NOMINAL_FRAME`. Returns
+    /// whether the scene may look any different afterwards; when it doesn't, the last frame
+    /// drawn is still up to date.
+    fn update(&mut self, dt: Duration, rng: &mut impl Rng) -> bool {
+        self.update_with_events(dt, rng, &mut |_| {})
+    }
+
+    /// Like `update`, also telling `on_event` about each thing that happened in the frame,
+    /// for embedders playing sounds or keeping a log
+    fn update_with_events(
+        &mut self,
+        dt: Duration,
+        rng: &mut impl Rng,
+        on_event: &mut dyn FnMut(SceneEvent),
+    ) -> bool {
+        let (args, vehicle_styles) = (Rc::clone(&self.args), Rc::clone(&self.vehicle_styles));
+        let args = &*args;
+        self.dt = dt;
+        let (spawned, lightning) = (self.vehicles_spawned, self.lightning);
+        self.flash.advance(dt);
+        update_beacons(&mut self.buildings, dt);
+        if let Some(WeatherSchedule(schedule)) = &args.weather_schedule {
+            self.weather_clock += dt;
+            let weather = current_weather(schedule, self.weather_clock);
+            if self.scheduled != Some(weather) {
+                self.scheduled = Some(weather);
+                self.follow_schedule(weather, args, rng);
+            }
+        }
+        let steps = dt.as_secs_f32() / NOMINAL_FRAME.as_secs_f32();
+        let dt = dt.as_secs_f32();
+        let spawn_rate = if args.rush_hour {
+            (args.spawn_rate * traffic_multiplier(self.time_of_day)).min(1.0)
+        } else {
+            args.spawn_rate
+        };
+        if !args.no_vehicles
+            && self.vehicles.len() < MAX_VEHICLES
+            && rng.random_bool(spawn_rate as f64)
+        {
+            let road = self.road();
+            let mut vehicle = spawn_vehicle(self.width, &road, &vehicle_styles, rng);
+            if args.vehicle_jitter {
+                vehicle.y = jitter_row(vehicle.y, &road, rng);
+            }
+            self.add_vehicle(vehicle);
+        }
+        let snow_on_road = self.road_snow.iter().any(|&depth| depth > 0.0);
+        if !args.no_vehicles
+            && snow_on_road
+            && self.vehicles.len() < MAX_VEHICLES
+            && !self.vehicles.iter().any(|v| v.is_plow)
+            && rng.random_bool(PLOW_CHANCE)
+        {
+            self.add_vehicle(spawn_plow(self.width, &self.road(), rng));
+        }
+
+        self.tick = self.tick.wrapping_add(1);
+        if self.city_width > self.width {
+            self.camera_x = (self.camera_x + PAN_SPEED * steps) % self.city_width as f32;
+        }
+        self.time_of_day = advance_time_of_day(self.time_of_day, dt, args.day_length);
+        if args.star_drift {
+            let pivot = (self.width as f32 / 2.0, self.horizon_y() as f32);
+            let angle = STAR_DRIFT_SPEED * dt;
+            drift_stars(&mut self.stars, pivot, angle, self.width, self.height, rng);
+        }
+        let windows_changed = update_windows(&mut self.buildings, self.time_of_day, rng);
+        let max_gust = self.max_gust(args);
+        update_wind(&mut self.wind, max_gust, dt, rng);
+        if self.storm && self.lightning == 0 && rng.random_bool(LIGHTNING_CHANCE) {
+            self.lightning = LIGHTNING_FRAMES;
+        } else {
+            self.lightning = self.lightning.saturating_sub(1);
+        }
+        let ctx = self.ctx();
+        let last_x: Vec<f32> = self.vehicles.iter().map(|v| v.x).collect();
+        let stars_before = stars_look(&self.stars);
+        self.update_entities(&ctx, rng);
+        let stars_changed = stars_look(&self.stars) != stars_before;
+        if let Some(intersection_x) = args.intersection_x {
+            self.turn_at_intersection(&last_x, intersection_x, args, &vehicle_styles, rng);
+        }
+        self.vehicles.retain(|v| on_road(v, ctx.width));
+        for plow in self.vehicles.iter().filter(|v| v.is_plow) {
+            plow_road_snow(&mut self.road_snow, plow);
+        }
+        if let Some(tumbleweed) = spawn_tumbleweed(self.wind.current, self.width, &self.road(), rng)
+        {
+            self.tumbleweeds.push(tumbleweed);
+        }
+        for tumbleweed in &mut self.tumbleweeds {
+            update_tumbleweed(tumbleweed, self.wind.current, steps);
+        }
+        let width = self.width as f32;
+        self.tumbleweeds.retain(|t| (-1.0..width).contains(&t.x));
+        if self.birds.is_empty() && args.flock_size > 0 && rng.random_bool(FLOCK_CHANCE) {
+            self.birds = create_flock(self.width, self.height, args.flock_size, rng);
+        }
+        update_flock(&mut self.birds, flock_band(self.height), steps);
+        // The flock flies together, so once the last of it has left it's gone for good
+        let width = self.width as f32;
+        if self
+            .birds
+            .iter()
+            .all(|b| !(-1.0..width + 1.0).contains(&b.x))
+        {
+            self.birds.clear();
+        }
+        if args.satellites {
+            if self.satellite.is_none()
+                && is_night(self.time_of_day)
+                && rng.random_bool(SATELLITE_CHANCE)
+            {
+                self.satellite = Some(spawn_satellite(self.width, self.height, rng));
+            }
+            update_satellite(&mut self.satellite, self.width, self.height, steps);
+        }
+        if let Some(fireworks) = &mut self.fireworks {
+            if fireworks.len() < MAX_FIREWORKS && rng.random_bool(FIREWORK_CHANCE) {
+                fireworks.push(launch_firework(self.width, self.height, rng));
+            }
+            update_fireworks(fireworks, rng);
+        }
+        if let Some(streams) = &mut self.matrix {
+            update_matrix_streams(streams, self.height + self.ground_rows, rng);
+        }
+        update_ripples(&mut self.ripples);
+        if self.weather == WeatherState::Rain {
+            let sources = if args.cloud_rain {
+                rain_sources(&self.clouds, self.width)
+            } else {
+                Vec::new()
+            };
+            let road_y = self.road().near_lane;
+            update_raindrops(
+                &mut self.raindrops,
+                &mut self.ripples,
+                self.width,
+                road_y,
+                self.rain_intensity.slant() as i32 + self.wind.current.round() as i32,
+                &sources,
+                rng,
+            );
+        }
+        if self.weather == WeatherState::Snow {
+            update_snowflakes(
+                &mut self.snowflakes,
+                self.width,
+                self.height,
+                self.wind.current,
+                rng,
+            );
+        }
+        update_petals(
+            &mut self.petals,
+            self.width,
+            self.height,
+            self.wind.current,
+            steps,
+            rng,
+        );
+        if let Some(crane) = &mut self.crane {
+            update_crane(crane, self.height, steps);
+        }
+        if let Some(phase) = &mut self.aurora {
+            *phase = (*phase + AURORA_SPEED * steps) % (std::f32::consts::TAU * 100.0);
+        }
+        if let Some(angle) = &mut self.sweep {
+            *angle = advance_sweep(*angle, dt);
+        }
+        if let Some(elapsed) = &mut self.drift {
+            // Wrap after a full cycle of both axes so the clock never loses precision
+            *elapsed = (*elapsed + dt) % (DRIFT_PERIOD_SECS.0 * DRIFT_PERIOD_SECS.1);
+        }
+        if let Some(wheel) = &mut self.ferris {
+            wheel.angle = (wheel.angle + FERRIS_SPEED * steps) % std::f32::consts::TAU;
+        }
+        let mut water_changed = false;
+        if self.river {
+            update_boats(&mut self.boats, self.width, steps);
+            let shifts = self.water.toggles;
+            self.water.advance(self.dt);
+            if self.water.toggles != shifts {
+                self.water_phase = advance_water_phase(self.water_phase);
+                water_changed = true;
+            }
+        }
+        update_clouds(&mut self.fog_wisps, self.width, self.wind.current, steps);
+        let (raining, snowing) = (
+            self.weather == WeatherState::Rain,
+            self.weather == WeatherState::Snow,
+        );
+        update_rainbow(&mut self.rainbow, raining, dt);
+        self.road_wetness = update_road_wetness(self.road_wetness, raining, dt);
+        update_roof_snow(&mut self.buildings, snowing, dt);
+        update_road_snow(&mut self.road_snow, snowing, dt);
+        update_puddles(&mut self.puddles, raining, self.width, rng);
+        let steam_y = self.road().near_lane;
+        update_street_steam(
+            &mut self.steam,
+            &self.manholes,
+            steam_y,
+            self.wind.current,
+            steps,
+            rng,
+        );
+
+        // What happened shows in how the frame ended up against how it started
+        for _ in spawned..self.vehicles_spawned {
+            on_event(SceneEvent::VehicleSpawned);
+        }
+        if lightning == 0 && self.lightning > 0 {
+            on_event(SceneEvent::Lightning);
+        }
+        for _ in self
+            .vehicles
+            .iter()
+            .filter(|v| v.stop_timer == BUS_STOP_TIME)
+        {
+            on_event(SceneEvent::BusStopped);
+        }
+        windows_changed
+            || stars_changed
+            || water_changed
+            || args.day_length > 0
+            || self.is_animated()
+    }
+
+    /// Whether anything in the scene moves, fades or flickers from one frame to the next
+    ///
+    /// Stars, windows and the river's ripples only change now and then, so `update` checks
+    /// those itself.
+    fn is_animated(&self) -> bool {
+        !self.clouds.is_empty()
+            || !self.planes.is_empty()
+            || !self.balloons.is_empty()
+            || !self.vehicles.is_empty()
+            || !self.entities.is_empty()
+            || !self.boats.is_empty()
+            || !self.tumbleweeds.is_empty()
+            || !self.birds.is_empty()
+            || self.satellite.is_some()
+            || self.weather != WeatherState::Clear
+            || !self.petals.is_empty()
+            || self.crane.is_some()
+            || self.aurora.is_some()
+            || self.sweep.is_some()
+            || self.fireworks.is_some()
+            || self.matrix.is_some()
+            || self.drift.is_some()
+            || self.ferris.is_some()
+            || !self.fog_wisps.is_empty()
+            || self.rainbow.alpha > 0.0
+            || self.road_wetness > 0.0
+            || self.road_snow.iter().any(|&depth| depth > 0.0)
+            || self.buildings.iter().any(|b| b.roof_snow > 0.0)
+            || self.steam.iter().next().is_some()
+            || self.ripples.iter().next().is_some()
+            || self.storm
+            || self.lightning > 0
+            || self.city_width > self.width
+    }
+
+    /// Switches the weather, creating the raindrops or snowflakes the first time they're
+    /// needed and clearing whichever has stopped falling
+    fn set_weather(&mut self, weather: WeatherState, args: &Args, rng: &mut impl Rng) {
+        self.weather = weather;
+        if weather != WeatherState::Rain {
+            self.raindrops.clear();
+        } else if self.raindrops.is_empty() {
+            self.raindrops = create_raindrops(
+                self.width,
+                self.height,
+                rng,
+                self.rain_intensity,
+                args.raindrops,
+            );
+        }
+        if weather != WeatherState::Snow {
+            self.snowflakes.clear();
+        } else if self.snowflakes.is_empty() {
+            self.snowflakes =
+                create_snowflakes_with_count(self.width, self.height, rng, args.snowflakes);
+        }
+    }
+
+    /// Switches to a step of the weather schedule, thickening the fog for a foggy one
+    fn follow_schedule(&mut self, weather: Weather, args: &Args, rng: &mut impl Rng) {
+        self.set_weather(weather.falling(), args, rng);
+        self.fog = if weather == Weather::Fog {
+            args.fog.max(SCHEDULED_FOG)
+        } else {
+            args.fog
+        };
+        self.fog_wisps = create_fog_wisps(self.width, self.height, rng, self.fog);
+    }
+
+    /// Lets vehicles that just crossed the intersection turn off the road, each replaced by
+    /// one turning on from the cross street into the other lane, heading the other way
+    ///
+    /// `last_x` holds where each vehicle was before this frame's move.
+    fn turn_at_intersection(
+        &mut self,
+        last_x: &[f32],
+        intersection_x: u16,
+        args: &Args,
+        vehicle_styles: &[VehicleStyle],
+        rng: &mut impl Rng,
+    ) {
+        let intersection_x = intersection_x as f32;
+        let road = self.road();
+        let mut turned_on = Vec::new();
+        let mut i = 0;
+        self.vehicles.retain(|vehicle| {
+            let turns = turns_at_intersection(
+                vehicle,
+                last_x[i],
+                intersection_x,
+                args.turn_chance as f64,
+                rng,
+            );
+            i += 1;
+            if turns {
+                turned_on.extend(spawn_turning_vehicle(
+                    vehicle,
+                    intersection_x,
+                    &road,
+                    vehicle_styles,
+                    rng,
+                ));
+            }
+            !turns
+        });
+        if args.vehicle_jitter {
+            for vehicle in &mut turned_on {
+                vehicle.y = jitter_row(vehicle.y, &road, rng);
+            }
+        }
+        self.vehicles.extend(turned_on);
+    }
+
+    /// Whips up a storm or lets it pass
+    ///
+    /// A storm darkens the clouds, speeds them up and brings more of them, strengthens the
+    /// wind and sets heavy rain falling. When it passes the clouds slow back down, the extra
+    /// ones leave and the weather and rain intensity return to what `args` asked for.
+    fn set_storm(&mut self, on: bool, args: &Args, rng: &mut impl Rng) {
+        if on == self.storm {
+            return;
+        }
+        self.storm = on;
+        self.lightning = 0;
+        // Fresh drops at the new intensity
+        self.raindrops.clear();
+        if on {
+            for cloud in &mut self.clouds {
+                cloud.speed *= STORM_CLOUD_SPEEDUP;
+            }
+            if !args.no_clouds {
+                let mut extra = create_clouds_with_count(
+                    self.width,
+                    self.height,
+                    rng,
+                    STORM_EXTRA_CLOUDS,
+                    args.cloud_size,
+                );
+                for cloud in &mut extra {
+                    cloud.speed *= STORM_CLOUD_SPEEDUP;
+                }
+                self.clouds.extend(extra);
+            }
+            self.rain_intensity = RainIntensity::Heavy;
+            self.set_weather(WeatherState::Rain, args, rng);
+        } else {
+            if !args.no_clouds {
+                let calm = self
+                    .clouds
+                    .len()
+                    .saturating_sub(STORM_EXTRA_CLOUDS as usize);
+                self.clouds.truncate(calm);
+            }
+            for cloud in &mut self.clouds {
+                cloud.speed /= STORM_CLOUD_SPEEDUP;
+            }
+            self.rain_intensity = args.rain_intensity;
+            self.set_weather(WeatherState::from_args(args), args, rng);
+        }
+    }
+
+    /// Strongest gust the wind may reach, stronger in a storm
+    fn max_gust(&self, args: &Args) -> f32 {
+        if self.storm {
+            args.wind.abs().max(STORM_WIND)
+        } else {
+            args.wind
+        }
+    }
+
+    /// Lays the scene out again for a terminal of a new size
+    ///
+    /// Buildings, and the crane among them, are regenerated for the new width. Sky entities
+    /// keep their height relative to the screen, street-level ones keep their distance from
+    /// the bottom, and everything is clamped back on screen. Only `rng` is drawn from, so a
+    /// seeded generator lays out the same scene every time.
+    fn resize(&mut self, width: u16, height: u16, rng: &mut impl Rng) {
+        self.ground_rows = ground_rows(self.horizon, self.lanes, height);
+        let height = height - self.ground_rows;
+        let old_height = self.height;
+        let scale_y = |y: u16| (y as u32 * height as u32 / old_height as u32) as u16;
+        let street_y = |y: u16| (height - 1).saturating_sub(old_height - 1 - y);
+        let clamp_x = |x: f32| x.clamp(0.0, (width - 1) as f32);
+
+        self.city_width = self.scene_width.map_or(width, |w| w.max(width));
+        self.camera_x = 0.0;
+        self.buildings = create_buildings(
+            self.city_width,
+            height,
+            &self.palette.buildings,
+            self.palette.window_on,
+            &self.building_layout,
+            rng,
+        );
+        place_radio_towers(&mut self.buildings, self.radio_towers, height, rng);
+        if self.crane.is_some() {
+            self.crane = Some(create_crane(&self.buildings, self.city_width, height, rng));
+        }
+        self.backdrops = create_backdrops(
+            width,
+            height,
+            &self.palette,
+            &self.building_layout,
+            self.backdrops.len() as u8 + 1,
+            rng,
+        );
+        if let Some(wheel) = &mut self.ferris {
+            wheel.x = wheel.x.min(width - 1);
+        }
+        if !self.manholes.is_empty() {
+            self.manholes = vec![width / 3, width * 2 / 3];
+        }
+
+        for star in &mut self.stars {
+            star.x = star.x.min(width - 1);
+            star.y = scale_y(star.y);
+            star.exact = (star.x as f32, star.y as f32);
+        }
+        for constellation in &mut self.constellations {
+            for (x, y) in &mut constellation.stars {
+                *x = (*x).min(width - 1);
+                *y = scale_y(*y);
+            }
+        }
+        if let Some(sat) = &mut self.satellite {
+            sat.x = clamp_x(sat.x);
+            sat.y = sat.y.min((height - 1) as f32);
+        }
+        for cloud in self.clouds.iter_mut().chain(&mut self.fog_wisps) {
+            cloud.x = clamp_x(cloud.x);
+            cloud.y = scale_y(cloud.y);
+        }
+        for plane in &mut self.planes {
+            plane.x = clamp_x(plane.x);
+            plane.y = scale_y(plane.y);
+            plane.trail.clear();
+        }
+        for bird in &mut self.birds {
+            bird.y = bird.y * height as f32 / old_height as f32;
+        }
+        for balloon in &mut self.balloons {
+            balloon.x = clamp_x(balloon.x);
+            balloon.y = scale_y(balloon.y);
+        }
+
+        for vehicle in &mut self.vehicles {
+            vehicle.x = clamp_x(vehicle.x);
+            vehicle.y = street_y(vehicle.y);
+        }
+        for tumbleweed in &mut self.tumbleweeds {
+            tumbleweed.x = clamp_x(tumbleweed.x);
+            tumbleweed.y = street_y(tumbleweed.y);
+        }
+        for boat in &mut self.boats {
+            boat.x = clamp_x(boat.x);
+        }
+        self.puddles.retain(|p| p.x < width);
+        self.road_snow.resize(width as usize, 0.0);
+        self.steam.clear();
+        self.ripples.clear();
+
+        for drop in &mut self.raindrops {
+            drop.x = drop.x.min(width - 1);
+            drop.y = scale_y(drop.y);
+        }
+        for flake in &mut self.snowflakes {
+            flake.x = flake.x.min(width - 1);
+            flake.y = scale_y(flake.y);
+        }
+        for petal in &mut self.petals {
+            petal.x = clamp_x(petal.x);
+            petal.y = petal.y.min((height - 1) as f32);
+        }
+        if let Some(streams) = &mut self.matrix {
+            *streams = create_matrix_streams(width, height + self.ground_rows, rng);
+        }
+
+        self.width = width;
+        self.height = height;
+    }
+
+    /// The street's rows, with as many of the requested lanes as the rows below it fit
+    fn road(&self) -> RoadLayout {
+        let fit = DEFAULT_LANES as u16 + self.ground_rows / 2;
+        street_layout(self.height, self.lanes.min(fit as u8))
+    }
+
+    /// Row of the far sidewalk the buildings stand on
+    fn horizon_y(&self) -> u16 {
+        road_layout(self.height).far_sidewalk
+    }
+
+    /// The scene-wide state entities see this frame
+    fn ctx(&self) -> SceneCtx {
+        SceneCtx {
+            width: self.width,
+            height: self.height,
+            wind: self.wind.current,
+            fog: self.fog,
+            flash_phase: self.flash.toggles,
+            dt: self.dt,
+            twinkle_speed: self.twinkle_speed,
+            motion_blur: self.motion_blur,
+            storm: self.storm,
+            bus_stop: bus_stop_x(self.width),
+        }
+    }
+
+    /// Registers an extra entity, after any others of the same `z`, keeping the registry
+    /// sorted so it never needs sorting again
+    #[cfg_attr(
+        not(test),
+        expect(dead_code, reason = "no extras are registered outside tests yet")
+    )]
+    fn add_entity(&mut self, entity: Box<dyn Entity>) {
+        let z = entity.z();
+        let at = self.entities.partition_point(|e| e.z() <= z);
+        self.entities.insert(at, entity);
+    }
+
+    /// Updates every entity in order of `z`: each built-in kind at its own depth, with the
+    /// registered extras slotted in between
+    fn update_entities(&mut self, ctx: &SceneCtx, rng: &mut dyn RngCore) {
+        let mut extras = self.entities.iter_mut().peekable();
+        let mut update = |z: u8, kind: &mut dyn Iterator<Item = &mut dyn Entity>| {
+            while let Some(extra) = extras.next_if(|e| e.z() < z) {
+                extra.update(ctx, rng);
+            }
+            for entity in kind {
+                entity.update(ctx, rng);
+            }
+        };
+        update(CLOUD_Z, &mut self.clouds.iter_mut().map(|e| e as _));
+        update(STAR_Z, &mut self.stars.iter_mut().map(|e| e as _));
+        update(PLANE_Z, &mut self.planes.iter_mut().map(|e| e as _));
+        update(BALLOON_Z, &mut self.balloons.iter_mut().map(|e| e as _));
+        update(VEHICLE_Z, &mut self.vehicles.iter_mut().map(|e| e as _));
+        for extra in extras {
+            extra.update(ctx, rng);
+        }
+    }
+
+    /// Draws the entities with a `z` in `zs`, in the same order `update_entities` goes in
+    fn draw_entities(&self, frame: &mut Frame, ctx: &SceneCtx, zs: impl RangeBounds<u8>) {
+        let mut extras = self
+            .entities
+            .iter()
+            .filter(|e| zs.contains(&e.z()))
+            .peekable();
+        let mut draw = |z: u8, kind: &mut dyn Iterator<Item = &dyn Entity>| {
+            while let Some(extra) = extras.next_if(|e| e.z() < z) {
+                extra.draw(frame, ctx);
+            }
+            if zs.contains(&z) {
+                for entity in kind {
+                    entity.draw(frame, ctx);
+                }
+            }
+        };
+        draw(CLOUD_Z, &mut self.clouds.iter().map(|e| e as _));
+        draw(STAR_Z, &mut self.stars.iter().map(|e| e as _));
+        draw(PLANE_Z, &mut self.planes.iter().map(|e| e as _));
+        draw(BALLOON_Z, &mut self.balloons.iter().map(|e| e as _));
+        draw(VEHICLE_Z, &mut self.vehicles.iter().map(|e| e as _));
+        for extra in extras {
+            extra.draw(frame, ctx);
+        }
+    }
+}
+
+impl Entity for Cloud {
+    fn update(&mut self, ctx: &SceneCtx, _rng: &mut dyn RngCore) {
+        update_clouds(std::slice::from_mut(self), ctx.width, ctx.wind, ctx.steps());
+    }
+
+    fn draw(&self, frame: &mut Frame, ctx: &SceneCtx) {
+        let color = if ctx.storm {
+            STORM_CLOUD_COLOR
+        } else {
+            CLOUD_COLOR
+        };
+        draw_clouds(
+            frame,
+            std::slice::from_ref(self),
+            ctx.width,
+            ctx.height / 4,
+            Layer::Sky,
+            color,
+        );
+    }
+
+    fn z(&self) -> u8 {
+        CLOUD_Z
+    }
+}
+
+impl Entity for Star {
+    fn update(&mut self, ctx: &SceneCtx, mut rng: &mut dyn RngCore) {
+        update_stars(std::slice::from_mut(self), ctx.twinkle_speed, &mut rng);
+    }
+
+    fn draw(&self, frame: &mut Frame, ctx: &SceneCtx) {
+        draw_stars(frame, std::slice::from_ref(self), ctx.fog);
+    }
+
+    fn z(&self) -> u8 {
+        STAR_Z
+    }
+}
+
+impl Entity for Plane {
+    fn update(&mut self, ctx: &SceneCtx, _rng: &mut dyn RngCore) {
+        update_planes(std::slice::from_mut(self), ctx.width, ctx.steps());
+    }
+
+    fn draw(&self, frame: &mut Frame, ctx: &SceneCtx) {
+        draw_planes(frame, std::slice::from_ref(self), ctx.fog);
+    }
+
+    fn z(&self) -> u8 {
+        PLANE_Z
+    }
+}
+
+impl Entity for Balloon {
+    fn update(&mut self, ctx: &SceneCtx, _rng: &mut dyn RngCore) {
+        update_balloons(std::slice::from_mut(self), ctx.width, ctx.steps());
+    }
+
+    fn draw(&self, frame: &mut Frame, ctx: &SceneCtx) {
+        draw_balloon(frame, self, ctx.width);
+    }
+
+    fn z(&self) -> u8 {
+        BALLOON_Z
+    }
+}
+
+impl Entity for Vehicle {
+    fn update(&mut self, ctx: &SceneCtx, _rng: &mut dyn RngCore) {
+        // A waiting bus pulls away at its cruising speed once everyone is aboard
+        if !self.stop_timer.is_zero() {
+            self.stop_timer = self.stop_timer.saturating_sub(ctx.dt);
+            if self.stop_timer.is_zero() {
+                self.speed = self.target_speed;
+            }
+            return;
+        }
+        let steps = ctx.steps();
+        self.speed += (self.target_speed - self.speed) * (VEHICLE_ACCELERATION * steps).min(1.0);
+        let last_x = self.x;
+        self.x += self.speed * 0.1 * steps;
+
+        let stop = ctx.bus_stop as f32;
+        let reached_stop = (last_x < stop && self.x >= stop) || (last_x > stop && self.x <= stop);
+        if self.style == BUS_GLYPH && reached_stop {
+            self.speed = 0.0;
+            self.stop_timer = BUS_STOP_TIME;
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, ctx: &SceneCtx) {
+        draw_vehicle(frame, self, ctx.width, ctx.flash_phase, ctx.motion_blur);
+    }
+
+    fn z(&self) -> u8 {
+        VEHICLE_Z
+    }
+}
+
+/// Totals for one session, printed by `--stats-on-exit`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SessionStats {
+    frames: u64,
+    vehicles_spawned: u64,
+    peak_vehicles: usize,
+    duration: Duration,
+}
+
+/// Summary of a session, one metric per line
+fn format_stats(stats: &SessionStats) -> String {
+    let secs = stats.duration.as_secs_f64();
+    let fps = if secs > 0.0 {
+        stats.frames as f64 / secs
+    } else {
+        0.0
+    };
+    format!(
+        "frames rendered:  {}\n\
+         vehicles spawned: {}\n\
+         peak vehicles:    {}\n\
+         average fps:      {:.1}\n\
+         duration:         {:.1}s",
+        stats.frames, stats.vehicles_spawned, stats.peak_vehicles, fps, secs
+    )
+}
+
+/// Time spent in each stage of a `--bench` run
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BenchReport {
+    frames: u32,
+    update: Duration,
+    render: Duration,
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let total = self.update + self.render;
+        let frames = self.frames.max(1);
+        writeln!(f, "frames:        {}", self.frames)?;
+        writeln!(f, "total:         {:.3?}", total)?;
+        writeln!(
+            f,
+            "update:        {:.3?} ({:.3?}/frame)",
+            self.update,
+            self.update / frames
+        )?;
+        writeln!(
+            f,
+            "render:        {:.3?} ({:.3?}/frame)",
+            self.render,
+            self.render / frames
+        )?;
+        writeln!(f, "average frame: {:.3?}", total / frames)?;
+        write!(
+            f,
+            "frames/sec:    {:.1}",
+            self.frames as f64 / total.as_secs_f64()
+        )
+    }
+}
+
+/// Runs the simulation and renderer for `frames` frames on a fixed-size, fixed-seed scene,
+/// rendering into memory so terminal latency is left out of the timings
+fn run_bench(args: &Args, vehicle_styles: &[VehicleStyle], frames: u32) -> io::Result<BenchReport> {
+    let mut rng = StdRng::seed_from_u64(BENCH_SEED);
+    let mut scene = Scene::new(args, vehicle_styles, BENCH_WIDTH, BENCH_HEIGHT, &mut rng);
+    let dt = Duration::from_millis(args.interval);
+    let mut sink = Vec::new();
+    let mut report = BenchReport {
+        frames,
+        update: Duration::ZERO,
+        render: Duration::ZERO,
+    };
+    for _ in 0..frames {
+        let start = Instant::now();
+        scene.update(dt, &mut rng);
+        let updated = Instant::now();
+        sink.clear();
+        draw_scene(&mut sink, &scene)?;
+        report.update += updated - start;
+        report.render += updated.elapsed();
+    }
+    Ok(report)
+}
+
+/// Runs the screensaver into the `--output` file or pipe rather than the terminal, which is
+/// left alone: no raw mode, no alternate screen and no key presses to stop it
+fn run_to_output(
+    args: &mut Args,
+    vehicle_styles: &[VehicleStyle],
+    path: &Path,
+) -> Result<(), Error> {
+    let mut out = io::BufWriter::new(create_output(path)?);
+    let (width, height) = output_size();
+    let seed = choose_seed(args);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut scene = Scene::new(args, vehicle_styles, width, height, &mut rng);
+    let dt = Duration::from_millis(args.interval);
+    let start = Instant::now();
+    while !should_exit_by_time(start, args.duration) {
+        let frame_start = Instant::now();
+        scene.update(dt, &mut rng);
+        write_frame(&mut out, &scene).map_err(Error::Render)?;
+        let remaining = Duration::from_millis(args.interval).saturating_sub(frame_start.elapsed());
+        std::thread::sleep(remaining);
+    }
+    info(args.quiet, &format!("seed: {}", seed));
+    Ok(())
+}
+
+/// Opens the `--output` file, or the named pipe already there, for writing
+fn create_output(path: &Path) -> Result<fs::File, Error> {
+    fs::File::create(path).map_err(|e| {
+        Error::Config(io::Error::new(
+            e.kind(),
+            format!("Failed to open {}: {}", path.display(), e),
+        ))
+    })
+}
+
+/// Size to render at away from the screen: the terminal's if there is one, at least the
+/// minimum either way
+fn output_size() -> (u16, u16) {
+    let (width, height) = terminal::size().unwrap_or(OUTPUT_SIZE);
+    (width.max(MIN_WIDTH), height.max(MIN_HEIGHT))
+}
+
+/// Takes a `--snapshot` and writes it out, never touching the terminal's modes
+fn run_snapshot(args: &mut Args, vehicle_styles: &[VehicleStyle]) -> Result<(), Error> {
+    let (width, height) = output_size();
+    let seed = choose_seed(args);
+    let frame = snapshot_frame(args, vehicle_styles, width, height, seed);
+    match &args.output {
+        Some(path) => create_output(path)?.write_all(&frame),
+        None => io::stdout().write_all(&frame),
+    }
+    .map_err(Error::Render)?;
+    info(args.quiet, &format!("seed: {}", seed));
+    Ok(())
+}
+
+/// One frame of the city grown from `seed`, after `SNAPSHOT_WARMUP_FRAMES` of simulation
+fn snapshot_frame(
+    args: &Args,
+    vehicle_styles: &[VehicleStyle],
+    width: u16,
+    height: u16,
+    seed: u64,
+) -> Vec<u8> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut scene = Scene::new(args, vehicle_styles, width, height, &mut rng);
+    let dt = Duration::from_millis(args.interval);
+    for _ in 0..SNAPSHOT_WARMUP_FRAMES {
+        scene.update(dt, &mut rng);
+    }
+    let mut frame = Vec::new();
+    draw_scene(&mut frame, &scene).expect("rendering into memory cannot fail");
+    frame
+}
+
+/// Writes one frame of a stream of them, starting it from a clear screen
+fn write_frame(out: &mut impl Write, scene: &Scene) -> io::Result<()> {
+    out.write_all(FRAME_SEPARATOR.as_bytes())?;
+    draw_scene(out, scene)
+}
+
+/// Renders the scene into memory and hashes the bytes written with FNV-1a
+fn frame_hash(scene: &Scene) -> u64 {
+    let mut bytes = Vec::new();
+    draw_scene(&mut bytes, scene).expect("rendering into memory cannot fail");
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Steps the fixed-size, fixed-seed `--bench` scene `frames` times and hashes the frame
+/// it ends on
+fn frame_hash_after(args: &Args, vehicle_styles: &[VehicleStyle], frames: u32) -> u64 {
+    let mut rng = StdRng::seed_from_u64(BENCH_SEED);
+    let mut scene = Scene::new(args, vehicle_styles, BENCH_WIDTH, BENCH_HEIGHT, &mut rng);
+    let dt = Duration::from_millis(args.interval);
+    for _ in 0..frames {
+        scene.update(dt, &mut rng);
+    }
+    frame_hash(&scene)
+}
+
+/// Why the screensaver stopped, sorted by what it was doing at the time
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("couldn't set up the terminal: failed to {action}: {source}")]
+    TerminalSetup {
+        action: &'static str,
+        source: io::Error,
+    },
+    #[error("couldn't restore the terminal: failed to {action}: {source}")]
+    TerminalRestore {
+        action: &'static str,
+        source: io::Error,
+    },
+    #[error("couldn't draw the scene: {0}")]
+    Render(#[source] io::Error),
+    #[error("couldn't read input: {0}")]
+    Input(#[source] io::Error),
+    #[error("invalid configuration: {0}")]
+    Config(#[source] io::Error),
+    #[error(
+        "the terminal is too small: need at least {MIN_WIDTH}x{MIN_HEIGHT}, got {width}x{height}"
+    )]
+    TooSmall { width: u16, height: u16 },
+}
+
+impl Error {
+    /// Wraps an error hit while trying to `action` on the way into screensaver mode
+    fn setup(action: &'static str) -> impl FnOnce(io::Error) -> Error {
+        move |source| Error::TerminalSetup { action, source }
+    }
+
+    /// Wraps an error hit while trying to `action` on the way back out of it
+    fn restore(action: &'static str) -> impl FnOnce(io::Error) -> Error {
+        move |source| Error::TerminalRestore { action, source }
+    }
+}
+
+/// Sets up the terminal for the screensaver by enabling raw mode and switching to alternate screen
+fn setup_terminal(mouse: bool) -> Result<std::io::Stdout, Error> {
+    let mut stdout = stdout();
+    resume_terminal(&mut stdout, mouse)?;
+    Ok(stdout)
+}
+
+/// Asks the terminal for its background color with OSC 11, in raw mode
+///
+/// The query is followed by a request for the terminal's attributes, which every terminal
+/// answers, so the reply is known to be complete once that answer is in, even when OSC 11
+/// goes unanswered. The reply is read straight from stdin on this thread, waiting at most
+/// `BACKGROUND_QUERY_TIMEOUT` in all, so nothing is left reading once the scene starts.
+/// A terminal that answers neither in time is taken to have a dark background.
+#[cfg(unix)]
+fn query_background(out: &mut impl Write) -> Option<Background> {
+    use std::io::IsTerminal;
+    use std::os::fd::AsRawFd;
+
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return None;
+    }
+    out.write_all(b"\x1b]11;?\x1b\\\x1b[c").ok()?;
+    out.flush().ok()?;
+    let fd = stdin.as_raw_fd();
+    let deadline = Instant::now() + BACKGROUND_QUERY_TIMEOUT;
+    let mut reply = Vec::new();
+    while !background_reply_complete(&reply) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let mut ready = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `ready` is a single valid pollfd for the duration of the call
+        if unsafe { libc::poll(&mut ready, 1, left.as_millis() as libc::c_int) } <= 0 {
+            break;
+        }
+        // Read the descriptor directly: std's buffered stdin would hold on to any bytes
+        // past the reply, out of reach of the event reader
+        let mut buf = [0u8; 64];
+        // SAFETY: `buf` is valid for writes of its whole length
+        let read = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+        if read <= 0 {
+            break;
+        }
+        reply.extend_from_slice(&buf[..read as usize]);
+    }
+    parse_background_reply(&reply)
+}
+
+/// Elsewhere there's no reading stdin with a deadline, so the background is taken as dark
+#[cfg(not(unix))]
+fn query_background(_out: &mut impl Write) -> Option<Background> {
+    None
+}
+
+/// Whether the reply to `query_background` is all in: the attributes answer, `ESC [ ? ... c`,
+/// comes last
+fn background_reply_complete(reply: &[u8]) -> bool {
+    reply
+        .windows(3)
+        .position(|w| w == b"\x1b[?")
+        .is_some_and(|start| reply[start..].contains(&b'c'))
+}
+
+/// Restores the terminal to its original state after the screensaver exits
+fn restore_terminal(stdout: &mut std::io::Stdout, mouse: bool) -> Result<(), Error> {
+    terminal::disable_raw_mode().map_err(Error::restore("disable raw mode"))?;
+    leave_screen(stdout, mouse)
+}
+
+/// Puts the terminal back into screensaver mode, whether starting up or resuming after a
+/// suspend
+fn resume_terminal(stdout: &mut impl Write, mouse: bool) -> Result<(), Error> {
+    enter_screen(stdout, mouse)?;
+    terminal::enable_raw_mode().map_err(Error::setup("enable raw mode"))
+}
+
+/// Switches to a cleared alternate screen and hides the cursor, capturing the mouse if asked
+///
+/// Safe to call again on an already set-up terminal, which is what resuming relies on.
+fn enter_screen(out: &mut impl Write, mouse: bool) -> Result<(), Error> {
+    out.execute(EnterAlternateScreen)
+        .map_err(Error::setup("enter the alternate screen"))?;
+    // Some terminals keep whatever was last on the alternate screen
+    out.execute(Clear(ClearType::All))
+        .map_err(Error::setup("clear the screen"))?;
+    out.execute(Hide).map_err(Error::setup("hide the cursor"))?;
+    if mouse {
+        out.execute(EnableMouseCapture)
+            .map_err(Error::setup("enable mouse capture"))?;
+    }
+    Ok(())
+}
+
+/// Undoes `enter_screen`, leaving the shell's screen, cursor, and colors as they were
+fn leave_screen(out: &mut impl Write, mouse: bool) -> Result<(), Error> {
+    if mouse {
+        out.execute(DisableMouseCapture)
+            .map_err(Error::restore("disable mouse capture"))?;
+    }
+    out.execute(style::ResetColor)
+        .map_err(Error::restore("reset colors"))?;
+    out.execute(Show)
+        .map_err(Error::restore("show the cursor"))?;
+    out.execute(LeaveAlternateScreen)
+        .map_err(Error::restore("leave the alternate screen"))?;
+    Ok(())
+}
+
+/// Flags raised by the job-control signals, checked once per frame
+///
+/// Catching `SIGTSTP` stops the kernel from suspending us mid-frame, so the terminal can be
+/// restored to the shell first; `SIGCONT` then re-enters screensaver mode, which also covers
+/// a plain `SIGSTOP` that gave no chance to clean up.
+#[cfg(unix)]
+struct JobControl {
+    suspend: Arc<AtomicBool>,
+    resume: Arc<AtomicBool>,
+}
+
+#[cfg(unix)]
+impl JobControl {
+    fn register() -> Result<Self, Error> {
+        let suspend = Arc::new(AtomicBool::new(false));
+        let resume = Arc::new(AtomicBool::new(false));
+        signal_hook::flag::register(SIGTSTP, Arc::clone(&suspend))
+            .map_err(Error::setup("catch suspend signals"))?;
+        signal_hook::flag::register(SIGCONT, Arc::clone(&resume))
+            .map_err(Error::setup("catch continue signals"))?;
+        Ok(JobControl { suspend, resume })
+    }
+
+    /// Suspends the process if asked to, and re-sets up the terminal once continued
+    fn handle(&self, stdout: &mut impl Write, mouse: bool) -> Result<(), Error> {
+        if self.suspend.swap(false, Ordering::Relaxed) {
+            terminal::disable_raw_mode().map_err(Error::restore("disable raw mode"))?;
+            leave_screen(stdout, mouse)?;
+            signal_hook::low_level::raise(SIGSTOP).map_err(Error::restore("suspend"))?;
+        }
+        if self.resume.swap(false, Ordering::Relaxed) {
+            resume_terminal(stdout, mouse)?;
+        }
+        Ok(())
+    }
+}
+
+/// Maps the row of a mouse click to what it should spawn: vehicles on the road, clouds in
+/// the upper sky
+fn click_target(row: u16, term_height: u16, road: &RoadLayout) -> ClickTarget {
+    if (road.far_lane..=road.near_lane).contains(&row) {
+        ClickTarget::Road
+    } else if row < term_height / 2 {
+        ClickTarget::Sky
+    } else {
+        ClickTarget::Nothing
+    }
+}
+
+/// Whether a run limited to `duration` seconds since `start` is over; 0 means no limit
+fn should_exit_by_time(start: Instant, duration: u64) -> bool {
+    duration > 0 && start.elapsed() >= Duration::from_secs(duration)
+}
+
+/// Checks that the terminal is large enough to lay out the scene
+fn check_min_size(width: u16, height: u16) -> Result<(), Error> {
+    if width < MIN_WIDTH || height < MIN_HEIGHT {
+        return Err(Error::TooSmall { width, height });
+    }
+    Ok(())
+}
+
+/// Clamps a named fraction into 0.0..=1.0, adding a warning to `warnings` when the input
+/// was out of range
+///
+/// Warnings are collected rather than printed so they can go out through `info`, which
+/// `--quiet` silences.
+fn clamp_unit(name: &str, value: f32, default: f32, warnings: &mut Vec<String>) -> f32 {
+    if value.is_nan() {
+        warnings.push(format!(
+            "Warning: {} is not a number, using {}",
+            name, default
+        ));
+        return default;
+    }
+    let clamped = value.clamp(0.0, 1.0);
+    if clamped != value {
+        warnings.push(format!(
+            "Warning: {} {} is out of range, using {}",
+            name, value, clamped
+        ));
+    }
+    clamped
+}
+
+/// Clamps a spawn probability into 0.0..=1.0, warning when the input was out of range
+fn validate_spawn_rate(rate: f32, warnings: &mut Vec<String>) -> f32 {
+    clamp_unit("spawn rate", rate, DEFAULT_SPAWN_RATE, warnings)
+}
+
+/// Clamps a frame interval into `MIN_INTERVAL_MS..=MAX_INTERVAL_MS`, warning when the
+/// input was out of range; a zero interval would poll for input in a busy loop
+fn validate_interval(ms: u64, warnings: &mut Vec<String>) -> u64 {
+    let clamped = ms.clamp(MIN_INTERVAL_MS, MAX_INTERVAL_MS);
+    if clamped != ms {
+        warnings.push(format!(
+            "Warning: interval {}ms is out of range, using {}ms",
+            ms, clamped
+        ));
+    }
+    clamped
+}
+
+/// Keeps building widths wide enough for a window and the range the right way round,
+/// warning about any width that had to change
+fn validate_building_widths(min: u16, max: u16, warnings: &mut Vec<String>) -> (u16, u16) {
+    let clamped_min = min.max(MIN_BUILDING_WIDTH);
+    if clamped_min != min {
+        warnings.push(format!(
+            "Warning: building min width {} is too narrow, using {}",
+            min, clamped_min
+        ));
+    }
+    let clamped_max = max.max(clamped_min);
+    if clamped_max != max {
+        warnings.push(format!(
+            "Warning: building max width {} is below the min width, using {}",
+            max, clamped_max
+        ));
+    }
+    (clamped_min, clamped_max)
+}
+
+/// Parses a `w:h` aspect ratio with both sides above zero
+fn parse_aspect(s: &str) -> Result<Aspect, String> {
+    let invalid = || format!("expected `w:h` with both sides above zero, got `{}`", s);
+    let (width, height) = s.split_once(':').ok_or_else(invalid)?;
+    let width: u16 = width.trim().parse().map_err(|_| invalid())?;
+    let height: u16 = height.trim().parse().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    Ok(Aspect { width, height })
+}
+
+/// Parses a comma-separated `kind:seconds` weather schedule, every step lasting a while
+fn parse_weather_schedule(s: &str) -> Result<WeatherSchedule, String> {
+    s.split(',')
+        .map(|step| {
+            let invalid = || format!("expected `kind:seconds`, got `{}`", step.trim());
+            let (kind, seconds) = step.split_once(':').ok_or_else(invalid)?;
+            let weather = Weather::from_str(kind.trim(), true)
+                .map_err(|_| format!("unknown weather `{}`", kind.trim()))?;
+            let seconds: u64 = seconds.trim().parse().map_err(|_| invalid())?;
+            if seconds == 0 {
+                return Err(format!("`{}` must last at least a second", step.trim()));
+            }
+            Ok((weather, Duration::from_secs(seconds)))
+        })
+        .collect::<Result<_, _>>()
+        .map(WeatherSchedule)
+}
+
+/// The step of the schedule in force `elapsed` into it, going round again after the last
+fn current_weather(schedule: &[(Weather, Duration)], elapsed: Duration) -> Weather {
+    let total: Duration = schedule.iter().map(|(_, length)| *length).sum();
+    let mut into = Duration::from_nanos((elapsed.as_nanos() % total.as_nanos()) as u64);
+    for &(weather, length) in schedule {
+        if into < length {
+            return weather;
+        }
+        into -= length;
+    }
+    schedule[schedule.len() - 1].0
+}
+
+/// Largest rectangle of the given aspect ratio that fits the terminal, centered in it
+fn fit_aspect(term_width: u16, term_height: u16, aspect: Aspect) -> Rect {
+    let (w, h) = (term_width as u32, term_height as u32);
+    let (aw, ah) = (aspect.width as u32, aspect.height as u32);
+    let (width, height) = if w * ah > h * aw {
+        (h * aw / ah, h)
+    } else {
+        (w, w * ah / aw)
+    };
+    Rect {
+        x: ((w - width) / 2) as u16,
+        y: ((h - height) / 2) as u16,
+        width: width as u16,
+        height: height as u16,
+    }
+}
+
+/// Part of the terminal the scene is drawn in: letterboxed to `aspect` when one is given,
+/// unless that would leave less than the smallest size the scene can be laid out in
+fn viewport(aspect: Option<Aspect>, term_width: u16, term_height: u16) -> Option<Rect> {
+    aspect
+        .map(|aspect| fit_aspect(term_width, term_height, aspect))
+        .filter(|rect| check_min_size(rect.width, rect.height).is_ok())
+}
+
+/// Parses a color given by name (e.g. `red`, `dark_grey`) or as `#rrggbb` hex
+fn parse_color(s: &str) -> Option<Color> {
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some(Color::Rgb {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        });
+    }
+    Color::try_from(s.to_lowercase().as_str()).ok()
+}
+
+/// Parses one `glyph,color,speed` line of a vehicles file
+///
+/// The glyph may itself contain commas since the line is split from the right.
+fn parse_vehicle_line(line: &str) -> io::Result<(String, Color, f32)> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let mut fields = line.rsplitn(3, ',');
+    let (Some(speed), Some(color), Some(glyph)) = (fields.next(), fields.next(), fields.next())
+    else {
+        return Err(invalid(format!(
+            "expected `glyph,color,speed`, got `{}`",
+            line
+        )));
+    };
+    if glyph.is_empty() {
+        return Err(invalid("vehicle glyph is empty".to_string()));
+    }
+    let color = parse_color(color.trim())
+        .ok_or_else(|| invalid(format!("unknown color `{}`", color.trim())))?;
+    let speed: f32 = speed
+        .trim()
+        .parse()
+        .map_err(|_| invalid(format!("invalid speed `{}`", speed.trim())))?;
+    if speed == 0.0 || !speed.is_finite() {
+        return Err(invalid(format!("speed must be non-zero, got `{}`", speed)));
+    }
+    Ok((glyph.to_string(), color, speed))
+}
+
+/// Loads the vehicle style pool from a file, skipping blank lines and `#` comments
+fn load_vehicle_styles(path: &Path) -> Result<Vec<VehicleStyle>, Error> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        Error::Config(io::Error::new(
+            e.kind(),
+            format!("Failed to read {}: {}", path.display(), e),
+        ))
+    })?;
+    let mut styles = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let (glyph, color, speed) = parse_vehicle_line(line).map_err(|e| {
+            Error::Config(io::Error::new(
+                e.kind(),
+                format!("{}:{}: {}", path.display(), i + 1, e),
+            ))
+        })?;
+        styles.push((Cow::Owned(glyph), color, speed));
+    }
+    if styles.is_empty() {
+        return Err(Error::Config(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} contains no vehicles", path.display()),
+        )));
+    }
+    Ok(styles)
+}
+
+/// Parses the command line, expanding `--preset` into the flags it stands for
+///
+/// The preset's flags are inserted ahead of the user's own, so with `args_override_self`
+/// anything given explicitly on the command line wins over the preset.
+fn parse_args<I>(argv: I) -> Args
+where
+    I: IntoIterator,
+    I::Item: Into<OsString> + Clone,
+{
+    let argv: Vec<OsString> = argv.into_iter().map(Into::into).collect();
+    let args = Args::parse_from(&argv);
+    let Some(preset) = args.preset else {
+        return args;
+    };
+    let mut expanded = argv[..1].to_vec();
+    expanded.extend(preset.args().iter().map(OsString::from));
+    expanded.extend_from_slice(&argv[1..]);
+    Args::parse_from(expanded)
+}
+
+/// Strips everything that moves out of the settings for `--skyline-only`, leaving the stars
+/// and windows, and slows the frame rate to match
+fn apply_skyline_only(args: &mut Args) {
+    args.no_vehicles = true;
+    args.no_clouds = true;
+    args.rain = false;
+    args.snow = false;
+    args.petals = false;
+    args.storm = false;
+    args.weather_schedule = None;
+    args.wind = 0.0;
+    args.balloons = 0;
+    args.flock_size = 0;
+    args.planes = 0;
+    args.satellites = false;
+    args.fireworks = false;
+    args.matrix = false;
+    args.interval = args.interval.max(SKYLINE_INTERVAL_MS);
+}
+
+/// Prints a non-fatal message to stderr, unless `--quiet` asked for silence
+fn info(quiet: bool, msg: &str) {
+    write_info(&mut io::stderr(), quiet, msg);
+}
+
+fn write_info(out: &mut impl Write, quiet: bool, msg: &str) {
+    if !quiet {
+        // Nowhere left to report a failure to write to stderr
+        let _ = writeln!(out, "{}", msg);
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("city-screensaver: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Parses the command line and runs whichever mode it asks for
+fn run() -> Result<(), Error> {
+    let mut args = parse_args(std::env::args_os());
+    let mut warnings = Vec::new();
+    args.spawn_rate = validate_spawn_rate(args.spawn_rate, &mut warnings);
+    args.turn_chance = clamp_unit(
+        "turn chance",
+        args.turn_chance,
+        DEFAULT_TURN_CHANCE,
+        &mut warnings,
+    );
+    args.interval = validate_interval(args.interval, &mut warnings);
+    if args.slow {
+        args.interval = (args.interval * SLOW_FACTOR).min(MAX_INTERVAL_MS);
+    }
+    if args.skyline_only {
+        apply_skyline_only(&mut args);
+    }
+    if args.high_contrast {
+        args.theme = Theme::HighContrast;
+    }
+    (args.building_min_width, args.building_max_width) = validate_building_widths(
+        args.building_min_width,
+        args.building_max_width,
+        &mut warnings,
+    );
+    args.fog = clamp_unit("fog", args.fog, 0.0, &mut warnings);
+    args.glow = clamp_unit("glow", args.glow, 0.0, &mut warnings);
+    for warning in &warnings {
+        info(args.quiet, warning);
+    }
+    let vehicle_styles = match &args.vehicles_file {
+        Some(path) => load_vehicle_styles(path)?,
+        None => VEHICLE_STYLES.to_vec(),
+    };
+
+    if args.list_presets || args.list_themes {
+        if args.list_presets {
+            print!("{}", value_listing::<Preset>());
+        }
+        if args.list_themes {
+            print!("{}", value_listing::<Theme>());
+        }
+        return Ok(());
+    }
+
+    if let Some(frames) = args.bench {
+        println!(
+            "{}",
+            run_bench(&args, &vehicle_styles, frames).map_err(Error::Render)?
+        );
+        return Ok(());
+    }
+    if let Some(frames) = args.frame_hash {
+        println!("{:016x}", frame_hash_after(&args, &vehicle_styles, frames));
+        return Ok(());
+    }
+    if args.snapshot {
+        return run_snapshot(&mut args, &vehicle_styles);
+    }
+    if let Some(path) = args.output.clone() {
+        return run_to_output(&mut args, &vehicle_styles, &path);
+    }
+
+    let (term_width, term_height) =
+        terminal::size().map_err(Error::setup("get the terminal size"))?;
+    // Bail out before touching terminal modes so a tiny terminal is left untouched
+    check_min_size(term_width, term_height)?;
+    let layout = viewport(args.aspect, term_width, term_height);
+    let (mut width, mut height) = layout.map_or((term_width, term_height), |r| (r.width, r.height));
+
+    let mut stdout = setup_terminal(args.mouse)?;
+    #[cfg(unix)]
+    let job_control = JobControl::register()?;
+    // Only the night theme leaves the terminal's background showing, so only it needs to know
+    if args.bg.is_none() && args.theme == Theme::Night {
+        args.bg = query_background(&mut stdout);
+    }
+
+    // Ensure terminal is restored on panic or exit
+    let seed = choose_seed(&mut args);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut scene = Scene::new(&args, &vehicle_styles, width, height, &mut rng);
+    scene.viewport = layout;
+    scene.screen = (term_width, term_height);
+
+    // FPS tracking
+    let mut frame_count = 0;
+    let mut last_fps_update = Instant::now();
+    let mut fps = 0.0;
+    let start = Instant::now();
+    let mut frames_rendered = 0;
+    let mut peak_vehicles = 0;
+
+    let result = (|| -> Result<(), Error> {
+        let mut running = true;
+        let mut idle = false;
+        let mut pacer = args.max_fps.map(RenderPacer::new);
+        let mut clock = StepClock::new(Duration::from_millis(args.interval));
+        let mut stale = false; // Changed since the last frame drawn
+        // Later frames are only drawn when something changed, so draw the first regardless
+        draw_scene(&mut stdout, &scene).map_err(Error::Render)?;
+        frames_rendered += 1;
+        let mut last_update = Instant::now();
+        while running {
+            let frame_start = Instant::now();
+            if should_exit_by_time(start, args.duration) {
+                break;
+            }
+            #[cfg(unix)]
+            job_control.handle(&mut stdout, args.mouse)?;
+
+            // A still scene only needs waking for input, so wait for it longer
+            let timeout = if idle {
+                IDLE_POLL_MS
+            } else if pacer.is_some() {
+                INPUT_POLL_MS
+            } else {
+                args.interval
+            };
+            let mut input = false;
+            if event::poll(Duration::from_millis(timeout)).map_err(Error::Input)? {
+                input = true;
+                match event::read().map_err(Error::Input)? {
+                    Event::Key(key) => match key.code {
+                        KeyCode::Char('?') => scene.show_help = !scene.show_help,
+                        KeyCode::Char('>') => scene.fast_forward = !scene.fast_forward,
+                        KeyCode::Char('r') => {
+                            scene.set_weather(scene.weather.toggle_rain(), &args, &mut rng)
+                        }
+                        KeyCode::Char('n') => {
+                            scene.set_weather(scene.weather.toggle_snow(), &args, &mut rng)
+                        }
+                        KeyCode::Char('c') => {
+                            scene.set_weather(WeatherState::Clear, &args, &mut rng)
+                        }
+                        KeyCode::Char('s') => scene.set_storm(!scene.storm, &args, &mut rng),
+                        KeyCode::Char(digit @ '1'..='9') => {
+                            let index = digit as usize - '1' as usize;
+                            if index < vehicle_styles.len()
+                                && !args.no_vehicles
+                                && scene.vehicles.len() < MAX_VEHICLES
+                            {
+                                let road = scene.road();
+                                let mut vehicle = spawn_vehicle_style(
+                                    width,
+                                    &road,
+                                    &vehicle_styles,
+                                    index,
+                                    &mut rng,
+                                );
+                                if args.vehicle_jitter {
+                                    vehicle.y = jitter_row(vehicle.y, &road, &mut rng);
+                                }
+                                scene.add_vehicle(vehicle);
+                            }
+                        }
+                        KeyCode::Char('i') => {
+                            scene.rain_intensity = scene.rain_intensity.next();
+                            if scene.weather == WeatherState::Rain {
+                                scene.raindrops = create_raindrops(
+                                    width,
+                                    scene.height,
+                                    &mut rng,
+                                    scene.rain_intensity,
+                                    args.raindrops,
+                                );
+                            }
+                        }
+                        _ => running = false,
+                    },
+                    Event::Mouse(mouse)
+                        if mouse.kind == MouseEventKind::Down(MouseButton::Left) =>
+                    {
+                        // Clicks land in the letterboxed scene, not the whole terminal
+                        let (left, top) = scene.viewport.map_or((0, 0), |r| (r.x, r.y));
+                        let (column, row) = (
+                            mouse.column.saturating_sub(left),
+                            mouse.row.saturating_sub(top),
+                        );
+                        let road = scene.road();
+                        match click_target(row, scene.height, &road) {
+                            ClickTarget::Road
+                                if !args.no_vehicles && scene.vehicles.len() < MAX_VEHICLES =>
+                            {
+                                let mut vehicle =
+                                    spawn_vehicle(width, &road, &vehicle_styles, &mut rng);
+                                vehicle.x = column as f32;
+                                // Dropped into the lane clicked, heading that lane's way
+                                if let Some(lane) = road.lane_at(row) {
+                                    let direction = road.lane_direction(lane);
+                                    vehicle.y = row;
+                                    vehicle.speed = vehicle.speed.abs() * direction;
+                                    vehicle.target_speed = vehicle.target_speed.abs() * direction;
+                                }
+                                scene.add_vehicle(vehicle);
+                            }
+                            ClickTarget::Sky if !args.no_clouds => {
+                                let mut cloud = create_clouds_with_count(
+                                    width,
+                                    scene.height,
+                                    &mut rng,
+                                    1,
+                                    args.cloud_size,
+                                )
+                                .remove(0);
+                                cloud.x = column as f32;
+                                cloud.y = row;
+                                scene.clouds.push(cloud);
+                            }
+                            _ => {}
+                        }
+                    }
+                    // Keep laying out for at least the minimum size when shrunk below it
+                    Event::Resize(w, h) => {
+                        scene.screen = (w, h);
+                        let (w, h) = (w.max(MIN_WIDTH), h.max(MIN_HEIGHT));
+                        scene.viewport = viewport(args.aspect, w, h);
+                        (width, height) = scene.viewport.map_or((w, h), |r| (r.width, r.height));
+                        scene.resize(width, height, &mut rng);
+                    }
+                    _ => {}
+                }
+            }
+
+            // Advance by the time that really passed, which stretches while idle; with a
+            // pacer the loop comes round far more often, so step at the usual interval
+            let now = Instant::now();
+            let dt = (now - last_update).min(MAX_FRAME_DT);
+            last_update = now;
+            if pacer.is_some() {
+                for _ in 0..clock.steps(dt) {
+                    stale |= scene.advance(clock.step, &mut rng);
+                }
+            } else {
+                stale |= scene.advance(dt, &mut rng);
+            }
+            stale |= input;
+            idle = !stale;
+            peak_vehicles = peak_vehicles.max(scene.vehicles.len());
+
+            // Calculate and display FPS
+            frame_count += 1;
+            let elapsed = last_fps_update.elapsed();
+            if elapsed.as_secs() >= 1 {
+                fps = frame_count as f64 / elapsed.as_secs_f64();
+                frame_count = 0;
+                last_fps_update = Instant::now();
+
+                // Display FPS for debugging (only when not in terminal)
+                // In a terminal screensaver, we typically don't show FPS overlay
+            }
+
+            if idle || pacer.as_mut().is_some_and(|p| !p.due(now)) {
+                continue;
+            }
+            draw_scene(&mut stdout, &scene).map_err(Error::Render)?;
+            frames_rendered += 1;
+            stale = false;
+
+            // Calculate frame time for FPS display purposes
+            // A frame that overran its interval moves straight on to the next; with a
+            // pacer, the short input poll spaces the loop out instead
+            let frame_time = frame_start.elapsed();
+            let remaining = Duration::from_millis(args.interval).saturating_sub(frame_time);
+            if pacer.is_none() && !remaining.is_zero() {
+                std::thread::sleep(remaining);
+            }
+        }
+        Ok(())
+    })();
+
+    // Always restore terminal
+    if let Err(e) = restore_terminal(&mut stdout, args.mouse) {
+        info(args.quiet, &format!("Error restoring terminal: {}", e));
+    }
+    // Printed only now so the alternate screen doesn't swallow it
+    if result.is_ok() {
+        info(args.quiet, &format!("seed: {}", seed));
+    }
+    if args.stats_on_exit {
+        let stats = SessionStats {
+            frames: frames_rendered,
+            vehicles_spawned: scene.vehicles_spawned,
+            peak_vehicles,
+            duration: start.elapsed(),
+        };
+        eprintln!("{}", format_stats(&stats));
+    }
+
+    result
+}
+
+/// Seed the session is drawn from: the one given with `--seed`, or a random one that is
+/// stored back in `args` so it can be printed on exit
+fn choose_seed(args: &mut Args) -> u64 {
+    *args.seed.get_or_insert_with(rand::random)
+}
+
+/// Picks a building kind and matching width and height for a terminal of the given height
+///
+/// Mid-rises take any width in the layout's range; houses and towers keep their own
+/// proportions, squeezed into that range.
+fn roll_building_dims(
+    term_height: u16,
+    layout: &BuildingLayout,
+    rng: &mut impl Rng,
+) -> (BuildingKind, u16, u16) {
+    // Leave a couple of rows above the tallest building for its roof or antenna
+    let max_height = road_layout(term_height).far_sidewalk - 2;
+    let fit = |width: u16| width.clamp(layout.min_width, layout.max_width);
+    let roll = rng.random_range(0..10);
+    if roll < 3 {
+        let width = fit(rng.random_range(HOUSE_WIDTHS));
+        let height = rng.random_range(HOUSE_HEIGHTS.start..HOUSE_HEIGHTS.end.min(max_height));
+        (BuildingKind::House, width, height)
+    } else if roll < 5 {
+        let width = fit(rng.random_range(SKYSCRAPER_WIDTHS));
+        let height = rng.random_range((max_height * 2 / 3).max(5)..max_height);
+        (BuildingKind::Skyscraper, width, height)
+    } else {
+        let width = rng.random_range(layout.min_width..=layout.max_width);
+        let height = rng.random_range(5..max_height);
+        (BuildingKind::MidRise, width, height)
+    }
+}
+
+fn create_buildings(
+    term_width: u16,
+    term_height: u16,
+    colors: &[Color],
+    window_on: Color,
+    layout: &BuildingLayout,
+    rng: &mut impl Rng,
+) -> Vec<Building> {
+    let mut buildings = Vec::new();
+    let mut x = 0;
+
+    while x < term_width {
+        let (kind, width, height) = roll_building_dims(term_height, layout, rng);
+        let color = jitter_color(colors[rng.random_range(0..colors.len())], COLOR_JITTER, rng);
+        let window_layout = layout.window_style.layout(width, height);
+        let mut windows = Vec::new();
+
+        for _ in 0..window_layout.rows(height) {
+            let mut row = Vec::new();
+            for _ in 0..window_layout.cols(width) {
+                let on = rng.random_bool(0.3);
+                row.push(Window {
+                    on,
+                    tv: rng.random_bool(TV_WINDOW_CHANCE),
+                    flicker: 1.0,
+                    brightness: if on { 1.0 } else { 0.0 },
+                    lamp: jitter_color(window_on, COLOR_JITTER, rng),
+                });
+            }
+            windows.push(row);
+        }
+
+        // Houses get a roof and skyscrapers a spire instead of an antenna
+        let has_antenna = kind == BuildingKind::MidRise && rng.random_bool(0.3);
+        let antenna_char = if has_antenna {
+            ANTENNA_CHARS[rng.random_range(0..ANTENNA_CHARS.len())]
+        } else {
+            ' '
+        };
+        let beacon = (has_antenna && rng.random_bool(ANTENNA_BEACON_CHANCE))
+            .then(|| BeaconState::new(rng.random_range(Duration::ZERO..BEACON_PERIOD)));
+
+        buildings.push(Building {
+            kind,
+            x,
+            width,
+            height,
+            color,
+            windows,
+            window_layout,
+            has_antenna,
+            antenna_char,
+            roof_snow: 0.0,
+            beacon,
+        });
+        x += width + rng.random_range(layout.gap_max.min(1)..=layout.gap_max);
+    }
+    buildings
+}
+
+/// Turns `count` buildings picked at random into radio towers, as tall as leaves room for
+/// the beacon blinking on top
+fn place_radio_towers(
+    buildings: &mut [Building],
+    count: u16,
+    term_height: u16,
+    rng: &mut impl Rng,
+) {
+    let height = road_layout(term_height)
+        .far_sidewalk
+        .saturating_sub(RADIO_TOWER_CLEARANCE);
+    for i in (0..buildings.len()).choose_multiple(rng, count as usize) {
+        let building = &mut buildings[i];
+        building.kind = BuildingKind::RadioTower;
+        building.width = building.width.min(RADIO_TOWER_WIDTH);
+        building.height = height;
+        building.windows.clear();
+        building.has_antenna = false;
+        building.antenna_char = ' ';
+        building.beacon = Some(BeaconState::new(
+            rng.random_range(Duration::ZERO..BEACON_PERIOD),
+        ));
+    }
+}
+
+/// Rows of buildings standing behind the street's, farthest first, for `layers` rows in all
+///
+/// They stand on the same sidewalk, so the street's buildings hide most of them, but each
+/// row back is darker and its tallest buildings shorter, peeking through the gaps.
+fn create_backdrops(
+    term_width: u16,
+    term_height: u16,
+    palette: &Palette,
+    layout: &BuildingLayout,
+    layers: u8,
+    rng: &mut impl Rng,
+) -> Vec<Vec<Building>> {
+    let black = Color::Rgb { r: 0, g: 0, b: 0 };
+    (1..layers as u16)
+        .rev()
+        .map(|depth| {
+            let dim = |color| lerp_color(color, black, depth as f32 * DEPTH_DIMMING);
+            // Laying a row out for a shorter terminal caps its height
+            let height = term_height
+                .saturating_sub(depth * DEPTH_SHRINK)
+                .max(MIN_HEIGHT);
+            create_buildings(
+                term_width,
+                height,
+                &palette.buildings.map(dim),
+                dim(palette.window_on),
+                layout,
+                rng,
+            )
+        })
+        .collect()
+}
+
+/// Rows of ground left below the street to lift the horizon to `horizon` rows from the
+/// bottom, as many as fit while leaving the minimum height above them
+///
+/// A street of more than two lanes needs two rows more for each extra lane, which come out
+/// of the same rows.
+fn ground_rows(horizon: u16, lanes: u8, term_height: u16) -> u16 {
+    let extra_lanes = (2 * lanes as u16).saturating_sub(2 * DEFAULT_LANES as u16);
+    horizon
+        .saturating_sub(STREET_ROWS)
+        .max(extra_lanes)
+        .min(term_height.saturating_sub(MIN_HEIGHT))
+}
+
+/// Lays the street out along the bottom five rows of the terminal
+fn road_layout(term_height: u16) -> RoadLayout {
+    street_layout(term_height, DEFAULT_LANES)
+}
+
+/// Lays out a street of `lanes` lanes from the far sidewalk of a two-lane street of the
+/// given height, running on down into the rows below it if it needs more than five
+fn street_layout(term_height: u16, lanes: u8) -> RoadLayout {
+    let far_sidewalk = term_height - STREET_ROWS;
+    let far_lane = far_sidewalk + 1;
+    let near_lane = far_lane + 2 * (lanes as u16 - 1);
+    RoadLayout {
+        far_sidewalk,
+        far_lane,
+        center_line: far_sidewalk + lanes as u16,
+        near_lane,
+        near_sidewalk: near_lane + 1,
+        lanes,
+    }
+}
+
+fn create_vehicles(_term_height: u16) -> Vec<Vehicle> {
+    Vec::new()
+}
+
+fn spawn_vehicle(
+    term_width: u16,
+    road: &RoadLayout,
+    styles: &[VehicleStyle],
+    rng: &mut impl Rng,
+) -> Vehicle {
+    spawn_vehicle_style(
+        term_width,
+        road,
+        styles,
+        rng.random_range(0..styles.len()),
+        rng,
+    )
+}
+
+/// Sends out a vehicle of the style at `index` in `styles` from the edge it faces away from,
+/// into a random lane heading its way
+///
+/// When no lane heads that way, as on a one-lane street, it turns round to take any lane.
+fn spawn_vehicle_style(
+    term_width: u16,
+    road: &RoadLayout,
+    styles: &[VehicleStyle],
+    index: usize,
+    rng: &mut impl Rng,
+) -> Vehicle {
+    let (style, color, base_speed) = styles[index].clone();
+    let mut target_speed =
+        base_speed * rng.random_range(1.0 - VEHICLE_SPEED_JITTER..=1.0 + VEHICLE_SPEED_JITTER);
+    let lane = (0..road.lanes)
+        .filter(|&i| road.lane_direction(i) == target_speed.signum())
+        .choose(rng)
+        .unwrap_or_else(|| rng.random_range(0..road.lanes));
+    target_speed = target_speed.abs() * road.lane_direction(lane);
+    let y = road.lane(lane);
+    let x = if target_speed > 0.0 {
+        0.0
+    } else {
+        term_width as f32
+    };
+    let flashing = EMERGENCY_GLYPHS.contains(&style.as_ref());
+
+    Vehicle {
+        x,
+        y,
+        style,
+        color,
+        speed: target_speed * VEHICLE_ENTRY_SPEED,
+        target_speed,
+        flashing,
+        is_plow: false,
+        stop_timer: Duration::ZERO,
+    }
+}
+
+/// A row up to one away from lane row `y` for `--vehicle-jitter`, kept between the outer
+/// lanes so no vehicle ends up driving along a sidewalk
+///
+/// Lanes are two rows apart, so a nudged vehicle straddles the line beside its lane rather
+/// than reaching the next one; it keeps its lane's direction, though `lane_at` no longer
+/// finds a lane on its row.
+fn jitter_row(y: u16, road: &RoadLayout, rng: &mut impl Rng) -> u16 {
+    let jittered = y as i32 + rng.random_range(-1..=1);
+    jittered.clamp(road.far_lane as i32, road.near_lane as i32) as u16
+}
+
+/// Whether a vehicle that moved from `last_x` turns off at the intersection, which it may
+/// only do the frame it crosses it, with probability `chance`
+///
+/// Snowplows keep to their route along the road.
+fn turns_at_intersection(
+    vehicle: &Vehicle,
+    last_x: f32,
+    intersection_x: f32,
+    chance: f64,
+    rng: &mut impl Rng,
+) -> bool {
+    let crossed = (last_x < intersection_x && vehicle.x >= intersection_x)
+        || (last_x > intersection_x && vehicle.x <= intersection_x);
+    crossed && !vehicle.is_plow && rng.random_bool(chance)
+}
+
+/// A vehicle turning onto the road at the intersection to make up for one that turned off,
+/// into a lane heading the other way, or `None` when no lane or style goes that way
+fn spawn_turning_vehicle(
+    turned_off: &Vehicle,
+    intersection_x: f32,
+    road: &RoadLayout,
+    styles: &[VehicleStyle],
+    rng: &mut impl Rng,
+) -> Option<Vehicle> {
+    let oncoming: Vec<&VehicleStyle> = styles
+        .iter()
+        .filter(|(_, _, speed)| speed.signum() == -turned_off.speed.signum())
+        .collect();
+    if oncoming.is_empty() {
+        return None;
+    }
+    let (style, color, base_speed) = oncoming[rng.random_range(0..oncoming.len())].clone();
+    let target_speed =
+        base_speed * rng.random_range(1.0 - VEHICLE_SPEED_JITTER..=1.0 + VEHICLE_SPEED_JITTER);
+    let lane = (0..road.lanes)
+        .filter(|&i| road.lane_direction(i) == -turned_off.speed.signum())
+        .choose(rng)?;
+    let y = road.lane(lane);
+    let flashing = EMERGENCY_GLYPHS.contains(&style.as_ref());
+    Some(Vehicle {
+        x: intersection_x,
+        y,
+        style,
+        color,
+        speed: target_speed * VEHICLE_ENTRY_SPEED,
+        target_speed,
+        flashing,
+        is_plow: false,
+        stop_timer: Duration::ZERO,
+    })
+}
+
+/// Column of the bus stop, three quarters of the way along the road
+fn bus_stop_x(term_width: u16) -> u16 {
+    term_width * 3 / 4
+}
+
+/// Sends a snowplow out from one edge or the other along the near lane, where the snow lies
+fn spawn_plow(term_width: u16, road: &RoadLayout, rng: &mut impl Rng) -> Vehicle {
+    let rightward = rng.random_bool(0.5);
+    let (style, target_speed) = if rightward {
+        (PLOW_GLYPHS[0], PLOW_SPEED)
+    } else {
+        (PLOW_GLYPHS[1], -PLOW_SPEED)
+    };
+    Vehicle {
+        x: if rightward { 0.0 } else { term_width as f32 },
+        y: road.near_lane,
+        style: style.into(),
+        color: PLOW_COLOR,
+        speed: target_speed * VEHICLE_ENTRY_SPEED,
+        target_speed,
+        flashing: false,
+        is_plow: true,
+        stop_timer: Duration::ZERO,
+    }
+}
+
+/// Creates a specified number of stars with random positions and characters
+fn create_stars_with_count(
+    term_width: u16,
+    term_height: u16,
+    rng: &mut impl Rng,
+    count: u16,
+    distribution: StarDistribution,
+) -> Vec<Star> {
+    let mut stars = Vec::new();
+    for _ in 0..count {
+        let phase = rng.random_range(0.0..std::f32::consts::TAU);
+        let (x, y) = (
+            rng.random_range(0..term_width),
+            sample_star_y(distribution, term_height, rng),
+        );
+        stars.push(Star {
+            x,
+            y,
+            char: STAR_CHARS[rng.random_range(0..STAR_CHARS.len())],
+            brightness: star_brightness(phase),
+            phase,
+            exact: (x as f32, y as f32),
+        });
+    }
+    stars
+}
+
+/// Drop count and per-frame fall speed range for a rain intensity
+fn rain_params(intensity: RainIntensity) -> (u16, Range<u16>) {
+    match intensity {
+        RainIntensity::Light => (40, 1..2),
+        RainIntensity::Medium => (100, 1..3),
+        RainIntensity::Heavy => (250, 2..4),
+    }
+}
+
+/// Creates raindrops for an intensity, optionally overriding its drop count
+fn create_raindrops(
+    term_width: u16,
+    term_height: u16,
+    rng: &mut impl Rng,
+    intensity: RainIntensity,
+    count: Option<u16>,
+) -> Vec<RainDrop> {
+    let (default_count, speeds) = rain_params(intensity);
+    create_raindrops_with_count(
+        term_width,
+        term_height,
+        rng,
+        count.unwrap_or(default_count),
+        speeds,
+    )
+}
+
+fn create_raindrops_with_count(
+    term_width: u16,
+    term_height: u16,
+    rng: &mut impl Rng,
+    count: u16,
+    speeds: Range<u16>,
+) -> Vec<RainDrop> {
+    let mut raindrops = Vec::new();
+    for _ in 0..count {
+        raindrops.push(RainDrop {
+            x: rng.random_range(0..term_width),
+            y: rng.random_range(0..term_height),
+            speed: rng.random_range(speeds.clone()),
+        });
+    }
+    raindrops
+}
+
+/// How much busier than usual the road is at a given hour under `--rush-hour`
+///
+/// Traffic swells around each of `RUSH_HOURS` and thins out in the small hours; each
+/// bump is a bell curve over the hours either side, wrapping around midnight.
+fn traffic_multiplier(time_of_day: f32) -> f32 {
+    let bump = |hour: f32, width: f32| {
+        let distance = (time_of_day - hour).rem_euclid(24.0);
+        let distance = distance.min(24.0 - distance);
+        (-(distance / width).powi(2)).exp()
+    };
+    let rush: f32 = RUSH_HOURS.iter().map(|&h| bump(h, RUSH_HOUR_WIDTH)).sum();
+    TRAFFIC_BASE + TRAFFIC_PEAK * rush
+        - TRAFFIC_OVERNIGHT_DIP * bump(OVERNIGHT_HOUR, OVERNIGHT_WIDTH)
+}
+
+/// Advances the clock by `dt` seconds, where a full day lasts `day_length` seconds
+fn advance_time_of_day(time_of_day: f32, dt: f32, day_length: u64) -> f32 {
+    if day_length == 0 {
+        return time_of_day;
+    }
+    (time_of_day + dt * 24.0 / day_length as f32).rem_euclid(24.0)
+}
+
+/// Moves `current` a fraction `rate` of the way toward `target`, snapping once close
+///
+/// With `rate` in 0.0..=1.0 this approaches the target monotonically and never overshoots.
+fn ease(current: f32, target: f32, rate: f32) -> f32 {
+    let next = current + (target - current) * rate.clamp(0.0, 1.0);
+    if (target - next).abs() < 0.01 {
+        target
+    } else {
+        next
+    }
+}
+
+/// Fraction of windows that should be lit at a time of day, peaking in the evening
+fn target_lit_fraction(time_of_day: f32) -> f32 {
+    let tod = time_of_day.rem_euclid(24.0);
+    for pair in LIT_FRACTION_KEYFRAMES.windows(2) {
+        let ((h0, f0), (h1, f1)) = (pair[0], pair[1]);
+        if tod <= h1 {
+            return f0 + (f1 - f0) * (tod - h0) / (h1 - h0);
+        }
+    }
+    LIT_FRACTION_KEYFRAMES[0].1
+}
+
+/// Updates the state of windows in all buildings
+///
+/// Each building nudges its lit-window count toward the fraction expected at this time of
+/// day, with a little random toggling on top for flicker. Returns whether any window
+/// visibly changed: faded on or off, or flickered to another color.
+fn update_windows(buildings: &mut [Building], time_of_day: f32, rng: &mut impl Rng) -> bool {
+    let mut changed = false;
+    let target = target_lit_fraction(time_of_day);
+    for building in buildings {
+        let total = building.windows.iter().map(Vec::len).sum::<usize>();
+        let lit = building.windows.iter().flatten().filter(|w| w.on).count();
+        let wanted = (target * total as f32).round() as usize;
+        if lit != wanted && rng.random_bool(0.1) {
+            // Flip one window that moves the count toward the target
+            let turn_on = lit < wanted;
+            let candidates = building
+                .windows
+                .iter_mut()
+                .flatten()
+                .filter(|w| w.on != turn_on);
+            if let Some(window) = candidates.choose(rng) {
+                window.on = turn_on;
+            }
+        }
+
+        for row in &mut building.windows {
+            for window in row {
+                let shown = window_color(window);
+                if rng.random_bool(0.002) {
+                    window.on = !window.on;
+                }
+                if window.tv {
+                    window.flicker = rng.random_range(0.3..=1.0);
+                }
+                let target = if window.on { 1.0 } else { 0.0 };
+                window.brightness = ease(window.brightness, target, WINDOW_FADE_RATE);
+                changed |= window_color(window) != shown;
+            }
+        }
+    }
+    changed
+}
+
+/// Whether a vehicle is still on the road rather than driven off the far edge
+fn on_road(vehicle: &Vehicle, term_width: u16) -> bool {
+    let vehicle_width = vehicle.style.len() as f32; // Assuming ASCII chars have width 1
+    !((vehicle.speed > 0.0 && vehicle.x > term_width as f32)
+        || (vehicle.speed < 0.0 && vehicle.x < -vehicle_width))
+}
+
+/// Places two of the constellation templates at random spots in the upper sky
+fn create_constellations(
+    term_width: u16,
+    term_height: u16,
+    rng: &mut impl Rng,
+) -> Vec<Constellation> {
+    let mut templates: Vec<usize> = (0..CONSTELLATIONS.len()).collect();
+    let mut constellations = Vec::new();
+    for _ in 0..2 {
+        let (offsets, segments) =
+            CONSTELLATIONS[templates.swap_remove(rng.random_range(0..templates.len()))];
+        let span_x = offsets.iter().map(|o| o.0).max().unwrap_or(0);
+        let span_y = offsets.iter().map(|o| o.1).max().unwrap_or(0);
+        let origin_x = rng.random_range(0..term_width.saturating_sub(span_x).max(1));
+        let origin_y = rng.random_range(0..(term_height / 2).saturating_sub(span_y).max(1));
+        constellations.push(Constellation {
+            stars: offsets
+                .iter()
+                .map(|&(dx, dy)| (origin_x + dx, origin_y + dy))
+                .collect(),
+            segments,
+        });
+    }
+    constellations
+}
+
+/// Cells on the straight line from `a` to `b`, including both endpoints
+fn bresenham(a: (u16, u16), b: (u16, u16)) -> Vec<(u16, u16)> {
+    let (mut x, mut y) = (a.0 as i32, a.1 as i32);
+    let (x1, y1) = (b.0 as i32, b.1 as i32);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut points = Vec::new();
+    loop {
+        points.push((x as u16, y as u16));
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+    points
+}
+
+/// Rows of sky a flock keeps to
+fn flock_band(term_height: u16) -> Range<f32> {
+    1.0..(term_height / 3).max(2) as f32
+}
+
+/// Gathers a flock at the left or right edge, heading across the sky
+fn create_flock(term_width: u16, term_height: u16, size: u16, rng: &mut impl Rng) -> Vec<Bird> {
+    let rightward = rng.random_bool(0.5);
+    let band = flock_band(term_height);
+    let center_y = rng.random_range(band.clone());
+    (0..size)
+        .map(|_| {
+            let x = rng.random_range(0.0..3.0);
+            Bird {
+                x: if rightward {
+                    x
+                } else {
+                    term_width as f32 - 1.0 - x
+                },
+                y: (center_y + rng.random_range(-1.5..1.5)).clamp(band.start, band.end),
+                vx: if rightward { BIRD_SPEED } else { -BIRD_SPEED },
+                vy: rng.random_range(-0.1..0.1),
+            }
+        })
+        .collect()
+}
+
+/// Steers each bird by the boid rules and flies the flock along
+///
+/// A bird drifts toward the center of the neighbors it can see, matches their average
+/// velocity, and backs off from any that come too close, while being pulled back into
+/// `band` if it strays out of it. Every bird is compared with every other, which is cheap
+/// for a flock of a few dozen.
+fn update_flock(birds: &mut [Bird], band: Range<f32>, steps: f32) {
+    let steering: Vec<(f32, f32)> = birds
+        .iter()
+        .enumerate()
+        .map(|(i, bird)| {
+            let (mut ax, mut ay) = (0.0, 0.0);
+            let (mut cx, mut cy, mut vx, mut vy, mut seen) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for (j, other) in birds.iter().enumerate() {
+                let (dx, dy) = (other.x - bird.x, other.y - bird.y);
+                let distance = dx.hypot(dy);
+                if i == j || distance > BIRD_NEIGHBOR_RADIUS {
+                    continue;
+                }
+                (cx, cy, vx, vy, seen) = (
+                    cx + other.x,
+                    cy + other.y,
+                    vx + other.vx,
+                    vy + other.vy,
+                    seen + 1.0,
+                );
+                if distance < BIRD_SEPARATION {
+                    // Push straight away, picking a side for birds on top of each other
+                    let (ux, uy) = if distance > 0.0 {
+                        (dx / distance, dy / distance)
+                    } else {
+                        (if i < j { 1.0 } else { -1.0 }, 0.0)
+                    };
+                    let push = BIRD_AVOIDANCE * (BIRD_SEPARATION - distance);
+                    ax -= ux * push;
+                    ay -= uy * push;
+                }
+            }
+            if seen > 0.0 {
+                ax += (cx / seen - bird.x) * BIRD_COHESION + (vx / seen - bird.vx) * BIRD_ALIGNMENT;
+                ay += (cy / seen - bird.y) * BIRD_COHESION + (vy / seen - bird.vy) * BIRD_ALIGNMENT;
+            }
+            if bird.y < band.start {
+                ay += BIRD_BOUNDS_PULL;
+            } else if bird.y > band.end {
+                ay -= BIRD_BOUNDS_PULL;
+            }
+            (ax, ay)
+        })
+        .collect();
+
+    for (bird, (ax, ay)) in birds.iter_mut().zip(steering) {
+        bird.vx += ax * steps;
+        bird.vy += ay * steps;
+        let speed = bird.vx.hypot(bird.vy);
+        if speed > BIRD_MAX_SPEED {
+            bird.vx *= BIRD_MAX_SPEED / speed;
+            bird.vy *= BIRD_MAX_SPEED / speed;
+        }
+        bird.x += bird.vx * steps;
+        bird.y += bird.vy * steps;
+    }
+}
+
+/// Draws the flock, each bird beating its wings a little out of step with the others
+fn draw_birds(frame: &mut Frame, birds: &[Bird], tick: u32, term_width: u16, fog: f32) {
+    let color = apply_fog(BIRD_COLOR, fog);
+    for (i, bird) in birds.iter().enumerate() {
+        if bird.x < 0.0 || bird.x >= term_width as f32 || bird.y < 0.0 {
+            continue;
+        }
+        let beat = (tick / BIRD_FLAP_FRAMES + i as u32) as usize % BIRD_GLYPHS.len();
+        frame.put(
+            Layer::Sky,
+            bird.x as u16,
+            bird.y as u16,
+            BIRD_GLYPHS[beat],
+            color,
+        );
+    }
+}
+
+/// Starts a satellite pass from the left or right edge of the upper sky
+fn spawn_satellite(term_width: u16, term_height: u16, rng: &mut impl Rng) -> Satellite {
+    let rightward = rng.random_bool(0.5);
+    Satellite {
+        x: if rightward {
+            0.0
+        } else {
+            (term_width - 1) as f32
+        },
+        y: rng.random_range(0..term_height / 4) as f32,
+        vx: if rightward {
+            SATELLITE_SPEED
+        } else {
+            -SATELLITE_SPEED
+        },
+        vy: rng.random_range(0.0..0.03),
+    }
+}
+
+/// Moves the satellite along its velocity, removing it once it leaves the sky
+fn update_satellite(
+    satellite: &mut Option<Satellite>,
+    term_width: u16,
+    term_height: u16,
+    steps: f32,
+) {
+    if let Some(sat) = satellite {
+        sat.x += sat.vx * steps;
+        sat.y += sat.vy * steps;
+        if sat.x < 0.0
+            || sat.x >= term_width as f32
+            || sat.y < 0.0
+            || sat.y >= (term_height / 2) as f32
+        {
+            *satellite = None;
+        }
+    }
+}
+
+/// Sends a shell up from the skyline, fast enough to burst somewhere in the upper third of
+/// the sky
+fn launch_firework(term_width: u16, term_height: u16, rng: &mut impl Rng) -> Firework {
+    let launch_y = road_layout(term_height).far_sidewalk as f32;
+    let apex_y = rng.random_range(1.0..(term_height / 3).max(2) as f32);
+    Firework {
+        x: rng.random_range(0..term_width) as f32,
+        y: launch_y,
+        vy: -(2.0 * FIREWORK_GRAVITY * (launch_y - apex_y)).sqrt(),
+        state: FireworkState::Rising,
+        color: FIREWORK_COLORS[rng.random_range(0..FIREWORK_COLORS.len())],
+        sparks: Vec::new(),
+    }
+}
+
+/// Sparks thrown out evenly all round from a burst at `(x, y)`, each direction nudged a
+/// little so bursts don't look stamped out
+///
+/// Rows are about twice the height of columns, so sparks move half as fast up and down.
+fn burst_sparks(x: f32, y: f32, count: usize, rng: &mut impl Rng) -> Vec<Spark> {
+    let step = std::f32::consts::TAU / count as f32;
+    (0..count)
+        .map(|i| {
+            let angle = i as f32 * step + rng.random_range(-step / 4.0..step / 4.0);
+            let speed = rng.random_range(FIREWORK_SPARK_SPEED);
+            Spark {
+                x,
+                y,
+                vx: angle.cos() * speed,
+                vy: angle.sin() * speed / 2.0,
+                life: FIREWORK_SPARK_LIFE,
+            }
+        })
+        .collect()
+}
+
+/// Climbs shells until they slow to a stop and burst, lets sparks fall and burn out, and
+/// removes fireworks with nothing left alight
+fn update_fireworks(fireworks: &mut Vec<Firework>, rng: &mut impl Rng) {
+    for firework in fireworks.iter_mut() {
+        match firework.state {
+            FireworkState::Rising => {
+                firework.y += firework.vy;
+                firework.vy += FIREWORK_GRAVITY;
+                if firework.vy >= 0.0 {
+                    firework.state = FireworkState::Bursting;
+                    firework.sparks = burst_sparks(firework.x, firework.y, FIREWORK_SPARKS, rng);
+                }
+            }
+            FireworkState::Bursting => {
+                for spark in &mut firework.sparks {
+                    spark.x += spark.vx;
+                    spark.y += spark.vy;
+                    spark.vy += FIREWORK_GRAVITY / 2.0;
+                    spark.life -= 1;
+                }
+                firework.sparks.retain(|spark| spark.life > 0);
+            }
+        }
+    }
+    fireworks.retain(|f| f.state == FireworkState::Rising || !f.sparks.is_empty());
+}
+
+fn create_planes(term_width: u16, term_height: u16, rng: &mut impl Rng, count: u16) -> Vec<Plane> {
+    (0..count)
+        .map(|_| {
+            let speed = rng.random_range(PLANE_SPEEDS);
+            Plane {
+                x: rng.random_range(0.0..term_width as f32),
+                y: rng.random_range(0..(term_height / 6).max(1)),
+                speed: if rng.random_bool(0.5) { speed } else { -speed },
+                blink: 0.0,
+                trail: ParticlePool::default(),
+            }
+        })
+        .collect()
+}
+
+/// Flies planes along, laying contrail behind each and letting its oldest cells dissipate
+///
+/// A plane leaving the screen reappears at the other edge, its old contrail fading out.
+fn update_planes(planes: &mut [Plane], term_width: u16, steps: f32) {
+    for plane in planes {
+        let tail = plane_tail_x(plane);
+        plane.x += plane.speed * steps;
+        plane.blink = (plane.blink + steps) % 256.0;
+        let glyph_width = PLANE_GLYPH_WIDTH as f32;
+        if plane.x > term_width as f32 {
+            plane.x = -glyph_width;
+        } else if plane.x < -glyph_width {
+            plane.x = term_width as f32;
+        }
+
+        for cell in plane.trail.iter_mut() {
+            cell.age += 1;
+        }
+        plane.trail.retain(|c| (c.age as usize) < CONTRAIL_MAX_LEN);
+        let y = plane.y;
+        if (0..term_width as i32).contains(&tail)
+            && !plane.trail.iter().any(|c| c.x == tail as u16 && c.y == y)
+        {
+            plane.trail.spawn(ContrailCell {
+                x: tail as u16,
+                y,
+                age: 0,
+            });
+        }
+    }
+}
+
+/// Column just behind a plane, where its contrail starts
+fn plane_tail_x(plane: &Plane) -> i32 {
+    if plane.speed > 0.0 {
+        plane.x as i32 - 1
+    } else {
+        plane.x as i32 + PLANE_GLYPH_WIDTH as i32
+    }
+}
+
+/// Color of the wing nav light, alternating red and green every few frames
+fn nav_light_color(blink: u8) -> Color {
+    if (blink / PLANE_BLINK_FRAMES).is_multiple_of(2) {
+        Color::Red
+    } else {
+        Color::Green
+    }
+}
+
+fn update_stars(stars: &mut [Star], twinkle_speed: f32, rng: &mut impl Rng) {
+    for star in stars {
+        star.phase = (star.phase + twinkle_speed).rem_euclid(std::f32::consts::TAU);
+        star.brightness = star_brightness(star.phase);
+        if rng.random_bool(0.05) {
+            star.char = STAR_CHARS[rng.random_range(0..STAR_CHARS.len())];
+        }
+    }
+}
+
+/// Row in the top half of the screen for a new star, spread as `distribution` asks
+///
+/// The weighted spreads square a uniform draw, so stars bunch toward one end of the sky
+/// while still reaching the other.
+fn sample_star_y(distribution: StarDistribution, term_height: u16, rng: &mut impl Rng) -> u16 {
+    let sky = term_height / 2;
+    let squared = |rng: &mut dyn RngCore| rng.random::<f32>().powi(2);
+    let y = match distribution {
+        StarDistribution::Uniform => return rng.random_range(0..sky),
+        StarDistribution::Zenith => squared(rng) * sky as f32,
+        StarDistribution::Horizon => (1.0 - squared(rng)) * sky as f32,
+    };
+    (y as u16).min(sky - 1)
+}
+
+/// Turns the stars `angle` radians anticlockwise about `pivot`, so those in the east rise
+///
+/// Cells are about twice as tall as they are wide, so rows are doubled first to keep the
+/// circles round. A star carried out of the top half of the screen is replaced by a new one.
+fn drift_stars(
+    stars: &mut [Star],
+    pivot: (f32, f32),
+    angle: f32,
+    term_width: u16,
+    term_height: u16,
+    rng: &mut impl Rng,
+) {
+    let (sin, cos) = angle.sin_cos();
+    let sky_height = term_height / 2;
+    for star in stars {
+        let (dx, dy) = (star.exact.0 - pivot.0, (star.exact.1 - pivot.1) * 2.0);
+        let (x, y) = (
+            pivot.0 + dx * cos + dy * sin,
+            pivot.1 + (dy * cos - dx * sin) / 2.0,
+        );
+        if x < 0.0 || y < 0.0 || x >= term_width as f32 || y >= sky_height as f32 {
+            let (x, y) = (
+                rng.random_range(0..term_width),
+                rng.random_range(0..sky_height),
+            );
+            (star.x, star.y, star.exact) = (x, y, (x as f32, y as f32));
+        } else {
+            star.exact = (x, y);
+            (star.x, star.y) = (x as u16, y as u16);
+        }
+    }
+}
+
+/// Brightness of a star at a point in its twinkle cycle, in 0.0..=1.0
+fn star_brightness(phase: f32) -> f32 {
+    ((phase.sin() + 1.0) / 2.0).clamp(0.0, 1.0)
+}
+
+/// Hash of where each star is and the glyph and color it is drawn with, to tell whether an
+/// update changed how any of them look
+fn stars_look(stars: &[Star]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for star in stars {
+        (star.x, star.y, star.char, star_color(star.brightness)).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Blends a star from dim gray to full white by its brightness
+fn star_color(brightness: f32) -> Color {
+    lerp_color(STAR_DIM_COLOR, STAR_COLOR, brightness)
+}
+
+/// Where rain can fall from under `--cloud-rain`: the columns beneath each cloud on screen,
+/// and the row just below it
+fn rain_sources(clouds: &[Cloud], term_width: u16) -> Vec<(Range<u16>, u16)> {
+    clouds
+        .iter()
+        .filter_map(|cloud| {
+            let left = cloud.x.max(0.0) as u16;
+            let right = (cloud.x + cloud_width(cloud.shape) as f32).clamp(0.0, term_width as f32);
+            let columns = left..right as u16;
+            (!columns.is_empty()).then_some((columns, cloud.y + cloud.shape.len() as u16))
+        })
+        .collect()
+}
+
+/// Drops fall and drift `drift` columns a frame; one that reaches the near lane at `road_y`
+/// splashes there and starts again, beneath one of the `sources` if there are any, or anywhere
+/// along the top if not
+fn update_raindrops(
+    raindrops: &mut [RainDrop],
+    ripples: &mut ParticlePool<Ripple>,
+    term_width: u16,
+    road_y: u16,
+    drift: i32,
+    sources: &[(Range<u16>, u16)],
+    rng: &mut impl Rng,
+) {
+    for drop in raindrops {
+        drop.y += drop.speed;
+        drop.x = (drop.x as i32 + drift).rem_euclid(term_width as i32) as u16;
+        if drop.y >= road_y {
+            ripples.spawn(Ripple {
+                x: drop.x,
+                y: road_y,
+                age: 0,
+            });
+            let source =
+                (!sources.is_empty()).then(|| &sources[rng.random_range(0..sources.len())]);
+            match source {
+                Some((columns, below)) => {
+                    drop.x = rng.random_range(columns.clone());
+                    drop.y = (*below).min(road_y - 1);
+                }
+                None => {
+                    drop.x = rng.random_range(0..term_width);
+                    drop.y = 0;
+                }
+            }
+        }
+    }
+}
+
+/// How far a splash has spread, from 0 for a speck up to its widest ring
+fn ripple_radius(ripple: &Ripple) -> usize {
+    (ripple.age / RIPPLE_STAGE_FRAMES) as usize
+}
+
+/// Spreads each splash, removing it once it has spread past its widest ring
+fn update_ripples(ripples: &mut ParticlePool<Ripple>) {
+    for ripple in ripples.iter_mut() {
+        ripple.age += 1;
+    }
+    ripples.retain(|ripple| ripple_radius(ripple) < RIPPLE_GLYPHS.len());
+}
+
+fn create_petals(term_width: u16, term_height: u16, rng: &mut impl Rng, count: u16) -> Vec<Petal> {
+    (0..count)
+        .map(|_| Petal {
+            x: rng.random_range(0.0..term_width as f32),
+            y: rng.random_range(0.0..term_height as f32),
+            fall_speed: rng.random_range(0.1..0.3),
+            phase: rng.random_range(0.0..std::f32::consts::TAU),
+            char: PETAL_CHARS[rng.random_range(0..PETAL_CHARS.len())],
+            color: PETAL_COLORS[rng.random_range(0..PETAL_COLORS.len())],
+        })
+        .collect()
+}
+
+/// Moves petals along a two-axis swirl: a side-to-side sway, and a fall that slows and
+/// quickens at twice the rate, so they flutter rather than drop
+fn update_petals(
+    petals: &mut [Petal],
+    term_width: u16,
+    term_height: u16,
+    wind: f32,
+    steps: f32,
+    rng: &mut impl Rng,
+) {
+    for petal in petals {
+        petal.phase = (petal.phase + PETAL_SWIRL_SPEED * steps) % std::f32::consts::TAU;
+        let sway = petal.phase.cos() * PETAL_SWAY + wind * 0.5;
+        petal.x = (petal.x + sway * steps).rem_euclid(term_width as f32);
+        petal.y += petal.fall_speed * (1.0 + 0.5 * (2.0 * petal.phase).sin()) * steps;
+        if petal.y >= term_height as f32 {
+            petal.y = 0.0;
+            petal.x = rng.random_range(0.0..term_width as f32);
+        }
+    }
+}
+
+fn create_snowflakes_with_count(
+    term_width: u16,
+    term_height: u16,
+    rng: &mut impl Rng,
+    count: u16,
+) -> Vec<Snowflake> {
+    let mut snowflakes = Vec::new();
+    for _ in 0..count {
+        snowflakes.push(Snowflake {
+            x: rng.random_range(0..term_width),
+            y: rng.random_range(0..term_height),
+            speed_y: rng.random_range(1..2),
+            speed_x: rng.random_range(-1..2),
+            char: SNOWFLAKE_CHARS[rng.random_range(0..SNOWFLAKE_CHARS.len())],
+        });
+    }
+    snowflakes
+}
+
+/// A stream for every column, each starting somewhere above the screen so they arrive
+/// staggered
+fn create_matrix_streams(
+    term_width: u16,
+    term_height: u16,
+    rng: &mut impl Rng,
+) -> Vec<MatrixStream> {
+    (0..term_width)
+        .map(|x| spawn_matrix_stream(x, term_height, rng))
+        .collect()
+}
+
+fn spawn_matrix_stream(x: u16, term_height: u16, rng: &mut impl Rng) -> MatrixStream {
+    MatrixStream {
+        x,
+        head: -rng.random_range(0.0..term_height as f32),
+        speed: rng.random_range(MATRIX_SPEEDS),
+        trail: rng.random_range(MATRIX_TRAILS),
+        glyphs: (0..term_height).map(|_| matrix_glyph(rng)).collect(),
+    }
+}
+
+fn matrix_glyph(rng: &mut impl Rng) -> char {
+    MATRIX_GLYPHS.chars().choose(rng).unwrap_or('0')
+}
+
+/// Moves each stream down, flickering one of its glyphs, and starts a new one above the
+/// screen once its whole trail has run off the bottom
+fn update_matrix_streams(streams: &mut [MatrixStream], term_height: u16, rng: &mut impl Rng) {
+    for stream in streams {
+        stream.head += stream.speed;
+        if stream.head - stream.trail as f32 >= term_height as f32 {
+            *stream = spawn_matrix_stream(stream.x, term_height, rng);
+        }
+        if !stream.glyphs.is_empty() {
+            let row = rng.random_range(0..stream.glyphs.len());
+            stream.glyphs[row] = matrix_glyph(rng);
+        }
+    }
+}
+
+/// Rows of a stream on screen with their brightness, from 1.0 at the head fading down
+/// the trail behind it
+fn matrix_trail(stream: &MatrixStream, term_height: u16) -> Vec<(u16, f32)> {
+    let head = stream.head.floor() as i32;
+    (0..=stream.trail)
+        .filter_map(|i| {
+            let y = head - i as i32;
+            let brightness = 1.0 - i as f32 / (stream.trail + 1) as f32;
+            (0..term_height as i32)
+                .contains(&y)
+                .then_some((y as u16, brightness))
+        })
+        .collect()
+}
+
+fn update_snowflakes(
+    snowflakes: &mut [Snowflake],
+    term_width: u16,
+    term_height: u16,
+    wind: f32,
+    rng: &mut impl Rng,
+) {
+    for flake in snowflakes {
+        flake.y += flake.speed_y;
+        if flake.y >= term_height {
+            flake.y = 0;
+            flake.x = rng.random_range(0..term_width);
+        }
+
+        let drift = flake.speed_x as i32 + wind.round() as i32;
+        flake.x = (flake.x as i32 + drift).rem_euclid(term_width as i32) as u16;
+    }
+}
+
+fn create_clouds_with_count(
+    term_width: u16,
+    term_height: u16,
+    rng: &mut impl Rng,
+    count: u16,
+    size: CloudSize,
+) -> Vec<Cloud> {
+    let sprites = size.sprites();
+    let mut clouds: Vec<Cloud> = Vec::new();
+    for _ in 0..count {
+        let shape = sprites[rng.random_range(0..sprites.len())];
+        let mut spot = (0.0, 0);
+        for _ in 0..CLOUD_PLACEMENT_TRIES {
+            // Bands split the upper quarter of the screen evenly
+            let band = rng.random_range(0..CLOUD_BANDS);
+            spot = (
+                rng.random_range(0..term_width) as f32,
+                band * (term_height / 4) / CLOUD_BANDS,
+            );
+            let crowded = clouds
+                .iter()
+                .any(|other| other.y == spot.1 && clouds_overlap(other, spot.0, shape));
+            if !crowded {
+                break;
+            }
+        }
+        clouds.push(Cloud {
+            x: spot.0,
+            y: spot.1,
+            shape,
+            speed: rng.random_range(0.5..1.5),
+            phase: rng.random_range(0.0..std::f32::consts::TAU),
+        });
+    }
+    clouds
+}
+
+/// Whether a cloud of `shape` at column `x` would overlap `other` side to side
+fn clouds_overlap(other: &Cloud, x: f32, shape: &[&str]) -> bool {
+    x < other.x + cloud_width(other.shape) as f32 && other.x < x + cloud_width(shape) as f32
+}
+
+/// Width of a cloud sprite in columns, taken from its widest row
+fn cloud_width(shape: &[&str]) -> u16 {
+    shape
+        .iter()
+        .map(|row| display_width(row))
+        .max()
+        .unwrap_or(0)
+}
+
+fn update_clouds(clouds: &mut [Cloud], term_width: u16, wind: f32, steps: f32) {
+    for cloud in clouds {
+        cloud.x += (cloud.speed + wind * CLOUD_WIND_FACTOR) * 0.1 * steps;
+        cloud.phase = (cloud.phase + CLOUD_BOB_SPEED * steps) % std::f32::consts::TAU;
+        let width = cloud_width(cloud.shape) as f32;
+        if cloud.x > term_width as f32 {
+            cloud.x = -width; // Wrap around
+        } else if cloud.x < -width {
+            cloud.x = term_width as f32; // A strong headwind blows clouds back
+        }
+    }
+}
+
+/// How far the burn-in drift has moved the picture after `elapsed` seconds, in columns and
+/// rows, never more than `DRIFT_RANGE` either way
+fn drift_offset(elapsed: f32) -> (i16, i16) {
+    let swing = |range: f32, period: f32| {
+        (range * (elapsed / period * std::f32::consts::TAU).sin()).round() as i16
+    };
+    (
+        swing(DRIFT_RANGE.0, DRIFT_PERIOD_SECS.0),
+        swing(DRIFT_RANGE.1, DRIFT_PERIOD_SECS.1),
+    )
+}
+
+/// Eases the wind toward its current gust, picking a new gust at random intervals
+///
+/// The wind never changes by more than `WIND_MAX_DELTA` in a frame, so gusts build up and die
+/// down rather than snapping.
+fn update_wind(wind: &mut Wind, max_gust: f32, dt: f32, rng: &mut impl Rng) {
+    wind.change_timer -= dt;
+    if wind.change_timer <= 0.0 {
+        let max_gust = max_gust.abs();
+        wind.target = rng.random_range(-max_gust..=max_gust);
+        wind.change_timer = rng.random_range(GUST_INTERVAL_SECS);
+    }
+    let eased = ease(wind.current, wind.target, WIND_EASE_RATE);
+    wind.current += (eased - wind.current).clamp(-WIND_MAX_DELTA, WIND_MAX_DELTA);
+}
+
+/// Starts the rainbow when rain stops and fades it out over `RAINBOW_FADE_SECS`
+fn update_rainbow(rainbow: &mut Rainbow, raining: bool, dt: f32) {
+    if rainbow.was_raining && !raining {
+        rainbow.alpha = 1.0;
+    } else if raining {
+        rainbow.alpha = 0.0;
+    } else {
+        rainbow.alpha = (rainbow.alpha - dt / RAINBOW_FADE_SECS).max(0.0);
+    }
+    rainbow.was_raining = raining;
+}
+
+/// Rises, drifts and fades the steam puffs, then lets new ones off the manholes
+///
+/// Puffs are let off after the others move, so a new puff starts right on its manhole's
+/// row at `manhole_y`.
+fn update_street_steam(
+    puffs: &mut ParticlePool<SteamPuff>,
+    manholes: &[u16],
+    manhole_y: u16,
+    wind: f32,
+    steps: f32,
+    rng: &mut impl Rng,
+) {
+    for puff in puffs.iter_mut() {
+        puff.y -= STEAM_RISE * steps;
+        puff.x += (rng.random_range(-STEAM_DRIFT..=STEAM_DRIFT) + wind * STEAM_DRIFT) * steps;
+        puff.life -= STEAM_FADE * steps;
+    }
+    puffs.retain(|p| p.life > 0.0 && p.y >= 0.0);
+
+    for &x in manholes {
+        if rng.random_bool(STEAM_EMIT_CHANCE) {
+            puffs.spawn(SteamPuff {
+                x: x as f32,
+                y: manhole_y as f32,
+                life: 1.0,
+            });
+        }
+    }
+}
+
+/// Piles snow on every roof while snowing, up to `ROOF_SNOW_MAX`, and melts it afterwards
+fn update_roof_snow(buildings: &mut [Building], snowing: bool, dt: f32) {
+    for building in buildings {
+        building.roof_snow = if snowing {
+            (building.roof_snow + ROOF_SNOW_RATE * dt).min(ROOF_SNOW_MAX)
+        } else {
+            (building.roof_snow - ROOF_SNOW_MELT * dt).max(0.0)
+        };
+    }
+}
+
+/// Lets snow settle on every road column while snowing and melts it off afterwards
+fn update_road_snow(road_snow: &mut [f32], snowing: bool, dt: f32) {
+    for depth in road_snow {
+        *depth = if snowing {
+            (*depth + ROAD_SNOW_RATE * dt).min(1.0)
+        } else {
+            (*depth - ROAD_SNOW_MELT * dt).max(0.0)
+        };
+    }
+}
+
+/// Scrapes the road bare in the columns under a snowplow
+fn plow_road_snow(road_snow: &mut [f32], plow: &Vehicle) {
+    let left = plow.x.max(0.0) as usize;
+    let right = (plow.x + display_width(&plow.style) as f32).max(0.0) as usize;
+    for depth in road_snow.iter_mut().take(right).skip(left) {
+        *depth = 0.0;
+    }
+}
+
+/// Soaks the road quickly while raining and dries it over `ROAD_DRY_SECS` afterwards
+fn update_road_wetness(wetness: f32, raining: bool, dt: f32) -> f32 {
+    if raining {
+        (wetness + dt).min(1.0)
+    } else {
+        (wetness - dt / ROAD_DRY_SECS).max(0.0)
+    }
+}
+
+/// Blends the dry road color toward the darker wet color by `wetness`
+fn road_color(wetness: f32) -> Color {
+    lerp_color(ROAD_COLOR, WET_ROAD_COLOR, wetness)
+}
+
+/// Scatters puddles when rain starts, ripples them while it lasts and clears them after
+fn update_puddles(puddles: &mut Vec<Puddle>, raining: bool, term_width: u16, rng: &mut impl Rng) {
+    if !raining {
+        puddles.clear();
+        return;
+    }
+    if puddles.is_empty() {
+        for _ in 0..(term_width / 10).max(1) {
+            puddles.push(Puddle {
+                x: rng.random_range(0..term_width),
+                phase: rng.random_range(0..PUDDLE_CHARS.len() as u8),
+            });
+        }
+    }
+    for puddle in puddles {
+        if rng.random_bool(0.2) {
+            puddle.phase = (puddle.phase + 1) % PUDDLE_CHARS.len() as u8;
+        }
+    }
+}
+
+/// Computes the cells of a semicircular arc above `(center_x, center_y)`
+///
+/// Terminal cells are about twice as tall as they are wide, so the arc is stretched
+/// horizontally to look round. Points are mirrored around the center column and clipped
+/// to the screen.
+fn rainbow_arc_points(
+    center_x: i32,
+    center_y: i32,
+    radius: i32,
+    term_width: u16,
+    term_height: u16,
+) -> Vec<(u16, u16)> {
+    let mut offsets = std::collections::BTreeSet::new();
+    let steps = radius.max(1) * 8;
+    for i in 0..=steps {
+        let theta = std::f32::consts::FRAC_PI_2 * i as f32 / steps as f32;
+        let dx = (2.0 * radius as f32 * theta.cos()).round() as i32;
+        let dy = (radius as f32 * theta.sin()).round() as i32;
+        offsets.insert((dx, dy));
+        offsets.insert((-dx, dy));
+    }
+    offsets
+        .into_iter()
+        .map(|(dx, dy)| (center_x + dx, center_y - dy))
+        .filter(|&(x, y)| x >= 0 && y >= 0 && x < term_width as i32 && y < term_height as i32)
+        .map(|(x, y)| (x as u16, y as u16))
+        .collect()
+}
+
+/// Creates fog wisps hugging the horizon, more of them the thicker the fog
+fn create_fog_wisps(term_width: u16, term_height: u16, rng: &mut impl Rng, fog: f32) -> Vec<Cloud> {
+    let count = (fog * 6.0).ceil() as u16;
+    let horizon = road_layout(term_height).far_sidewalk;
+    let mut wisps = Vec::new();
+    for _ in 0..count {
+        wisps.push(Cloud {
+            x: rng.random_range(0..term_width) as f32,
+            y: rng.random_range(horizon - 4..horizon),
+            shape: FOG_WISP_SHAPES[rng.random_range(0..FOG_WISP_SHAPES.len())],
+            speed: rng.random_range(0.2..0.6),
+            phase: rng.random_range(0.0..std::f32::consts::TAU),
+        });
+    }
+    wisps
+}
+
+/// Blends a color toward the fog color in proportion to the fog intensity
+fn apply_fog(color: Color, intensity: f32) -> Color {
+    lerp_color(color, FOG_COLOR, intensity)
+}
+
+/// Places a crane in the widest gap between buildings
+fn create_crane(
+    buildings: &[Building],
+    term_width: u16,
+    term_height: u16,
+    rng: &mut impl Rng,
+) -> Crane {
+    let mut best = (0, 0); // (gap width, gap center)
+    for pair in buildings.windows(2) {
+        let start = pair[0].x + pair[0].width;
+        let gap = pair[1].x.saturating_sub(start);
+        if gap > best.0 {
+            best = (gap, start + gap / 2);
+        }
+    }
+    let x = if best.0 > 0 {
+        best.1
+    } else {
+        rng.random_range(0..term_width)
+    };
+    // Keep the jib a few rows clear of the top of the screen
+    let max_height = road_layout(term_height).far_sidewalk - 3;
+    let height = rng.random_range((term_height / 2).min(max_height - 1)..max_height);
+    let (hook_min, _) = crane_hook_range(height, term_height);
+    Crane {
+        x,
+        height,
+        jib_angle: rng.random_range(0.0..std::f32::consts::TAU),
+        hook_y: hook_min,
+        hook_dir: 1.0,
+    }
+}
+
+/// Highest and lowest rows the crane hook travels between
+fn crane_hook_range(height: u16, term_height: u16) -> (f32, f32) {
+    let base_y = road_layout(term_height).far_sidewalk - 1;
+    ((base_y - height + 1) as f32, (base_y - 1) as f32)
+}
+
+/// Turns the jib and raises or lowers the hook, reversing at either end of its travel
+fn update_crane(crane: &mut Crane, term_height: u16, steps: f32) {
+    crane.jib_angle =
+        (crane.jib_angle + CRANE_TURN_SPEED * steps).rem_euclid(std::f32::consts::TAU);
+    let (hook_min, hook_max) = crane_hook_range(crane.height, term_height);
+    crane.hook_y += crane.hook_dir * CRANE_HOOK_SPEED * steps;
+    if crane.hook_y >= hook_max {
+        crane.hook_y = hook_max;
+        crane.hook_dir = -1.0;
+    } else if crane.hook_y <= hook_min {
+        crane.hook_y = hook_min;
+        crane.hook_dir = 1.0;
+    }
+}
+
+/// Launches a couple of boats heading in opposite directions
+fn create_boats(term_width: u16, rng: &mut impl Rng) -> Vec<Boat> {
+    [1.0, -1.0]
+        .into_iter()
+        .map(|dir| Boat {
+            x: rng.random_range(0..term_width) as f32,
+            speed: dir * rng.random_range(0.05..0.15),
+            glyph: BOAT_GLYPHS[rng.random_range(0..BOAT_GLYPHS.len())],
+        })
+        .collect()
+}
+
+/// Moves boats by their speed, wrapping them around at either edge
+fn update_boats(boats: &mut [Boat], term_width: u16, steps: f32) {
+    for boat in boats {
+        boat.x += boat.speed * steps;
+        let width = display_width(boat.glyph) as f32;
+        if boat.speed > 0.0 && boat.x > term_width as f32 {
+            boat.x = -width;
+        } else if boat.speed < 0.0 && boat.x < -width {
+            boat.x = term_width as f32;
+        }
+    }
+}
+
+/// Occasionally starts a tumbleweed at the upwind edge of the road while the wind is strong
+fn spawn_tumbleweed(
+    wind: f32,
+    term_width: u16,
+    road: &RoadLayout,
+    rng: &mut impl Rng,
+) -> Option<Tumbleweed> {
+    if wind.abs() <= TUMBLEWEED_WIND || !rng.random_bool(TUMBLEWEED_CHANCE) {
+        return None;
+    }
+    Some(Tumbleweed {
+        x: if wind > 0.0 {
+            0.0
+        } else {
+            (term_width - 1) as f32
+        },
+        y: road.near_lane,
+        spin: 0.0,
+    })
+}
+
+/// Rolls a tumbleweed along with the wind, faster the harder it blows
+fn update_tumbleweed(tumbleweed: &mut Tumbleweed, wind: f32, steps: f32) {
+    tumbleweed.x += wind * TUMBLEWEED_WIND_FACTOR * steps;
+    tumbleweed.spin = (tumbleweed.spin + steps) % 256.0;
+}
+
+/// Rows a tumbleweed hops up off the road for its spin, giving an uneven bounce
+fn tumbleweed_bounce(spin: u8) -> u16 {
+    (spin % 7 < 2) as u16
+}
+
+/// Shifts the water ripple pattern along by one cell
+fn advance_water_phase(phase: u8) -> u8 {
+    (phase + 1) % WATER_PATTERN.len() as u8
+}
+
+/// Creates the given number of hot-air balloons in the upper third of the screen
+fn create_balloons(
+    term_width: u16,
+    term_height: u16,
+    rng: &mut impl Rng,
+    count: u16,
+) -> Vec<Balloon> {
+    let mut balloons = Vec::new();
+    for _ in 0..count {
+        balloons.push(Balloon {
+            x: rng.random_range(0..term_width) as f32,
+            y: rng.random_range(0..term_height / 3),
+            color: BALLOON_COLORS[rng.random_range(0..BALLOON_COLORS.len())],
+            frame: rng.random::<u8>() as f32,
+        });
+    }
+    balloons
+}
+
+/// Drifts balloons to the right, wrapping them around like clouds
+fn update_balloons(balloons: &mut [Balloon], term_width: u16, steps: f32) {
+    for balloon in balloons {
+        balloon.x += BALLOON_SPEED * 0.1 * steps;
+        balloon.frame = (balloon.frame + steps) % 256.0;
+        if balloon.x > term_width as f32 {
+            balloon.x = -(BALLOON_SPRITE[1].len() as f32); // Wrap around
+        }
+    }
+}
+
+/// Vertical offset of a balloon for its current frame, giving a slow bob
+fn balloon_bob(frame: u8) -> u16 {
+    (frame / 32 % 2) as u16
+}
+
+/// Depth at which something is drawn into a `Frame`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    Sky,   // Behind everything solid, so hidden wherever solid scenery stands
+    Solid, // Buildings and other scenery that blocks the sky behind it
+    Front, // Drawn over whatever is already there
+}
+
+/// A single drawn character cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cell {
+    ch: char,
+    color: Color,
+}
+
+/// Off-screen grid of cells the scene is drawn into before it is written to the terminal
+///
+/// Cells drawn on the solid layer are recorded in an occlusion mask, and sky layer cells only
+/// land where the mask is clear, so the sky never shows through buildings.
+struct Frame {
+    width: u16,
+    height: u16,
+    cells: Vec<Option<Cell>>,
+    solid: Vec<bool>,
+}
+
+impl Frame {
+    fn new(width: u16, height: u16) -> Self {
+        let len = width as usize * height as usize;
+        Frame {
+            width,
+            height,
+            cells: vec![None; len],
+            solid: vec![false; len],
+        }
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+
+    /// Draws a character at a cell on the given layer, ignoring cells off the frame
+    fn put(&mut self, layer: Layer, x: u16, y: u16, ch: char, color: Color) {
+        let Some(i) = self.index(x, y) else {
+            return;
+        };
+        match layer {
+            Layer::Sky if self.solid[i] => return,
+            Layer::Solid => self.solid[i] = true,
+            _ => {}
+        }
+        self.cells[i] = Some(Cell { ch, color });
+        // A wide glyph covers the next cell too, which must not print over its right half
+        if display_width(ch.encode_utf8(&mut [0; 4])) > 1
+            && let Some(next) = self.index(x + 1, y)
+        {
+            self.cells[next] = None;
+        }
+    }
+
+    /// Draws a string from a starting column, clipping any characters off either edge
+    fn put_str(&mut self, layer: Layer, x: i32, y: u16, text: &str, color: Color) {
+        let mut col = x;
+        for ch in text.chars() {
+            let width = display_width(ch.encode_utf8(&mut [0; 4])) as i32;
+            if col >= 0 && col + width <= self.width as i32 {
+                self.put(layer, col as u16, y, ch, color);
+            }
+            col += width;
+        }
+    }
+
+    /// The cell drawn at a position, if any
+    fn get(&self, x: u16, y: u16) -> Option<Cell> {
+        self.index(x, y).and_then(|i| self.cells[i])
+    }
+
+    /// Blends the color of an already drawn cell `amount` of the way toward `color`
+    fn tint(&mut self, x: u16, y: u16, color: Color, amount: f32) {
+        if let Some(Some(cell)) = self.index(x, y).map(|i| &mut self.cells[i]) {
+            cell.color = lerp_color(cell.color, color, amount);
+        }
+    }
+
+    /// A copy of the frame moved `dx` columns right and `dy` rows down, with whatever
+    /// moves off one edge wrapping around to the other
+    fn shifted(&self, dx: i16, dy: i16) -> Frame {
+        let mut moved = Frame::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let to_x = (x as i32 + dx as i32).rem_euclid(self.width as i32) as u16;
+                let to_y = (y as i32 + dy as i32).rem_euclid(self.height as i32) as u16;
+                let (Some(from), Some(to)) = (self.index(x, y), moved.index(to_x, to_y)) else {
+                    continue;
+                };
+                moved.cells[to] = self.cells[from];
+                moved.solid[to] = self.solid[from];
+            }
+        }
+        moved
+    }
+
+    /// Copies the part of a wider `canvas` starting at column `camera_x` onto the solid
+    /// layer, wrapping around to the canvas's start past its right edge
+    fn pan_over(&mut self, canvas: &Frame, camera_x: u16) {
+        for y in 0..self.height.min(canvas.height) {
+            for x in 0..self.width {
+                let source = ((camera_x as u32 + x as u32) % canvas.width as u32) as u16;
+                if let Some(cell) = canvas.get(source, y) {
+                    self.put(Layer::Solid, x, y, cell.ch, cell.color);
+                }
+            }
+        }
+    }
+
+    /// Writes the frame to the terminal, every cell of it, blanks in the `background`
+    ///
+    /// Nothing from an earlier frame or from before the screensaver started can show
+    /// through, so the screen needn't be cleared first. With a `viewport`, the frame is
+    /// drawn inside it and the margins around it, out to the edges of the `screen`, are
+    /// filled with `LETTERBOX_COLOR`.
+    fn flush_to(
+        &self,
+        out: &mut impl Write,
+        background: Color,
+        viewport: Option<Rect>,
+        screen: (u16, u16),
+    ) -> io::Result<()> {
+        let (left, top) = viewport.map_or((0, 0), |rect| (rect.x, rect.y));
+        if viewport.is_some() {
+            self.fill_margins(out, (left, top), screen)?;
+        }
+        out.queue(style::SetBackgroundColor(background))?;
+        let mut current_color = None;
+        for y in 0..self.height {
+            if !move_to_checked(out, left as i32, (top + y) as i32, screen)? {
+                continue;
+            }
+            let mut col = 0;
+            for x in 0..self.width {
+                // Still covered by the right half of a wide glyph
+                if x < col {
+                    continue;
+                }
+                let Some(cell) = self.get(x, y) else {
+                    out.queue(Print(' '))?;
+                    col = x + 1;
+                    continue;
+                };
+                if current_color != Some(cell.color) {
+                    out.queue(style::SetForegroundColor(cell.color))?;
+                    current_color = Some(cell.color);
+                }
+                out.queue(Print(cell.ch))?;
+                col = x + display_width(cell.ch.encode_utf8(&mut [0; 4]));
+            }
+        }
+        out.flush()
+    }
+
+    /// Writes a blank in `LETTERBOX_COLOR` to every cell of the `screen` the frame, drawn
+    /// from `origin`, doesn't cover
+    fn fill_margins(
+        &self,
+        out: &mut impl Write,
+        origin: (u16, u16),
+        screen: (u16, u16),
+    ) -> io::Result<()> {
+        let (left, top) = origin;
+        let (right, bottom) = (left + self.width, top + self.height);
+        out.queue(style::SetBackgroundColor(LETTERBOX_COLOR))?;
+        for y in 0..screen.1 {
+            let spans = if (top..bottom).contains(&y) {
+                [(0, left), (right, screen.0)]
+            } else {
+                [(0, screen.0), (0, 0)]
+            };
+            for (from, to) in spans.into_iter().filter(|(from, to)| from < to) {
+                out.queue(cursor::MoveTo(from, y))?
+                    .queue(Print(" ".repeat((to - from) as usize)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Moves the cursor to `(x, y)` when that lies inside `bounds` (width, height), returning
+/// whether it did; nothing is written for a position off the screen, so the caller can
+/// skip drawing there rather than scribble on whatever the terminal wraps it to
+fn move_to_checked(w: &mut impl Write, x: i32, y: i32, bounds: (u16, u16)) -> io::Result<bool> {
+    let (Ok(col), Ok(row)) = (u16::try_from(x), u16::try_from(y)) else {
+        return Ok(false);
+    };
+    if col >= bounds.0 || row >= bounds.1 {
+        return Ok(false);
+    }
+    w.queue(cursor::MoveTo(col, row))?;
+    Ok(true)
+}
+
+/// Draws the entire scene by calling individual drawing functions
+fn draw_scene(stdout: &mut impl Write, scene: &Scene) -> io::Result<()> {
+    let mut frame = Frame::new(scene.width, scene.height + scene.ground_rows);
+    let frame = &mut frame;
+
+    // Solid scenery goes in first so the sky layers below are masked wherever it stands,
+    // whatever their own order
+    let dusk = is_dusk(scene.time_of_day);
+    for backdrop in &scene.backdrops {
+        draw_buildings(
+            frame,
+            backdrop,
+            scene.height,
+            scene.fog,
+            dusk,
+            &scene.palette,
+            scene.outlines,
+        );
+    }
+    let mut city = Frame::new(scene.city_width, scene.height);
+    draw_buildings(
+        &mut city,
+        &scene.buildings,
+        scene.height,
+        scene.fog,
+        dusk,
+        &scene.palette,
+        scene.outlines,
+    );
+    if let Some(crane) = &scene.crane {
+        draw_crane(&mut city, crane, scene.city_width, scene.height);
+    }
+    if let Some(fireworks) = &scene.fireworks {
+        draw_firework_glow(&mut city, scene, fireworks);
+    }
+    frame.pan_over(&city, scene.camera_x as u16);
+
+    // Sky layers, back to front
+    if scene.glow > 0.0 {
+        draw_light_pollution(frame, scene);
+    }
+    if let Some(phase) = scene.aurora {
+        draw_aurora(frame, phase, scene.width, scene.fog);
+    }
+    if let Some(angle) = scene.sweep {
+        draw_sweep(frame, scene, angle);
+    }
+    let ctx = scene.ctx();
+    scene.draw_entities(frame, &ctx, ..FOREGROUND_Z);
+    draw_constellations(
+        frame,
+        &scene.constellations,
+        scene.width,
+        scene.height,
+        scene.fog,
+    );
+    if let Some(sat) = &scene.satellite {
+        frame.put(
+            Layer::Sky,
+            sat.x as u16,
+            sat.y as u16,
+            '.',
+            apply_fog(SATELLITE_COLOR, scene.fog),
+        );
+    }
+    draw_birds(frame, &scene.birds, scene.tick, scene.width, scene.fog);
+    if let Some(fireworks) = &scene.fireworks {
+        draw_fireworks(frame, fireworks, scene.fog);
+    }
+    draw_moon(frame, scene.width, scene.fog);
+    draw_rainbow(frame, &scene.rainbow, scene.width, scene.height);
+    if let Some(wheel) = &scene.ferris {
+        draw_ferris_wheel(frame, wheel, scene.horizon_y());
+    }
+
+    // Foreground layers, back to front, drawn over everything before them
+    draw_clouds(
+        frame,
+        &scene.fog_wisps,
+        scene.width,
+        scene.horizon_y() - 1,
+        Layer::Front,
+        FOG_COLOR,
+    );
+    let road = scene.road();
+    draw_road(
+        frame,
+        scene.width,
+        &road,
+        scene.road_wetness,
+        &scene.puddles,
+    );
+    draw_road_snow(frame, &scene.road_snow, &road);
+    draw_bus_stop(frame, scene.width, &road);
+    draw_ground(frame, scene.width, &road, scene.ground);
+    draw_street_steam(frame, &scene.manholes, &scene.steam, scene.width, &road);
+    if scene.river {
+        draw_water(frame, scene.width, scene.height, scene.water_phase);
+        draw_sky_reflections(
+            frame,
+            scene.horizon_y(),
+            scene.height - 1,
+            &scene.stars,
+            scene.width,
+            scene.height,
+            scene.tick,
+        );
+        draw_boats(frame, &scene.boats, scene.width, scene.height);
+    }
+    draw_weather_effects(
+        frame,
+        &scene.raindrops,
+        &scene.ripples,
+        &scene.snowflakes,
+        scene.weather,
+        scene.rain_intensity.glyph(),
+    );
+    for petal in &scene.petals {
+        frame.put(
+            Layer::Front,
+            petal.x as u16,
+            petal.y as u16,
+            petal.char,
+            petal.color,
+        );
+    }
+    for tumbleweed in &scene.tumbleweeds {
+        draw_tumbleweed(frame, tumbleweed);
+    }
+    if is_night(scene.time_of_day) {
+        draw_vehicle_lights(frame, &scene.vehicles, scene.width);
+    }
+    scene.draw_entities(frame, &ctx, FOREGROUND_Z..);
+    if let Some(streams) = &scene.matrix {
+        draw_matrix(frame, streams);
+    }
+
+    if scene.show_help {
+        draw_help_overlay(frame, &KEY_BINDINGS);
+    }
+
+    let sky = if scene.lightning > 0 {
+        LIGHTNING_COLOR
+    } else if dusk {
+        DUSK_SKY_COLOR
+    } else {
+        scene.palette.sky
+    };
+    if let Some(elapsed) = scene.drift {
+        let (dx, dy) = drift_offset(elapsed);
+        *frame = frame.shifted(dx, dy);
+    }
+    frame.flush_to(stdout, sky, scene.viewport, scene.screen)
+}
+
+/// Lines of the help overlay: a title, then one aligned line per key binding
+fn help_lines(bindings: &[(&str, &str)]) -> Vec<String> {
+    let key_width = bindings
+        .iter()
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines = vec!["Keys".to_string()];
+    for (key, action) in bindings {
+        lines.push(format!("{:<key_width$}  {}", key, action));
+    }
+    lines
+}
+
+/// Draws a bordered box of key bindings centered over the scene
+///
+/// The box is shrunk to fit the terminal, cutting off lines and text that don't fit.
+fn draw_help_overlay(frame: &mut Frame, bindings: &[(&str, &str)]) {
+    let lines = help_lines(bindings);
+    let text_width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u16;
+    let box_width = (text_width + 4).min(frame.width);
+    let box_height = (lines.len() as u16 + 2).min(frame.height);
+    if box_width < 2 || box_height < 2 {
+        return;
+    }
+    let left = (frame.width - box_width) / 2;
+    let top = (frame.height - box_height) / 2;
+    let (right, bottom) = (left + box_width - 1, top + box_height - 1);
+    for y in top..=bottom {
+        for x in left..=right {
+            let ch = match (x == left || x == right, y == top || y == bottom) {
+                (true, true) => '+',
+                (false, true) => '-',
+                (true, false) => '|',
+                (false, false) => ' ',
+            };
+            frame.put(Layer::Front, x, y, ch, HELP_BORDER_COLOR);
+        }
+    }
+    let inner_width = box_width.saturating_sub(4) as usize;
+    for (row, line) in lines.iter().take(box_height as usize - 2).enumerate() {
+        for (col, ch) in line.chars().take(inner_width).enumerate() {
+            frame.put(
+                Layer::Front,
+                left + 2 + col as u16,
+                top + 1 + row as u16,
+                ch,
+                HELP_TEXT_COLOR,
+            );
+        }
+    }
+}
+
+/// Draws all clouds in the scene, none bobbing lower than `max_y`
+fn draw_clouds(
+    frame: &mut Frame,
+    clouds: &[Cloud],
+    term_width: u16,
+    max_y: u16,
+    layer: Layer,
+    color: Color,
+) {
+    for cloud in clouds {
+        for (x, y, ch) in cloud_cells(cloud, max_y) {
+            if x < 0 || x >= term_width as i32 {
+                continue;
+            }
+            frame.put(layer, x as u16, y, ch, color);
+        }
+    }
+}
+
+/// Row the top of a cloud is drawn on this frame, bobbed up or down from its own, but no
+/// lower than `max_y`
+fn cloud_display_y(cloud: &Cloud, max_y: u16) -> u16 {
+    let bob = cloud.phase.sin() * CLOUD_BOB_AMPLITUDE;
+    (cloud.y as f32 + bob).round().clamp(0.0, max_y as f32) as u16
+}
+
+/// Visible cells of a cloud sprite, each row drawn one line below the previous
+fn cloud_cells(cloud: &Cloud, max_y: u16) -> Vec<(i32, u16, char)> {
+    let top = cloud_display_y(cloud, max_y);
+    let mut cells = Vec::new();
+    for (row, line) in cloud.shape.iter().enumerate() {
+        for (col, ch) in line.chars().enumerate() {
+            if ch != ' ' {
+                cells.push((cloud.x as i32 + col as i32, top + row as u16, ch));
+            }
+        }
+    }
+    cells
+}
+
+/// Draws all stars in the scene
+fn draw_stars(frame: &mut Frame, stars: &[Star], fog: f32) {
+    for star in stars {
+        frame.put(
+            Layer::Sky,
+            star.x,
+            star.y,
+            star.char,
+            apply_fog(star_color(star.brightness), fog),
+        );
+    }
+}
+
+/// Draws constellation lines in a faint color with bright stars at their anchors
+fn draw_constellations(
+    frame: &mut Frame,
+    constellations: &[Constellation],
+    term_width: u16,
+    term_height: u16,
+    fog: f32,
+) {
+    let on_screen = |&(x, y): &(u16, u16)| x < term_width && y < term_height;
+    for constellation in constellations {
+        for &(from, to) in constellation.segments {
+            let (a, b) = (constellation.stars[from], constellation.stars[to]);
+            let glyph = if a.1 == b.1 {
+                '-'
+            } else if a.0 == b.0 {
+                '|'
+            } else {
+                '.'
+            };
+            let line = bresenham(a, b);
+            for cell in line[1..line.len() - 1].iter().filter(|c| on_screen(c)) {
+                frame.put(
+                    Layer::Sky,
+                    cell.0,
+                    cell.1,
+                    glyph,
+                    apply_fog(CONSTELLATION_LINE_COLOR, fog),
+                );
+            }
+        }
+        for star in constellation.stars.iter().filter(|s| on_screen(s)) {
+            frame.put(Layer::Sky, star.0, star.1, '*', apply_fog(STAR_COLOR, fog));
+        }
+    }
+}
+
+/// Draws rising shells as a spark trailing upward and bursts as sparks that dim and shrink
+/// as they burn out, all behind the buildings
+fn draw_fireworks(frame: &mut Frame, fireworks: &[Firework], fog: f32) {
+    let black = Color::Rgb { r: 0, g: 0, b: 0 };
+    for firework in fireworks {
+        if firework.state == FireworkState::Rising {
+            let color = apply_fog(firework.color, fog);
+            frame.put(Layer::Sky, firework.x as u16, firework.y as u16, '|', color);
+            continue;
+        }
+        for spark in firework.sparks.iter().filter(|s| s.x >= 0.0 && s.y >= 0.0) {
+            let age = 1.0 - spark.life as f32 / FIREWORK_SPARK_LIFE as f32;
+            let ch = match spark.life * 3 / FIREWORK_SPARK_LIFE {
+                2.. => '*',
+                1 => '+',
+                _ => '.',
+            };
+            let color = apply_fog(lerp_color(firework.color, black, age * 0.7), fog);
+            frame.put(Layer::Sky, spark.x as u16, spark.y as u16, ch, color);
+        }
+    }
+}
+
+/// Tints lit windows near each burst toward its color, fading with distance from it
+///
+/// Buildings are drawn across the whole city, so bursts are moved from screen columns into
+/// the city's by the camera's position.
+fn draw_firework_glow(city: &mut Frame, scene: &Scene, fireworks: &[Firework]) {
+    let top = scene.horizon_y();
+    for firework in fireworks.iter().filter(|f| !f.sparks.is_empty()) {
+        let burst_x = firework.x + scene.camera_x;
+        let fade = firework.sparks[0].life as f32 / FIREWORK_SPARK_LIFE as f32;
+        for building in &scene.buildings {
+            let layout = building.window_layout;
+            let top_y = top - building.height;
+            for (wy, row) in building.windows.iter().enumerate() {
+                for (wx, _) in row.iter().enumerate().filter(|(_, w)| w.on) {
+                    let (dx, dy) = layout.origin(wx as u16, wy as u16);
+                    let x = building.x + dx;
+                    let distance = (x as f32 - burst_x).abs();
+                    if distance >= FIREWORK_GLOW_RADIUS {
+                        continue;
+                    }
+                    let amount = FIREWORK_GLOW * fade * (1.0 - distance / FIREWORK_GLOW_RADIUS);
+                    for cy in 0..layout.size {
+                        for cx in 0..layout.size {
+                            city.tint(x + cx, top_y + dy + cy, firework.color, amount);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Draws each plane with its blinking wing light and a contrail dimming with age
+fn draw_planes(frame: &mut Frame, planes: &[Plane], fog: f32) {
+    for plane in planes {
+        for cell in plane.trail.iter() {
+            let fade = 1.0 - cell.age as f32 / CONTRAIL_MAX_LEN as f32;
+            let color = lerp_color(ROAD_COLOR, CONTRAIL_COLOR, fade);
+            frame.put(Layer::Sky, cell.x, cell.y, '.', apply_fog(color, fog));
+        }
+        let glyph = if plane.speed > 0.0 { "-=>" } else { "<=-" };
+        let x = plane.x as i32;
+        frame.put_str(Layer::Sky, x, plane.y, glyph, apply_fog(PLANE_COLOR, fog));
+        // The wing light hangs under the middle of the fuselage
+        if x + 1 >= 0 {
+            let light = nav_light_color(plane.blink as u8);
+            frame.put(Layer::Sky, (x + 1) as u16, plane.y + 1, '\'', light);
+        }
+    }
+}
+
+/// Top-left cell of the moon's sprite
+fn moon_origin(term_width: u16) -> (u16, u16) {
+    (term_width.saturating_sub(15), 1)
+}
+
+/// Cells of a soft halo around `center` reaching `radius` rows out, each weighted from
+/// near 1.0 beside the center down to 0.0 at the edge
+///
+/// Cells are about twice as tall as they are wide, so the halo reaches twice as many
+/// columns as rows to look round. Cells that would fall off the top or left of the screen
+/// are left out.
+fn moon_halo_cells(center: (u16, u16), radius: u16) -> Vec<((u16, u16), f32)> {
+    let (cx, cy) = (center.0 as i32, center.1 as i32);
+    let r = radius as i32;
+    let mut cells = Vec::new();
+    for dy in -r..=r {
+        for dx in -2 * r..=2 * r {
+            let distance = ((dx as f32 / 2.0).powi(2) + (dy as f32).powi(2)).sqrt();
+            let (x, y) = (cx + dx, cy + dy);
+            if distance == 0.0 || distance >= radius as f32 || x < 0 || y < 0 {
+                continue;
+            }
+            cells.push(((x as u16, y as u16), 1.0 - distance / radius as f32));
+        }
+    }
+    cells
+}
+
+/// Draws the moon in the scene, glowing faintly into the empty sky around it
+fn draw_moon(frame: &mut Frame, term_width: u16, fog: f32) {
+    let color = apply_fog(MOON_COLOR, fog);
+    let (left, top) = moon_origin(term_width);
+    let center = (left + MOON_SPRITE[1].len() as u16 / 2, top + 1);
+    for ((x, y), weight) in moon_halo_cells(center, MOON_HALO_RADIUS) {
+        if frame.get(x, y).is_none() {
+            let glow = lerp_color(Color::Black, color, weight * MOON_HALO_STRENGTH);
+            frame.put(Layer::Sky, x, y, '·', glow);
+        }
+    }
+    for (row, line) in MOON_SPRITE.iter().enumerate() {
+        for (col, ch) in line.chars().enumerate() {
+            if ch != ' ' {
+                frame.put(Layer::Sky, left + col as u16, top + row as u16, ch, color);
+            }
+        }
+    }
+}
+
+/// How strongly the city lights up the sky above a column, from 0.0 to 1.0
+///
+/// Every building within `GLOW_REACH` columns adds its height times its lit windows,
+/// fading with distance, so tall, bright towers glow most and empty lots barely at all.
+fn glow_intensity(column: u16, buildings: &[Building]) -> f32 {
+    let total: f32 = buildings
+        .iter()
+        .map(|building| {
+            let distance = if column < building.x {
+                building.x - column
+            } else {
+                column.saturating_sub(building.x + building.width - 1)
+            } as f32;
+            if distance >= GLOW_REACH {
+                return 0.0;
+            }
+            let lit = building.windows.iter().flatten().filter(|w| w.on).count();
+            (1.0 - distance / GLOW_REACH) * building.height as f32 * lit as f32
+        })
+        .sum();
+    (total / GLOW_SATURATION).min(1.0)
+}
+
+/// Tints the sky rows just above the skyline with the warm haze of the city's lights
+fn draw_light_pollution(frame: &mut Frame, scene: &Scene) {
+    let horizon = scene.horizon_y();
+    for x in 0..scene.width {
+        let column = ((scene.camera_x as u32 + x as u32) % scene.city_width as u32) as u16;
+        let intensity = scene.glow * glow_intensity(column, &scene.buildings);
+        let roof = scene
+            .buildings
+            .iter()
+            .filter(|b| (b.x..b.x + b.width).contains(&column))
+            .map(|b| b.height)
+            .max()
+            .unwrap_or(0);
+        let top = horizon.saturating_sub(roof);
+        for row in 1..=GLOW_ROWS.min(top) {
+            let strength = intensity * (1.0 - (row - 1) as f32 / GLOW_ROWS as f32);
+            if strength >= GLOW_MIN {
+                let color = lerp_color(Color::Black, GLOW_COLOR, strength);
+                frame.put(Layer::Sky, x, top - row, '░', color);
+            }
+        }
+    }
+}
+
+/// Draws the fading rainbow arc, centered below the horizon
+fn draw_rainbow(frame: &mut Frame, rainbow: &Rainbow, term_width: u16, term_height: u16) {
+    if rainbow.alpha <= 0.0 {
+        return;
+    }
+    let horizon = term_height as i32 - 3;
+    let outer_radius = horizon - 2;
+    for (band, color) in RAINBOW_COLORS.iter().enumerate() {
+        let faded = lerp_color(Color::Black, *color, rainbow.alpha);
+        let radius = outer_radius - band as i32;
+        for (x, y) in rainbow_arc_points(
+            term_width as i32 / 2,
+            horizon,
+            radius,
+            term_width,
+            term_height,
+        ) {
+            frame.put(Layer::Sky, x, y, '░', faded);
+        }
+    }
+}
+
+/// Draws a single hot-air balloon, clipping any part that is off-screen
+fn draw_balloon(frame: &mut Frame, balloon: &Balloon, term_width: u16) {
+    let y = balloon.y + balloon_bob(balloon.frame as u8);
+    let basket_row = BALLOON_SPRITE.len() - 1;
+    for (row, line) in BALLOON_SPRITE.iter().enumerate() {
+        let color = if row == basket_row {
+            BASKET_COLOR
+        } else {
+            balloon.color
+        };
+        for (col, ch) in line.chars().enumerate() {
+            let x = balloon.x as i32 + col as i32;
+            if ch == ' ' || x < 0 || x >= term_width as i32 {
+                continue;
+            }
+            frame.put(Layer::Sky, x as u16, y + row as u16, ch, color);
+        }
+    }
+}
+
+/// Draws all buildings in the scene
+///
+/// As `silhouette`s, against the dusk sky, every building is the same near-black and only
+/// its windows keep their color. With `outlines`, a building's walls and roof are traced in
+/// the palette's outline color, or else a darker shade of the building.
+fn draw_buildings(
+    frame: &mut Frame,
+    buildings: &[Building],
+    term_height: u16,
+    fog: f32,
+    silhouette: bool,
+    palette: &Palette,
+    outlines: bool,
+) {
+    // Buildings stand on the far sidewalk
+    let base_y = road_layout(term_height).far_sidewalk;
+    for building in buildings {
+        let body_color = if silhouette {
+            SILHOUETTE_COLOR
+        } else {
+            apply_fog(building.color, fog)
+        };
+        let edge_color = match palette.outline {
+            Some(outline) => outline,
+            None if outlines => lerp_color(body_color, Color::Black, OUTLINE_SHADE),
+            None => body_color,
+        };
+        let top_y = base_y - building.height;
+        // Draw building structure
+        for y in 0..building.height {
+            for x in 0..building.width {
+                let (ch, color) = if building.kind == BuildingKind::RadioTower {
+                    let lattice = RADIO_TOWER_LATTICE[y as usize % 2];
+                    (lattice[x as usize % lattice.len()], edge_color)
+                } else {
+                    let edge = x == 0 || x == building.width - 1 || y == 0;
+                    ('█', if edge { edge_color } else { body_color })
+                };
+                frame.put(Layer::Solid, building.x + x, top_y + y, ch, color);
+            }
+        }
+
+        match building.kind {
+            BuildingKind::House => {
+                for (row, line) in roof_rows(building.width).iter().enumerate() {
+                    for (col, ch) in line.chars().enumerate() {
+                        if ch == ' ' || top_y < row as u16 + 1 {
+                            continue;
+                        }
+                        let (x, y) = (building.x + col as u16, top_y - row as u16 - 1);
+                        frame.put(Layer::Solid, x, y, ch, edge_color);
+                    }
+                }
+            }
+            BuildingKind::Skyscraper => {
+                let spire_x = building.x + building.width / 2;
+                for dy in 1..=SPIRE_HEIGHT.min(top_y) {
+                    let ch = if dy == SPIRE_HEIGHT { '^' } else { '|' };
+                    frame.put(Layer::Solid, spire_x, top_y - dy, ch, edge_color);
+                }
+            }
+            BuildingKind::MidRise | BuildingKind::RadioTower => {}
+        }
+        draw_roof_snow(frame, building, top_y);
+
+        // Draw antenna if present
+        if building.has_antenna {
+            frame.put(
+                Layer::Solid,
+                building.x + building.width / 2,
+                top_y - 1,
+                building.antenna_char,
+                edge_color,
+            );
+        }
+        if let Some((x, y)) = beacon_position(building, base_y)
+            && building.beacon.as_ref().is_some_and(BeaconState::lit)
+        {
+            frame.put(
+                Layer::Solid,
+                x,
+                y,
+                BEACON_CHAR,
+                apply_fog(BEACON_COLOR, fog),
+            );
+        }
+
+        // Draw windows, with snow along the sill below each once it has piled up
+        let sill_snow = building.roof_snow >= SILL_SNOW_DEPTH;
+        let layout = building.window_layout;
+        let glyph = if palette.bold { '█' } else { layout.glyph };
+        for (wy, row) in building.windows.iter().enumerate() {
+            for (wx, window) in row.iter().enumerate() {
+                let color = window_color(window);
+                let (dx, dy) = layout.origin(wx as u16, wy as u16);
+                let (x, y) = (building.x + dx, top_y + dy);
+                for cy in 0..layout.size {
+                    for cx in 0..layout.size {
+                        frame.put(Layer::Solid, x + cx, y + cy, glyph, color);
+                    }
+                }
+                let sill_y = y + layout.size;
+                if sill_snow && sill_y < top_y + building.height {
+                    for cx in 0..layout.size {
+                        frame.put(Layer::Solid, x + cx, sill_y, '▀', SNOW_COLOR);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Moves every beacon's blink on by `dt`
+fn update_beacons(buildings: &mut [Building], dt: Duration) {
+    for beacon in buildings.iter_mut().filter_map(|b| b.beacon.as_mut()) {
+        beacon.timer.advance(dt);
+    }
+}
+
+/// Where a building's beacon shines: the tip of a radio tower's mast, or above its antenna
+fn beacon_position(building: &Building, base_y: u16) -> Option<(u16, u16)> {
+    building.beacon.as_ref()?;
+    let above = if building.kind == BuildingKind::RadioTower {
+        1
+    } else {
+        2
+    };
+    let y = (base_y - building.height).checked_sub(above)?;
+    Some((building.x + building.width / 2, y))
+}
+
+/// Draws the snow cap resting on a building's roof, half a cell at a time
+///
+/// Houses carry it on top of their sloped roof, so it follows the roof line.
+fn draw_roof_snow(frame: &mut Frame, building: &Building, top_y: u16) {
+    let halves = (building.roof_snow * 2.0).round() as u16;
+    if halves == 0 {
+        return;
+    }
+    let roof_height = |col: u16| match building.kind {
+        BuildingKind::House => (0..3.min(building.width / 2))
+            .filter(|&row| row <= col && col < building.width - row)
+            .count() as u16,
+        _ => 0,
+    };
+    for col in 0..building.width {
+        let surface = top_y.saturating_sub(roof_height(col));
+        for layer in 0..halves.div_ceil(2) {
+            let Some(y) = surface.checked_sub(layer + 1) else {
+                break;
+            };
+            let ch = if halves - layer * 2 >= 2 {
+                '█'
+            } else {
+                '▄'
+            };
+            frame.put(Layer::Solid, building.x + col, y, ch, SNOW_COLOR);
+        }
+    }
+}
+
+/// Color a window is drawn with, fading from dark to lit by its brightness
+///
+/// TV windows are lit by a blue that blends between dim and bright by their flicker
+/// rather than the steady warm color of their lamp.
+fn window_color(window: &Window) -> Color {
+    let lit = if window.tv {
+        lerp_color(TV_DIM_COLOR, TV_BRIGHT_COLOR, window.flicker)
+    } else {
+        window.lamp
+    };
+    lerp_color(WINDOW_OFF_COLOR, lit, window.brightness)
+}
+
+/// Nudges each channel of an RGB color by a random amount of at most `amount` either way,
+/// returning named colors unchanged
+fn jitter_color(base: Color, amount: u8, rng: &mut impl Rng) -> Color {
+    let Color::Rgb { r, g, b } = base else {
+        return base;
+    };
+    let amount = amount as i16;
+    let mut nudge =
+        |channel: u8| (channel as i16 + rng.random_range(-amount..=amount)).clamp(0, 255) as u8;
+    Color::Rgb {
+        r: nudge(r),
+        g: nudge(g),
+        b: nudge(b),
+    }
+}
+
+/// Blends `t` of the way from one color to another; every blend in the scene goes through here
+///
+/// Named colors are blended by their approximate RGB value. At either end the color is
+/// returned as given, so an unblended named color keeps its terminal palette entry.
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    if t == 0.0 {
+        return a;
+    }
+    if t == 1.0 {
+        return b;
+    }
+    let ((ar, ag, ab), (br, bg, bb)) = (ansi_rgb(a), ansi_rgb(b));
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+    Color::Rgb {
+        r: mix(ar, br),
+        g: mix(ag, bg),
+        b: mix(ab, bb),
+    }
+}
+
+/// Draws the crane mast, its jib foreshortened by the current rotation, and the hook
+fn draw_crane(frame: &mut Frame, crane: &Crane, term_width: u16, term_height: u16) {
+    let base_y = road_layout(term_height).far_sidewalk - 1;
+    let top_y = base_y - crane.height;
+    let mut cells = Vec::new();
+    for y in top_y + 1..=base_y {
+        cells.push((crane.x as i32, y, '╫'));
+    }
+
+    // Seen side-on, the rotating jib appears to shrink and swap sides
+    let reach = (crane.jib_angle.cos() * CRANE_JIB_LENGTH).round() as i32;
+    let tip_x = crane.x as i32 + reach;
+    for x in (crane.x as i32).min(tip_x)..=(crane.x as i32).max(tip_x) {
+        cells.push((x, top_y, '═'));
+    }
+    cells.push((crane.x as i32, top_y, '╦'));
+
+    let hook_y = crane.hook_y.round() as u16;
+    for y in top_y + 1..hook_y {
+        cells.push((tip_x, y, '│'));
+    }
+    cells.push((tip_x, hook_y, '▣'));
+
+    for (x, y, ch) in cells {
+        if x < 0 || x >= term_width as i32 {
+            continue;
+        }
+        frame.put(Layer::Solid, x as u16, y, ch, CRANE_COLOR);
+    }
+}
+
+/// Positions of a wheel's cabins, evenly spaced around the rim starting at `angle`
+///
+/// Points are returned in cell coordinates, with columns stretched by `FERRIS_ASPECT`.
+fn ferris_cabins(center: (f32, f32), radius: f32, angle: f32, count: usize) -> Vec<(f32, f32)> {
+    (0..count)
+        .map(|i| {
+            let a = angle + i as f32 * std::f32::consts::TAU / count as f32;
+            (
+                center.0 + a.cos() * radius * FERRIS_ASPECT,
+                center.1 + a.sin() * radius,
+            )
+        })
+        .collect()
+}
+
+/// Turns the beacon's beam on by `dt` seconds, wrapping its heading into 0..2π
+fn advance_sweep(angle: f32, dt: f32) -> f32 {
+    (angle + SWEEP_SPEED * dt).rem_euclid(std::f32::consts::TAU)
+}
+
+/// Where the rotating beacon sits on screen: just above the roof of the tallest building,
+/// or `None` while that building is panned out of view
+fn sweep_source(scene: &Scene) -> Option<(u16, u16)> {
+    let tallest = scene.buildings.iter().max_by_key(|b| b.height)?;
+    let center = tallest.x + tallest.width / 2;
+    let x =
+        (center as u32 + scene.city_width as u32 - scene.camera_x as u32) % scene.city_width as u32;
+    let y = (scene.horizon_y() - tallest.height).checked_sub(1)?;
+    (x < scene.width as u32).then_some((x as u16, y))
+}
+
+/// Sky cells lit by a beam heading `angle` radians anticlockwise from the right, with how
+/// brightly, fading toward the beam's edges and its far end
+///
+/// Only the half of the sweep above the beacon lights anything; the rest points down into
+/// the city. Rows count double so the beam keeps its width whichever way it points.
+fn sweep_cells(source: (u16, u16), angle: f32, term_width: u16) -> Vec<(u16, u16, f32)> {
+    let (sx, sy) = (source.0 as i32, source.1 as i32);
+    let reach = SWEEP_REACH as i32;
+    let mut cells = Vec::new();
+    for y in (sy - reach / 2).max(0)..sy {
+        for x in (sx - reach).max(0)..(sx + reach + 1).min(term_width as i32) {
+            let (dx, dy) = ((x - sx) as f32, ((sy - y) * 2) as f32);
+            let distance = dx.hypot(dy);
+            if distance > SWEEP_REACH {
+                continue;
+            }
+            let heading = dy.atan2(dx);
+            let off = (heading - angle).rem_euclid(std::f32::consts::TAU);
+            let off = off.min(std::f32::consts::TAU - off);
+            if off <= SWEEP_SPREAD {
+                let weight = (1.0 - distance / SWEEP_REACH) * (1.0 - off / SWEEP_SPREAD);
+                cells.push((x as u16, y as u16, weight));
+            }
+        }
+    }
+    cells
+}
+
+/// Draws the beacon and the beam it sweeps across the sky, into cells nothing else is in
+fn draw_sweep(frame: &mut Frame, scene: &Scene, angle: f32) {
+    let Some(source) = sweep_source(scene) else {
+        return;
+    };
+    for (x, y, weight) in sweep_cells(source, angle, scene.width) {
+        if frame.get(x, y).is_none() {
+            let color = lerp_color(Color::Black, SWEEP_COLOR, weight * SWEEP_STRENGTH);
+            let ch = if weight > 0.5 { '░' } else { '·' };
+            frame.put(Layer::Sky, x, y, ch, apply_fog(color, scene.fog));
+        }
+    }
+    frame.put(Layer::Solid, source.0, source.1, '*', SWEEP_COLOR);
+}
+
+/// Vertical offset of the aurora's band at a column, in rows from `AURORA_ROW`
+///
+/// The waves are gentle enough that neighbouring columns differ by less than a row.
+fn aurora_offset(x: u16, phase: f32) -> f32 {
+    AURORA_WAVES
+        .iter()
+        .map(|&(amplitude, freq, speed)| amplitude * (x as f32 * freq + phase * speed).sin())
+        .sum()
+}
+
+/// Draws the aurora as a wavy band, green at its lower edge fading to purple at the top
+fn draw_aurora(frame: &mut Frame, phase: f32, term_width: u16, fog: f32) {
+    for x in 0..term_width {
+        let center = (AURORA_ROW + aurora_offset(x, phase)).round() as i32;
+        for row in 0..AURORA_THICKNESS {
+            let y = center + row as i32 - (AURORA_THICKNESS / 2) as i32;
+            if y < 0 {
+                continue;
+            }
+            let t = 1.0 - row as f32 / (AURORA_THICKNESS - 1) as f32;
+            let ch = if row == AURORA_THICKNESS / 2 {
+                '▒'
+            } else {
+                '░'
+            };
+            let color = apply_fog(lerp_color(AURORA_GREEN, AURORA_PURPLE, t), fog);
+            frame.put(Layer::Sky, x, y as u16, ch, color);
+        }
+    }
+}
+
+/// Draws the ferris wheel's stand, rim, spokes, and cabins on the sky layer, so the buildings
+/// in front of it hide its lower part; it stands on the far sidewalk at row `horizon`
+fn draw_ferris_wheel(frame: &mut Frame, wheel: &FerrisWheel, horizon: u16) {
+    let ground_y = horizon as f32;
+    let center = (wheel.x as f32, ground_y - FERRIS_RADIUS - 1.0);
+    let to_cell = |(x, y): (f32, f32)| (x.round().max(0.0) as u16, y.round().max(0.0) as u16);
+    let hub = to_cell(center);
+
+    // Two legs splay out from the hub down to the ground
+    for leg in [-1.0, 1.0] {
+        let foot = to_cell((center.0 + leg * FERRIS_RADIUS, ground_y));
+        let glyph = if leg < 0.0 { '/' } else { '\\' };
+        for (x, y) in bresenham(hub, foot) {
+            frame.put(Layer::Sky, x, y, glyph, FERRIS_COLOR);
+        }
+    }
+    for (x, y) in ferris_cabins(center, FERRIS_RADIUS, 0.0, 48)
+        .into_iter()
+        .map(to_cell)
+    {
+        frame.put(Layer::Sky, x, y, '.', FERRIS_COLOR);
+    }
+    let cabins = ferris_cabins(center, FERRIS_RADIUS, wheel.angle, FERRIS_CABINS);
+    for &cabin in &cabins {
+        let line = bresenham(hub, to_cell(cabin));
+        for &(x, y) in &line[1..line.len().saturating_sub(1)] {
+            frame.put(Layer::Sky, x, y, '·', FERRIS_COLOR);
+        }
+    }
+    for (i, &cabin) in cabins.iter().enumerate() {
+        let (x, y) = to_cell(cabin);
+        let color = FERRIS_CABIN_COLORS[i % FERRIS_CABIN_COLORS.len()];
+        frame.put(Layer::Sky, x, y, 'o', color);
+    }
+    frame.put(Layer::Sky, hub.0, hub.1, '*', FERRIS_COLOR);
+}
+
+/// Builds the rows of a pitched house roof, bottom row first
+///
+/// Each row narrows by one cell per side, with `/` and `\` eaves and a solid fill.
+fn roof_rows(width: u16) -> Vec<String> {
+    let mut rows = Vec::new();
+    for row in 0..3.min(width / 2) {
+        let inner = (width - 2 * row).saturating_sub(2) as usize;
+        rows.push(format!(
+            "{}/{}\\",
+            " ".repeat(row as usize),
+            "█".repeat(inner)
+        ));
+    }
+    rows
+}
+
+/// Draws the snow lying on the near lane, deeper drifts in denser glyphs
+fn draw_road_snow(frame: &mut Frame, road_snow: &[f32], road: &RoadLayout) {
+    let y = road.near_lane;
+    for (x, &depth) in road_snow.iter().enumerate() {
+        if depth <= 0.0 {
+            continue;
+        }
+        let level = (depth * ROAD_SNOW_CHARS.len() as f32) as usize;
+        let ch = ROAD_SNOW_CHARS[level.min(ROAD_SNOW_CHARS.len() - 1)];
+        frame.put(Layer::Front, x as u16, y, ch, SNOW_COLOR);
+    }
+}
+
+/// Draws the street at the bottom of the scene: both sidewalks, the lanes and the dashed
+/// lines between them
+fn draw_road(
+    frame: &mut Frame,
+    term_width: u16,
+    road: &RoadLayout,
+    wetness: f32,
+    puddles: &[Puddle],
+) {
+    let color = road_color(wetness);
+    for x in 0..term_width {
+        frame.put(Layer::Front, x, road.far_sidewalk, '▁', SIDEWALK_COLOR);
+        for lane in 0..road.lanes {
+            frame.put(Layer::Front, x, road.lane(lane), '=', color);
+        }
+        for y in road.dividers() {
+            if x % 4 < 2 {
+                frame.put(Layer::Front, x, y, '-', CENTER_LINE_COLOR);
+            } else {
+                frame.put(Layer::Front, x, y, '=', color);
+            }
+        }
+        frame.put(Layer::Front, x, road.near_sidewalk, '▁', SIDEWALK_COLOR);
+    }
+
+    // Puddles gather along the center line, between the wheel tracks
+    for puddle in puddles.iter().filter(|p| p.x < term_width) {
+        frame.put(
+            Layer::Front,
+            puddle.x,
+            road.center_line,
+            PUDDLE_CHARS[puddle.phase as usize],
+            PUDDLE_COLOR,
+        );
+    }
+}
+
+/// Draws the bus stop sign on the near sidewalk, centered on where buses stop
+fn draw_bus_stop(frame: &mut Frame, term_width: u16, road: &RoadLayout) {
+    let y = road.near_sidewalk;
+    let left = bus_stop_x(term_width).saturating_sub(1);
+    for (i, ch) in BUS_STOP_SIGN.chars().enumerate() {
+        frame.put(Layer::Front, left + i as u16, y, ch, BUS_STOP_COLOR);
+    }
+}
+
+/// Draws the manhole covers in the near lane and the steam rising off them, thinning
+/// to lighter glyphs as each puff fades
+fn draw_street_steam(
+    frame: &mut Frame,
+    manholes: &[u16],
+    puffs: &ParticlePool<SteamPuff>,
+    term_width: u16,
+    road: &RoadLayout,
+) {
+    let y = road.near_lane;
+    for &x in manholes.iter().filter(|&&x| x < term_width) {
+        frame.put(Layer::Front, x, y, 'o', MANHOLE_COLOR);
+    }
+    for puff in puffs.iter() {
+        if puff.x < 0.0 || puff.x >= term_width as f32 {
+            continue;
+        }
+        let density = (puff.life * STEAM_CHARS.len() as f32) as usize;
+        let ch = STEAM_CHARS[density.min(STEAM_CHARS.len() - 1)];
+        let color = lerp_color(STEAM_FADED_COLOR, STEAM_COLOR, puff.life);
+        frame.put(Layer::Front, puff.x as u16, puff.y as u16, ch, color);
+    }
+}
+
+/// Covers the near sidewalk, along the bottom of the screen, with the ground texture
+///
+/// The river, when enabled, runs over it in turn.
+fn draw_ground(frame: &mut Frame, term_width: u16, road: &RoadLayout, ground: Ground) {
+    let Some((texture, color)) = ground.texture() else {
+        return;
+    };
+    for y in road.near_sidewalk..frame.height {
+        for x in 0..term_width {
+            let scatter = (x as usize * 7 + y as usize * 3) % texture.len();
+            frame.put(Layer::Front, x, y, texture[scatter], color);
+        }
+    }
+}
+
+/// Mirrors a point in the sky onto the river, before any ripple sway
+///
+/// The sky between the top of the screen and the far sidewalk at row `horizon` is squeezed
+/// into the rows from `river_top` to the bottom, so points high in the sky land deepest in
+/// the water.
+fn reflect_point(x: u16, y: u16, horizon: u16, river_top: u16, term_height: u16) -> (u16, u16) {
+    let horizon = horizon.max(1);
+    let water_rows = term_height - river_top;
+    let depth = (horizon.saturating_sub(y) as u32 * water_rows as u32 / horizon as u32) as u16;
+    (x, (river_top + depth).min(term_height - 1))
+}
+
+/// Sideways sway of a reflection on the given row, easing smoothly within
+/// `REFLECTION_JITTER` columns as the ripples pass
+fn reflection_jitter(y: u16, tick: u32) -> i32 {
+    let angle = tick as f32 * REFLECTION_SWAY_SPEED + y as f32 * 0.9;
+    (REFLECTION_JITTER * angle.sin()).round() as i32
+}
+
+/// Draws dimmed, wavering reflections of the moon and the brightest stars on the river
+fn draw_sky_reflections(
+    frame: &mut Frame,
+    horizon: u16,
+    river_top: u16,
+    stars: &[Star],
+    term_width: u16,
+    term_height: u16,
+    tick: u32,
+) {
+    let dim = |color| lerp_color(WATER_COLOR, color, 0.5);
+    let mut put = |x: u16, y: u16, ch, color| {
+        let x = x as i32 + reflection_jitter(y, tick);
+        if (0..term_width as i32).contains(&x) {
+            frame.put(Layer::Front, x as u16, y, ch, color);
+        }
+    };
+
+    let (left, top) = moon_origin(term_width);
+    let moon_width = MOON_SPRITE
+        .iter()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or(0);
+    let (_, y) = reflect_point(left, top + 1, horizon, river_top, term_height);
+    for col in 0..moon_width as u16 {
+        put(left + col, y, '~', dim(MOON_COLOR));
+    }
+    for star in stars
+        .iter()
+        .filter(|s| s.brightness >= REFLECTION_MIN_BRIGHTNESS)
+    {
+        let (x, y) = reflect_point(star.x, star.y, horizon, river_top, term_height);
+        put(x, y, star.char, dim(star_color(star.brightness)));
+    }
+}
+
+/// Draws the rippling river along the bottom row
+fn draw_water(frame: &mut Frame, term_width: u16, term_height: u16, phase: u8) {
+    for x in 0..term_width {
+        let ch = WATER_PATTERN[(x as usize + phase as usize) % WATER_PATTERN.len()];
+        frame.put(Layer::Front, x, term_height - 1, ch, WATER_COLOR);
+    }
+}
+
+/// Draws a tumbleweed with the glyph for its current spin
+fn draw_tumbleweed(frame: &mut Frame, tumbleweed: &Tumbleweed) {
+    let spin = tumbleweed.spin as u8;
+    let glyph = TUMBLEWEED_FRAMES[spin as usize % TUMBLEWEED_FRAMES.len()];
+    frame.put(
+        Layer::Front,
+        tumbleweed.x as u16,
+        tumbleweed.y - tumbleweed_bounce(spin),
+        glyph,
+        TUMBLEWEED_COLOR,
+    );
+}
+
+/// Draws the boats floating on the river, skipping any that are partly off-screen
+fn draw_boats(frame: &mut Frame, boats: &[Boat], term_width: u16, term_height: u16) {
+    for boat in boats {
+        if boat.x < 0.0 || boat.x as u16 + display_width(boat.glyph) > term_width {
+            continue;
+        }
+        frame.put_str(
+            Layer::Front,
+            boat.x as i32,
+            term_height - 1,
+            boat.glyph,
+            BOAT_COLOR,
+        );
+    }
+}
+
+/// Draws whichever of rain or snow the weather calls for
+fn draw_weather_effects(
+    frame: &mut Frame,
+    raindrops: &[RainDrop],
+    ripples: &ParticlePool<Ripple>,
+    snowflakes: &[Snowflake],
+    weather: WeatherState,
+    rain_glyph: char,
+) {
+    match weather {
+        WeatherState::Snow => {
+            for flake in snowflakes {
+                frame.put(Layer::Front, flake.x, flake.y, flake.char, SNOW_COLOR);
+            }
+        }
+        WeatherState::Rain => {
+            for drop in raindrops {
+                frame.put(Layer::Front, drop.x, drop.y, rain_glyph, RAIN_COLOR);
+            }
+        }
+        WeatherState::Clear => {}
+    }
+    // Splashes center on where the drop landed, widening out either side
+    for ripple in ripples.iter() {
+        let radius = ripple_radius(ripple);
+        let glyph = RIPPLE_GLYPHS[radius];
+        let left = ripple.x as i32 - radius as i32 / 2;
+        frame.put_str(Layer::Front, left, ripple.y, glyph, RAIN_COLOR);
+    }
+}
+
+/// Draws the `--matrix` streams over everything else, each led by a pale glyph
+fn draw_matrix(frame: &mut Frame, streams: &[MatrixStream]) {
+    for stream in streams {
+        for (y, brightness) in matrix_trail(stream, frame.height) {
+            let color = if brightness == 1.0 {
+                MATRIX_HEAD_COLOR
+            } else {
+                lerp_color(MATRIX_FADED_COLOR, MATRIX_COLOR, brightness)
+            };
+            frame.put(Layer::Front, stream.x, y, stream.glyphs[y as usize], color);
+        }
+    }
+}
+
+/// Draws a vehicle, with its motion trail and any flashing lights
+fn draw_vehicle(
+    frame: &mut Frame,
+    vehicle: &Vehicle,
+    term_width: u16,
+    blink_phase: u32,
+    motion_blur: bool,
+) {
+    // Shadow first, so it never darkens the vehicle itself
+    for (x, y) in vehicle_shadow_cells(vehicle, term_width) {
+        frame.tint(x, y, SHADOW_COLOR, VEHICLE_SHADOW_STRENGTH);
+    }
+    if motion_blur {
+        for (x, y, ch, intensity) in motion_trail_cells(vehicle, term_width) {
+            frame.put(
+                Layer::Front,
+                x,
+                y,
+                ch,
+                trail_color(vehicle.color, intensity),
+            );
+        }
+    }
+    frame.put_str(
+        Layer::Front,
+        vehicle.x as i32,
+        vehicle.y,
+        vehicle.glyph(),
+        vehicle.color,
+    );
+
+    if vehicle.flashing {
+        // One light on each side of the vehicle, swapping colors every phase
+        let (left, right) = flash_colors(blink_phase);
+        let left_x = vehicle.x as i32 - 1;
+        let right_x = vehicle.x as i32 + display_width(vehicle.glyph()) as i32;
+        for (x, color) in [(left_x, left), (right_x, right)] {
+            if x >= 0 && x < term_width as i32 {
+                frame.put(Layer::Front, x as u16, vehicle.y, '•', color);
+            }
+        }
+    }
+}
+
+/// Cells of the road below a vehicle its shadow falls on, one per column of the vehicle,
+/// shifted away from the moon and clipped to the screen
+fn vehicle_shadow_cells(vehicle: &Vehicle, term_width: u16) -> Vec<(u16, u16)> {
+    let left = vehicle.x as i32 - VEHICLE_SHADOW_OFFSET as i32;
+    let width = display_width(vehicle.glyph()) as i32;
+    (left..left + width)
+        .filter(|&x| x >= 0 && x < term_width as i32)
+        .map(|x| (x as u16, vehicle.y + 1))
+        .collect()
+}
+
+/// Cells of the fading trail left behind a fast vehicle, nearest (brightest) first
+///
+/// The trail repeats the vehicle's lead glyph, the one facing its direction of travel, and
+/// cells falling outside the screen are dropped.
+fn motion_trail_cells(vehicle: &Vehicle, term_width: u16) -> Vec<(u16, u16, char, f32)> {
+    let speed = vehicle.speed.abs();
+    if speed < MOTION_BLUR_SPEED {
+        return Vec::new();
+    }
+    let len = if speed >= MOTION_BLUR_FULL_SPEED {
+        2
+    } else {
+        1
+    };
+    let rightward = vehicle.speed > 0.0;
+    let lead = if rightward {
+        vehicle.glyph().chars().last()
+    } else {
+        vehicle.glyph().chars().next()
+    };
+    let Some(lead) = lead else {
+        return Vec::new();
+    };
+    // A wide glyph's copies sit a whole glyph apart so they don't overlap each other
+    let stride = display_width(&lead.to_string()) as i32;
+    let left = vehicle.x as i32;
+    let right = left + display_width(vehicle.glyph()) as i32;
+    MOTION_TRAIL_FADE[..len]
+        .iter()
+        .enumerate()
+        .map(|(i, &intensity)| {
+            let x = if rightward {
+                left - stride * (i as i32 + 1)
+            } else {
+                right + stride * i as i32
+            };
+            (x, intensity)
+        })
+        .filter(|&(x, _)| x >= 0 && x + stride <= term_width as i32)
+        .map(|(x, intensity)| (x as u16, vehicle.y, lead, intensity))
+        .collect()
+}
+
+/// The vehicle's color dimmed toward the dark road for a trail cell of the given intensity
+fn trail_color(color: Color, intensity: f32) -> Color {
+    lerp_color(ROAD_COLOR, color, intensity)
+}
+
+/// Approximate RGB value of a color, so named terminal colors can be blended too
+fn ansi_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::Black => (0, 0, 0),
+        Color::DarkGrey => (128, 128, 128),
+        Color::Red => (255, 0, 0),
+        Color::DarkRed => (128, 0, 0),
+        Color::Green => (0, 255, 0),
+        Color::DarkGreen => (0, 128, 0),
+        Color::Yellow => (255, 255, 0),
+        Color::DarkYellow => (128, 128, 0),
+        Color::Blue => (0, 0, 255),
+        Color::DarkBlue => (0, 0, 128),
+        Color::Magenta => (255, 0, 255),
+        Color::DarkMagenta => (128, 0, 128),
+        Color::Cyan => (0, 255, 255),
+        Color::DarkCyan => (0, 128, 128),
+        Color::Grey => (192, 192, 192),
+        _ => (255, 255, 255),
+    }
+}
+
+/// Whether the sun is setting, lighting the sky up behind the skyline
+fn is_dusk(time_of_day: f32) -> bool {
+    DUSK_HOURS.contains(&time_of_day.rem_euclid(24.0))
+}
+
+/// Whether the time of day is dark enough for vehicles to run their lights
+fn is_night(time_of_day: f32) -> bool {
+    let tod = time_of_day.rem_euclid(24.0);
+    !(6.0..19.0).contains(&tod)
+}
+
+/// Cells lit by a vehicle's headlights (ahead of it) and taillight (behind it)
+///
+/// Cells falling outside the screen are dropped.
+fn vehicle_light_cells(vehicle: &Vehicle, term_width: u16) -> Vec<(u16, u16, Color)> {
+    let left = vehicle.x as i32;
+    let right = left + display_width(vehicle.glyph()) as i32;
+    let cells = if vehicle.speed > 0.0 {
+        [
+            (right, HEADLIGHT_COLOR),
+            (right + 1, HEADLIGHT_COLOR),
+            (left - 1, TAILLIGHT_COLOR),
+        ]
+    } else {
+        [
+            (left - 1, HEADLIGHT_COLOR),
+            (left - 2, HEADLIGHT_COLOR),
+            (right, TAILLIGHT_COLOR),
+        ]
+    };
+    cells
+        .into_iter()
+        .filter(|&(x, _)| x >= 0 && x < term_width as i32)
+        .map(|(x, color)| (x as u16, vehicle.y, color))
+        .collect()
+}
+
+/// Draws headlight beams and taillights for every vehicle
+fn draw_vehicle_lights(frame: &mut Frame, vehicles: &[Vehicle], term_width: u16) {
+    for vehicle in vehicles {
+        for (x, y, color) in vehicle_light_cells(vehicle, term_width) {
+            frame.put(Layer::Front, x, y, '·', color);
+        }
+    }
+}
+
+/// Colors of the left and right emergency lights for a blink phase
+fn flash_colors(blink_phase: u32) -> (Color, Color) {
+    if blink_phase.is_multiple_of(2) {
+        (Color::Red, Color::Blue)
+    } else {
+        (Color::Blue, Color::Red)
+    }
+}
+
+/// Number of terminal columns a string occupies, counting emoji as two cells wide
+fn display_width(s: &str) -> u16 {
+    s.chars()
+        .map(|c| {
+            if c as u32 >= 0x1F000 || WIDE_SYMBOLS.contains(&c) {
+                2
+            } else {
+                1
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use rand::rngs::ThreadRng;
+
+    /// Test that create_stars_with_count creates the correct number of stars
+    #[test]
+    fn test_create_stars_with_count() {
+        let mut rng = ThreadRng::default();
+        let stars = create_stars_with_count(80, 24, &mut rng, 10, StarDistribution::Uniform);
+        assert_eq!(stars.len(), 10);
+
+        // Verify all stars are within the specified bounds
+        for star in &stars {
+            assert!(star.x < 80);
+            assert!(star.y < 24 / 2); // Stars only in the top half of the screen
+            assert!(STAR_CHARS.contains(&star.char));
+        }
+    }
+
+    /// Test that twinkle brightness stays in range and stars start at different phases
+    #[test]
+    fn test_star_brightness_in_range() {
+        for i in 0..=100 {
+            let phase = std::f32::consts::TAU * i as f32 / 100.0;
+            let brightness = star_brightness(phase);
+            assert!((0.0..=1.0).contains(&brightness));
+        }
+
+        let mut rng = ThreadRng::default();
+        let stars = create_stars_with_count(80, 24, &mut rng, 10, StarDistribution::Uniform);
+        assert!(stars.iter().any(|s| s.phase != stars[0].phase));
+    }
+
+    /// Test that the line algorithm yields a connected path including both endpoints
+    #[test]
+    fn test_bresenham_endpoint_inclusive() {
+        assert_eq!(
+            bresenham((0, 0), (4, 2)),
+            vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+        );
+        assert_eq!(
+            bresenham((3, 5), (3, 2)),
+            vec![(3, 5), (3, 4), (3, 3), (3, 2)]
+        );
+        assert_eq!(bresenham((2, 2), (2, 2)), vec![(2, 2)]);
+
+        let path = bresenham((10, 1), (0, 4));
+        assert_eq!(path.first(), Some(&(10, 1)));
+        assert_eq!(path.last(), Some(&(0, 4)));
+        for pair in path.windows(2) {
+            let step_x = (pair[0].0 as i32 - pair[1].0 as i32).abs();
+            let step_y = (pair[0].1 as i32 - pair[1].1 as i32).abs();
+            assert!(step_x <= 1 && step_y <= 1);
+        }
+    }
+
+    /// Test that a satellite moves along its velocity and despawns off-screen
+    #[test]
+    fn test_satellite_moves_and_despawns() {
+        let mut satellite = Some(Satellite {
+            x: 10.0,
+            y: 2.0,
+            vx: 0.5,
+            vy: 0.25,
+        });
+        update_satellite(&mut satellite, 80, 24, 1.0);
+        let sat = satellite.as_ref().unwrap();
+        assert_eq!((sat.x, sat.y), (10.5, 2.25));
+
+        satellite.as_mut().unwrap().x = 79.8;
+        update_satellite(&mut satellite, 80, 24, 1.0);
+        assert!(satellite.is_none());
+    }
+
+    /// Test that easing converges on the target monotonically without overshooting
+    #[test]
+    fn test_ease_converges_without_overshoot() {
+        let mut value = 0.0;
+        for _ in 0..100 {
+            let next = ease(value, 1.0, WINDOW_FADE_RATE);
+            assert!(next >= value && next <= 1.0);
+            value = next;
+        }
+        assert_eq!(value, 1.0);
+        assert_eq!(ease(0.5, 0.0, 1.0), 0.0);
+    }
+
+    /// Test that create_buildings creates buildings with valid properties
+    #[test]
+    fn test_create_buildings() {
+        let mut rng = ThreadRng::default();
+        let buildings = create_buildings(
+            80,
+            24,
+            &BUILDING_COLORS,
+            WINDOW_ON_COLOR,
+            &BuildingLayout::default(),
+            &mut rng,
+        );
+
+        for building in &buildings {
+            assert!(building.width >= 5 && building.width < 15);
+            assert!(building.height >= 5 && building.height < 24 - 5);
+            assert!(building.x < 80);
+            assert!(BUILDING_COLORS.iter().any(|&base| within_jitter(
+                building.color,
+                base,
+                COLOR_JITTER
+            )));
+        }
+    }
+
+    /// Test that spawn_vehicle creates valid vehicles
+    #[test]
+    fn test_spawn_vehicle() {
+        let mut rng = ThreadRng::default();
+        let vehicle = spawn_vehicle(80, &road_layout(24), &VEHICLE_STYLES, &mut rng);
+
+        // Check that the vehicle properties are from our valid set
+        let valid_styles: Vec<&str> = VEHICLE_STYLES
+            .iter()
+            .map(|(style, _, _)| &**style)
+            .collect();
+        assert!(valid_styles.contains(&&*vehicle.style));
+
+        let valid_colors: Vec<Color> = VEHICLE_STYLES.iter().map(|(_, color, _)| *color).collect();
+        assert!(valid_colors.contains(&vehicle.color));
+
+        // Cruising speed is the style's own, give or take the jitter
+        let (_, _, base_speed) = VEHICLE_STYLES
+            .iter()
+            .find(|(style, _, _)| *style == vehicle.style)
+            .unwrap();
+        let ratio = vehicle.target_speed / base_speed;
+        assert!((ratio - 1.0).abs() <= VEHICLE_SPEED_JITTER + 1e-4);
+    }
+
+    /// Test that vehicles spawn with appropriate y positions
+    #[test]
+    fn test_spawn_vehicle_y_position() {
+        let mut rng = ThreadRng::default();
+        let road = road_layout(24);
+        let vehicle1 = spawn_vehicle(80, &road_layout(24), &VEHICLE_STYLES, &mut rng);
+        let vehicle2 = spawn_vehicle(80, &road_layout(24), &VEHICLE_STYLES, &mut rng);
+
+        // Vehicles drive in one lane or the other
+        assert!(vehicle1.y == road.near_lane || vehicle1.y == road.far_lane);
+        assert!(vehicle2.y == road.near_lane || vehicle2.y == road.far_lane);
+    }
+
+    /// Test that a terminal below the minimum size is rejected
+    #[test]
+    fn test_check_min_size_too_small() {
+        let err = check_min_size(20, 6).unwrap_err();
+        assert!(err.to_string().contains("got 20x6"));
+    }
+
+    /// Test that a terminal at or above the minimum size is accepted
+    #[test]
+    fn test_check_min_size_adequate() {
+        assert!(check_min_size(MIN_WIDTH, MIN_HEIGHT).is_ok());
+        assert!(check_min_size(80, 24).is_ok());
+    }
+
+    /// Test that a zero or absurd interval is clamped and a normal one left untouched
+    #[test]
+    fn test_validate_interval_clamps() {
+        assert_eq!(validate_interval(0, &mut Vec::new()), MIN_INTERVAL_MS);
+        assert_eq!(
+            validate_interval(u64::MAX, &mut Vec::new()),
+            MAX_INTERVAL_MS
+        );
+        assert_eq!(validate_interval(50, &mut Vec::new()), 50);
+    }
+
+    /// Test that out-of-range spawn rates are clamped
+    #[test]
+    fn test_validate_spawn_rate_clamps() {
+        assert_eq!(validate_spawn_rate(-0.5, &mut Vec::new()), 0.0);
+        assert_eq!(validate_spawn_rate(2.0, &mut Vec::new()), 1.0);
+        assert_eq!(validate_spawn_rate(0.25, &mut Vec::new()), 0.25);
+    }
+
+    /// Test that a well-formed vehicle line with a named color parses
+    #[test]
+    fn test_parse_vehicle_line_valid() {
+        let (glyph, color, speed) = parse_vehicle_line("<=o=>,red,-3.5").unwrap();
+        assert_eq!(glyph, "<=o=>");
+        assert_eq!(color, Color::Red);
+        assert_eq!(speed, -3.5);
+    }
+
+    /// Test that hex colors are accepted in vehicle lines
+    #[test]
+    fn test_parse_vehicle_line_hex_color() {
+        let (glyph, color, _) = parse_vehicle_line("[a,b],#ff8000,2").unwrap();
+        assert_eq!(glyph, "[a,b]");
+        assert_eq!(
+            color,
+            Color::Rgb {
+                r: 255,
+                g: 128,
+                b: 0
+            }
+        );
+    }
+
+    /// Test that malformed vehicle lines are rejected
+    #[test]
+    fn test_parse_vehicle_line_malformed() {
+        assert!(parse_vehicle_line("car-only").is_err());
+        assert!(parse_vehicle_line("car,notacolor,2").is_err());
+        assert!(parse_vehicle_line("car,red,fast").is_err());
+        assert!(parse_vehicle_line("car,#12345,2").is_err());
+    }
+
+    /// Test that emergency vehicles flash alternating red and blue lights
+    #[test]
+    fn test_flashing_vehicle_alternates_colors() {
+        let mut rng = ThreadRng::default();
+        let ambulance = spawn_vehicle(
+            80,
+            &road_layout(24),
+            &[("🚑".into(), Color::Red, -4.0)],
+            &mut rng,
+        );
+        assert!(ambulance.flashing);
+        let car = spawn_vehicle(
+            80,
+            &road_layout(24),
+            &[("o-o-o".into(), Color::Cyan, 4.0)],
+            &mut rng,
+        );
+        assert!(!car.flashing);
+
+        for phase in 0..4 {
+            let (left, right) = flash_colors(phase);
+            let (next_left, next_right) = flash_colors(phase + 1);
+            assert!([Color::Red, Color::Blue].contains(&left));
+            assert_ne!(left, right);
+            assert_eq!((next_left, next_right), (right, left));
+        }
+    }
+
+    /// Test that a rightward vehicle has headlights ahead and a taillight behind
+    #[test]
+    fn test_vehicle_light_cells_rightward() {
+        let mut rng = ThreadRng::default();
+        let mut vehicle = spawn_vehicle(
+            80,
+            &road_layout(24),
+            &[("o-o-o".into(), Color::Cyan, 4.0)],
+            &mut rng,
+        );
+        vehicle.x = 10.0;
+        let cells = vehicle_light_cells(&vehicle, 80);
+        let right_edge = 10 + display_width(&vehicle.style);
+        for (x, y, color) in cells {
+            assert_eq!(y, vehicle.y);
+            match color {
+                HEADLIGHT_COLOR => assert!(x >= right_edge),
+                TAILLIGHT_COLOR => assert!(x < 10),
+                _ => panic!("unexpected light color"),
+            }
+        }
+
+        // Lights off the left edge are clamped away
+        vehicle.x = 0.0;
+        assert!(
+            vehicle_light_cells(&vehicle, 80)
+                .iter()
+                .all(|&(_, _, c)| c != TAILLIGHT_COLOR)
+        );
+        assert!(is_night(22.0) && !is_night(12.0));
+    }
+
+    /// Test that the crane jib wraps within a full turn and the hook stays in range
+    #[test]
+    fn test_crane_jib_wraps_and_hook_in_range() {
+        let mut rng = ThreadRng::default();
+        let buildings = create_buildings(
+            80,
+            24,
+            &BUILDING_COLORS,
+            WINDOW_ON_COLOR,
+            &BuildingLayout::default(),
+            &mut rng,
+        );
+        let mut crane = create_crane(&buildings, 80, 24, &mut rng);
+        crane.jib_angle = std::f32::consts::TAU - CRANE_TURN_SPEED / 2.0;
+        let (hook_min, hook_max) = crane_hook_range(crane.height, 24);
+        for _ in 0..1000 {
+            update_crane(&mut crane, 24, 1.0);
+            assert!((0.0..std::f32::consts::TAU).contains(&crane.jib_angle));
+            assert!(crane.hook_y >= hook_min && crane.hook_y <= hook_max);
+        }
+    }
+
+    /// Test that boats advance by their speed and the water pattern cycles
+    #[test]
+    fn test_boats_advance_and_water_cycles() {
+        let mut boats = vec![Boat {
+            x: 10.0,
+            speed: 0.5,
+            glyph: "[___]",
+        }];
+        update_boats(&mut boats, 80, 1.0);
+        assert_eq!(boats[0].x, 10.5);
+
+        let mut phase = 0;
+        for step in 1..=WATER_PATTERN.len() {
+            phase = advance_water_phase(phase);
+            assert_eq!(phase == 0, step == WATER_PATTERN.len());
+        }
+    }
+
+    /// Test that a large cloud covers exactly its sprite's rows and columns
+    #[test]
+    fn test_large_cloud_bounding_box() {
+        for shape in LARGE_CLOUD_SPRITES {
+            let cloud = Cloud {
+                x: 10.0,
+                y: 2,
+                shape,
+                speed: 1.0,
+                phase: 0.0,
+            };
+            let cells = cloud_cells(&cloud, 24 / 4);
+            let min_x = cells.iter().map(|c| c.0).min().unwrap();
+            let max_x = cells.iter().map(|c| c.0).max().unwrap();
+            let min_y = cells.iter().map(|c| c.1).min().unwrap();
+            let max_y = cells.iter().map(|c| c.1).max().unwrap();
+            assert_eq!((min_x, min_y), (10, 2));
+            assert_eq!((max_x - min_x + 1) as u16, cloud_width(shape));
+            assert_eq!((max_y - min_y + 1) as usize, shape.len());
+        }
+    }
+
+    /// Test that clicks are routed to the road, the sky, or nothing by row
+    #[test]
+    fn test_click_target_routing() {
+        assert_eq!(click_target(21, 24, &road_layout(24)), ClickTarget::Road);
+        assert_eq!(click_target(20, 24, &road_layout(24)), ClickTarget::Road);
+        assert_eq!(click_target(22, 24, &road_layout(24)), ClickTarget::Road);
+        assert_eq!(click_target(0, 24, &road_layout(24)), ClickTarget::Sky);
+        assert_eq!(click_target(11, 24, &road_layout(24)), ClickTarget::Sky);
+        assert_eq!(click_target(15, 24, &road_layout(24)), ClickTarget::Nothing);
+        assert_eq!(click_target(23, 24, &road_layout(24)), ClickTarget::Nothing);
+    }
+
+    /// Test that a balloon crossing the right edge wraps back to a negative x
+    #[test]
+    fn test_balloon_wraps_around() {
+        let mut balloons = vec![Balloon {
+            x: 80.0,
+            y: 2,
+            color: BALLOON_COLORS[0],
+            frame: 0.0,
+        }];
+        update_balloons(&mut balloons, 80, 1.0);
+        assert!(balloons[0].x < 0.0);
+    }
+
+    /// Test that houses are short and rendered with a pitched roof
+    #[test]
+    fn test_house_height_and_roof() {
+        let mut rng = ThreadRng::default();
+        let houses: Vec<Building> = (0..20)
+            .flat_map(|_| {
+                create_buildings(
+                    80,
+                    24,
+                    &BUILDING_COLORS,
+                    WINDOW_ON_COLOR,
+                    &BuildingLayout::default(),
+                    &mut rng,
+                )
+            })
+            .filter(|b| b.kind == BuildingKind::House)
+            .collect();
+        assert!(!houses.is_empty());
+        for house in &houses {
+            assert!(HOUSE_HEIGHTS.contains(&house.height));
+            assert!(HOUSE_WIDTHS.contains(&house.width));
+        }
+
+        // The last building may run off the right edge, clipping its roof
+        let house = houses.iter().position(|h| h.x + h.width <= 80).unwrap();
+        let mut frame = Frame::new(80, 24);
+        draw_buildings(
+            &mut frame,
+            &houses[house..=house],
+            24,
+            0.0,
+            false,
+            &Theme::Night.palette(),
+            false,
+        );
+        let rendered: String = frame.cells.iter().flatten().map(|cell| cell.ch).collect();
+        assert!(rendered.contains('/') && rendered.contains('\\'));
+    }
+
+    /// Test that no fog leaves colors untouched and full fog yields the fog color
+    #[test]
+    fn test_apply_fog_extremes() {
+        assert_eq!(apply_fog(STAR_COLOR, 0.0), STAR_COLOR);
+        assert_eq!(apply_fog(STAR_COLOR, 1.0), FOG_COLOR);
+        assert_eq!(apply_fog(BUILDING_COLORS[0], 1.0), FOG_COLOR);
+    }
+
+    /// Test that rainbow arc points are symmetric about the center and on screen
+    #[test]
+    fn test_rainbow_arc_points_symmetric() {
+        let points = rainbow_arc_points(40, 20, 10, 80, 24);
+        assert!(!points.is_empty());
+        for &(x, y) in &points {
+            assert!(x < 80 && y <= 20);
+            assert!(points.contains(&(80 - x, y)));
+        }
+        assert!(points.contains(&(40, 10)));
+        assert!(points.contains(&(20, 20)) && points.contains(&(60, 20)));
+    }
+
+    /// Test that the rainbow appears when rain stops and then fades out
+    #[test]
+    fn test_rainbow_fades_after_rain() {
+        let mut rainbow = Rainbow {
+            was_raining: true,
+            alpha: 0.0,
+        };
+        update_rainbow(&mut rainbow, false, 0.1);
+        assert_eq!(rainbow.alpha, 1.0);
+        update_rainbow(&mut rainbow, false, RAINBOW_FADE_SECS);
+        assert_eq!(rainbow.alpha, 0.0);
+    }
+
+    /// Test that a soaked road is darker than a dry one
+    #[test]
+    fn test_road_darker_when_wet() {
+        let brightness = |c: Color| match c {
+            Color::Rgb { r, g, b } => r as u32 + g as u32 + b as u32,
+            _ => unreachable!(),
+        };
+        assert!(brightness(road_color(1.0)) < brightness(road_color(0.0)));
+        assert_eq!(road_color(0.0), ROAD_COLOR);
+
+        // Once the rain stops the road dries back out
+        let wetness = update_road_wetness(1.0, false, ROAD_DRY_SECS);
+        assert_eq!(road_color(wetness), ROAD_COLOR);
+    }
+
+    /// Test that heavy rain has more and faster drops than light rain
+    #[test]
+    fn test_rain_params_heavy_exceeds_light() {
+        let (light_count, light_speeds) = rain_params(RainIntensity::Light);
+        let (heavy_count, heavy_speeds) = rain_params(RainIntensity::Heavy);
+        assert!(heavy_count > light_count);
+        assert!(heavy_speeds.start > light_speeds.start);
+        assert!(heavy_speeds.end > light_speeds.end);
+
+        let mut rng = ThreadRng::default();
+        let drops = create_raindrops(80, 24, &mut rng, RainIntensity::Heavy, None);
+        assert_eq!(drops.len(), heavy_count as usize);
+        assert!(drops.iter().all(|d| heavy_speeds.contains(&d.speed)));
+    }
+
+    /// Test that the lit fraction peaks in the evening and is near zero at midday
+    #[test]
+    fn test_target_lit_fraction_peaks_in_evening() {
+        let evening = target_lit_fraction(20.0);
+        for hour in 0..24 {
+            assert!(target_lit_fraction(hour as f32) <= evening);
+        }
+        assert!(target_lit_fraction(12.0) < 0.05);
+        assert_eq!(target_lit_fraction(24.0), target_lit_fraction(0.0));
+    }
+
+    /// Test that windows drift toward the target lit fraction
+    #[test]
+    fn test_update_windows_approaches_target() {
+        let mut rng = ThreadRng::default();
+        let mut buildings = create_buildings(
+            80,
+            24,
+            &BUILDING_COLORS,
+            WINDOW_ON_COLOR,
+            &BuildingLayout::default(),
+            &mut rng,
+        );
+        for _ in 0..2000 {
+            update_windows(&mut buildings, 12.0, &mut rng);
+        }
+        let total: usize = buildings
+            .iter()
+            .flat_map(|b| &b.windows)
+            .map(Vec::len)
+            .sum();
+        let lit = buildings
+            .iter()
+            .flat_map(|b| b.windows.iter().flatten())
+            .filter(|w| w.on)
+            .count();
+        assert!((lit as f32) < total as f32 * 0.15);
+    }
+
+    /// Test that TV windows are a minority and render in a blue tint
+    #[test]
+    fn test_tv_windows_minority_and_blue() {
+        let mut rng = ThreadRng::default();
+        let buildings: Vec<Building> = (0..10)
+            .flat_map(|_| {
+                create_buildings(
+                    80,
+                    24,
+                    &BUILDING_COLORS,
+                    WINDOW_ON_COLOR,
+                    &BuildingLayout::default(),
+                    &mut rng,
+                )
+            })
+            .collect();
+        let windows: Vec<&Window> = buildings
+            .iter()
+            .flat_map(|b| b.windows.iter().flatten())
+            .collect();
+        let tv_count = windows.iter().filter(|w| w.tv).count();
+        assert!(tv_count * 2 < windows.len());
+
+        for flicker in [0.3, 0.6, 1.0] {
+            let tv = Window {
+                on: true,
+                tv: true,
+                flicker,
+                brightness: 1.0,
+                lamp: WINDOW_ON_COLOR,
+            };
+            let Color::Rgb { r, g, b } = window_color(&tv) else {
+                panic!("TV windows should render in RGB");
+            };
+            assert!(b > r && b > g);
+        }
+    }
+
+    /// Test that building windows are created with the right pattern
+    #[test]
+    fn test_building_windows_pattern() {
+        let mut rng = ThreadRng::default();
+        let buildings = create_buildings(
+            80,
+            24,
+            &BUILDING_COLORS,
+            WINDOW_ON_COLOR,
+            &BuildingLayout::default(),
+            &mut rng,
+        );
+
+        // Verify buildings have windows created
+        for building in &buildings {
+            assert!(!building.windows.is_empty());
+        }
+    }
+
+    /// Test that a preset fills in its settings while explicit flags still win
+    #[test]
+    fn test_preset_expansion() {
+        let args = parse_args(["city", "--preset", "rainy-night"]);
+        assert!(args.rain);
+        assert_eq!(args.theme, Theme::Night);
+        assert_eq!(args.rain_intensity, RainIntensity::Heavy);
+
+        let args = parse_args([
+            "city",
+            "--preset",
+            "rainy-night",
+            "--rain-intensity",
+            "light",
+        ]);
+        assert_eq!(args.rain_intensity, RainIntensity::Light);
+
+        let args = parse_args(["city", "--preset", "snowy-morning", "--theme", "neon"]);
+        assert!(args.snow && !args.rain);
+        assert_eq!(args.theme, Theme::Neon);
+    }
+
+    /// Test that `--rain=false` switches off the otherwise default rain
+    #[test]
+    fn test_rain_can_be_disabled() {
+        assert!(parse_args(["city"]).rain);
+        assert!(parse_args(["city", "--rain"]).rain);
+        assert!(!parse_args(["city", "--rain=false"]).rain);
+    }
+
+    /// Test that the terminal can be torn down and set up again repeatedly, as happens
+    /// across suspend and resume
+    #[cfg(unix)]
+    /// Test that leaving and entering the alternate screen can be repeated, ending back on
+    /// the main screen
+    #[test]
+    fn test_screen_teardown_and_resetup_repeat() {
+        let mut out = Vec::new();
+        for _ in 0..3 {
+            enter_screen(&mut out, true).unwrap();
+            leave_screen(&mut out, true).unwrap();
+        }
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.matches("\x1b[?1049h").count(), 3);
+        assert_eq!(text.matches("\x1b[?1049l").count(), 3);
+        assert!(text.ends_with("\x1b[?1049l"));
+    }
+
+    /// Test that a fast rightward vehicle leaves a trail to its left that fades with distance
+    #[test]
+    fn test_motion_trail_fades_behind_vehicle() {
+        let vehicle = Vehicle {
+            x: 10.0,
+            y: 20,
+            style: "o-o-o".into(),
+            color: Color::Cyan,
+            speed: 5.0,
+            target_speed: 5.0,
+            flashing: false,
+            is_plow: false,
+            stop_timer: Duration::ZERO,
+        };
+        let cells = motion_trail_cells(&vehicle, 80);
+        assert_eq!(cells.len(), 2);
+        assert_eq!((cells[0].0, cells[0].2), (9, 'o'));
+        assert_eq!((cells[1].0, cells[1].2), (8, 'o'));
+        assert!(cells[0].3 > cells[1].3);
+
+        // Trails are clipped at the screen edge, and slow vehicles leave none
+        let at_edge = Vehicle { x: 1.0, ..vehicle };
+        assert_eq!(motion_trail_cells(&at_edge, 80).len(), 1);
+        let slow = Vehicle {
+            speed: 2.0,
+            ..at_edge
+        };
+        assert!(motion_trail_cells(&slow, 80).is_empty());
+    }
+
+    /// Test that a star behind a building is hidden while one in open sky is drawn
+    #[test]
+    fn test_sky_is_occluded_by_buildings() {
+        let building = Building {
+            kind: BuildingKind::MidRise,
+            x: 10,
+            width: 6,
+            height: 8,
+            color: BUILDING_COLORS[0],
+            windows: Vec::new(),
+            window_layout: BLOCK_WINDOWS,
+            has_antenna: false,
+            antenna_char: '|',
+            roof_snow: 0.0,
+            beacon: None,
+        };
+        let star = |x| Star {
+            x,
+            y: 18,
+            char: '*',
+            brightness: 1.0,
+            phase: 0.0,
+            exact: (x as f32, 18.0),
+        };
+        let mut frame = Frame::new(80, 24);
+        draw_buildings(
+            &mut frame,
+            &[building],
+            24,
+            0.0,
+            false,
+            &Theme::Night.palette(),
+            false,
+        );
+        draw_stars(&mut frame, &[star(12), star(30)], 0.0);
+        assert_eq!(frame.get(12, 18).map(|cell| cell.ch), Some('█'));
+        assert_eq!(frame.get(30, 18).map(|cell| cell.ch), Some('*'));
+    }
+
+    /// Test that the ground fills exactly the rows below the road with its texture
+    #[test]
+    fn test_draw_ground_fills_rows_below_road() {
+        let mut frame = Frame::new(40, 20);
+        draw_ground(&mut frame, 40, &road_layout(20), Ground::Grass);
+        for y in 0..20 {
+            for x in 0..40 {
+                let cell = frame.get(x, y);
+                if y >= 19 {
+                    let cell = cell.expect("ground rows should be filled");
+                    assert!(GRASS_TEXTURE.contains(&cell.ch));
+                    assert_eq!(cell.color, GRASS_COLOR);
+                } else {
+                    assert!(cell.is_none());
+                }
+            }
+        }
+
+        let mut frame = Frame::new(40, 20);
+        draw_ground(&mut frame, 40, &road_layout(20), Ground::None);
+        assert!(frame.cells.iter().all(Option::is_none));
+    }
+
+    /// Test that the wind eases toward its gust without ever jumping more than the max delta
+    #[test]
+    fn test_wind_eases_toward_target() {
+        let mut rng = ThreadRng::default();
+        let mut wind = Wind {
+            current: 0.0,
+            target: 3.0,
+            change_timer: 1000.0,
+        };
+        for _ in 0..500 {
+            let before = wind.current;
+            update_wind(&mut wind, 3.0, 0.1, &mut rng);
+            let step = wind.current - before;
+            assert!((0.0..=WIND_MAX_DELTA + 1e-4).contains(&step));
+        }
+        assert_eq!(wind.current, 3.0);
+
+        // Once the gust timer runs out, new targets stay within the strongest gust
+        wind.change_timer = 0.0;
+        for _ in 0..500 {
+            let before = wind.current;
+            update_wind(&mut wind, 1.5, 1.0, &mut rng);
+            assert!(wind.target.abs() <= 1.5);
+            assert!((wind.current - before).abs() <= WIND_MAX_DELTA + 1e-4);
+        }
+    }
+
+    /// Test that disabled entities are never created and stay absent through updates
+    #[test]
+    fn test_disable_flags_keep_entities_empty() {
+        let args = parse_args([
+            "city",
+            "--no-vehicles",
+            "--no-clouds",
+            "--no-stars",
+            "--spawn-rate",
+            "1.0",
+        ]);
+        let mut rng = ThreadRng::default();
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        for _ in 0..100 {
+            assert!(scene.vehicles.is_empty());
+            assert!(scene.clouds.is_empty());
+            assert!(scene.stars.is_empty());
+            scene.update(Duration::from_millis(50), &mut rng);
+        }
+
+        // Without the flags the same setup fills all three
+        let args = parse_args(["city", "--spawn-rate", "1.0"]);
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        scene.update(Duration::from_millis(50), &mut rng);
+        assert!(!scene.vehicles.is_empty());
+        assert!(!scene.clouds.is_empty());
+        assert!(!scene.stars.is_empty());
+    }
+
+    /// Test that a short bench run completes and times both stages
+    #[test]
+    fn test_bench_reports_timings() {
+        let args = parse_args(["city", "--bench", "5"]);
+        let report = run_bench(&args, &VEHICLE_STYLES, 5).unwrap();
+        assert_eq!(report.frames, 5);
+        assert!(report.update > Duration::ZERO);
+        assert!(report.render > Duration::ZERO);
+        assert!(report.to_string().contains("frames/sec"));
+    }
+
+    /// Test that tumbleweeds only appear in strong wind and roll the way it blows
+    #[test]
+    fn test_tumbleweed_spawns_in_high_wind_and_follows_it() {
+        let mut rng = ThreadRng::default();
+        for _ in 0..2000 {
+            assert!(spawn_tumbleweed(TUMBLEWEED_WIND, 80, &road_layout(24), &mut rng).is_none());
+            assert!(spawn_tumbleweed(-0.5, 80, &road_layout(24), &mut rng).is_none());
+        }
+
+        let mut tumbleweed = (0..10_000)
+            .find_map(|_| spawn_tumbleweed(-2.0, 80, &road_layout(24), &mut rng))
+            .expect("strong wind should eventually blow in a tumbleweed");
+        assert_eq!(tumbleweed.x, 79.0);
+        for _ in 0..10 {
+            let before = tumbleweed.x;
+            update_tumbleweed(&mut tumbleweed, -2.0, 1.0);
+            assert!(tumbleweed.x < before);
+        }
+    }
+
+    /// Test that a petal flutters both left and right on its way down
+    #[test]
+    fn test_petals_flutter_both_ways() {
+        let mut rng = ThreadRng::default();
+        let mut petals = create_petals(80, 24, &mut rng, 1);
+        petals[0].y = 0.0;
+        let (mut left, mut right) = (false, false);
+        for _ in 0..100 {
+            let (x, y) = (petals[0].x, petals[0].y);
+            update_petals(&mut petals, 80, 24, 0.0, 1.0, &mut rng);
+            let dx = petals[0].x - x;
+            // Ignore the step where the petal wraps around an edge
+            if dx.abs() < 1.0 {
+                left |= dx < 0.0;
+                right |= dx > 0.0;
+            }
+            assert!(petals[0].y > y || petals[0].y == 0.0);
+        }
+        assert!(left && right);
+    }
+
+    /// Test that petals can't be combined with snow
+    #[test]
+    fn test_petals_conflict_with_snow() {
+        assert!(Args::try_parse_from(["city", "--petals", "--snow"]).is_err());
+    }
+
+    /// Text of one frame row, with undrawn cells as spaces
+    fn row_text(frame: &Frame, y: u16) -> String {
+        (0..frame.width)
+            .map(|x| frame.get(x, y).map_or(' ', |cell| cell.ch))
+            .collect()
+    }
+
+    /// Test that the help overlay lists every key binding exactly once and fits the screen
+    #[test]
+    fn test_help_overlay_lists_each_binding_once() {
+        let mut frame = Frame::new(80, 24);
+        draw_help_overlay(&mut frame, &KEY_BINDINGS);
+        let text: String = (0..24).map(|y| row_text(&frame, y) + "\n").collect();
+        for (_, action) in KEY_BINDINGS {
+            assert_eq!(text.matches(action).count(), 1, "{action}");
+        }
+
+        // A tiny screen clips the box instead of overflowing it
+        let mut frame = Frame::new(10, 3);
+        draw_help_overlay(&mut frame, &KEY_BINDINGS);
+        assert_eq!(row_text(&frame, 0), "+--------+");
+        assert_eq!(row_text(&frame, 2), "+--------+");
+    }
+
+    /// Test that the run time limit never triggers at 0 and otherwise triggers once elapsed
+    #[test]
+    fn test_should_exit_by_time() {
+        let now = Instant::now();
+        let Some(ten_secs_ago) = now.checked_sub(Duration::from_secs(10)) else {
+            return; // Clock too close to its epoch to look back
+        };
+        assert!(!should_exit_by_time(ten_secs_ago, 0));
+        assert!(!should_exit_by_time(now, 5));
+        assert!(!should_exit_by_time(ten_secs_ago, 60));
+        assert!(should_exit_by_time(ten_secs_ago, 5));
+    }
+
+    /// Test that contrails stay within their cap and the nav light alternates colors
+    #[test]
+    fn test_plane_contrail_cap_and_blink() {
+        let mut rng = ThreadRng::default();
+        let mut planes = create_planes(80, 24, &mut rng, 3);
+        for _ in 0..1000 {
+            update_planes(&mut planes, 80, 1.0);
+            for plane in &planes {
+                assert!(plane.trail.len() <= CONTRAIL_MAX_LEN);
+            }
+        }
+
+        let colors: Vec<Color> = (0..4)
+            .map(|i| nav_light_color(i * PLANE_BLINK_FRAMES))
+            .collect();
+        assert_eq!(colors, [Color::Red, Color::Green, Color::Red, Color::Green]);
+    }
+
+    /// Test that ferris wheel cabins sit on the rim, evenly spaced from the starting angle
+    #[test]
+    fn test_ferris_cabins_evenly_spaced() {
+        let center = (40.0, 10.0);
+        let cabins = ferris_cabins(center, 5.0, 0.3, 8);
+        assert_eq!(cabins.len(), 8);
+        let angles: Vec<f32> = cabins
+            .iter()
+            .map(|&(x, y)| {
+                let (dx, dy) = ((x - center.0) / FERRIS_ASPECT, y - center.1);
+                assert!(((dx * dx + dy * dy).sqrt() - 5.0).abs() < 1e-4);
+                dy.atan2(dx)
+            })
+            .collect();
+        assert!((angles[0] - 0.3).abs() < 1e-4);
+        for pair in angles.windows(2) {
+            let step = (pair[1] - pair[0]).rem_euclid(std::f32::consts::TAU);
+            assert!((step - std::f32::consts::TAU / 8.0).abs() < 1e-4);
+        }
+    }
+
+    /// Records the order it is updated and drawn in
+    struct Probe {
+        z: u8,
+        log: std::rc::Rc<std::cell::RefCell<Vec<(&'static str, u8)>>>,
+    }
+
+    impl Entity for Probe {
+        fn update(&mut self, _ctx: &SceneCtx, _rng: &mut dyn RngCore) {
+            self.log.borrow_mut().push(("update", self.z));
+        }
+
+        fn draw(&self, _frame: &mut Frame, _ctx: &SceneCtx) {
+            self.log.borrow_mut().push(("draw", self.z));
+        }
+
+        fn z(&self) -> u8 {
+            self.z
+        }
+    }
+
+    /// Test that registered entities are updated and drawn in ascending z order
+    #[test]
+    fn test_entities_update_and_draw_in_z_order() {
+        let args = parse_args(["city", "--no-vehicles", "--no-stars", "--no-clouds"]);
+        let mut rng = ThreadRng::default();
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        let log = std::rc::Rc::default();
+        for z in [PLANE_Z, CLOUD_Z, VEHICLE_Z] {
+            let log = std::rc::Rc::clone(&log);
+            scene.add_entity(Box::new(Probe { z, log }));
+        }
+        assert!(scene.entities.is_sorted_by_key(|e| e.z()));
+
+        scene.update(Duration::from_millis(50), &mut rng);
+        draw_scene(&mut Vec::new(), &scene).unwrap();
+        assert_eq!(
+            *log.borrow(),
+            [
+                ("update", CLOUD_Z),
+                ("update", PLANE_Z),
+                ("update", VEHICLE_Z),
+                ("draw", CLOUD_Z),
+                ("draw", PLANE_Z),
+                ("draw", VEHICLE_Z),
+            ]
+        );
+    }
+
+    /// Test that the listings name every preset and theme exactly once
+    #[test]
+    fn test_listings_cover_every_name_once() {
+        fn check<T: ValueEnum>() {
+            let listing = value_listing::<T>();
+            let names: Vec<&str> = listing.lines().collect();
+            let unique: std::collections::HashSet<&str> = names.iter().copied().collect();
+            assert_eq!(unique.len(), names.len());
+            for variant in T::value_variants() {
+                let name = variant.to_possible_value().unwrap();
+                assert!(names.contains(&name.get_name()));
+            }
+        }
+        check::<Preset>();
+        check::<Theme>();
+    }
+
+    /// Test that roof snow piles up every frame while snowing and melts once it stops
+    #[test]
+    fn test_roof_snow_grows_while_snowing_and_melts_after() {
+        let args = parse_args(["city", "--snow"]);
+        let mut rng = ThreadRng::default();
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        let depth = |scene: &Scene| scene.buildings[0].roof_snow;
+        for _ in 0..20 {
+            let before = depth(&scene);
+            scene.update(Duration::from_millis(500), &mut rng);
+            assert!(depth(&scene) > before);
+        }
 
-    let mut stdout = setup_terminal()?;
+        scene.weather = WeatherState::Clear;
+        for _ in 0..20 {
+            let before = depth(&scene);
+            scene.update(Duration::from_millis(500), &mut rng);
+            assert!(depth(&scene) < before || depth(&scene) == 0.0);
+        }
+        assert_eq!(depth(&scene), 0.0);
+    }
 
-    // Ensure terminal is restored on panic or exit
-    let (width, height) = terminal::size().map_err(|e| {
-        io::Error::other(
-            format!("Failed to get terminal size: {}", e),
-        )
-    })?;
-    let mut rng = ThreadRng::default();
-    let mut buildings = create_buildings(width, height, &mut rng);
-    let mut vehicles = create_vehicles(height);
-    let mut stars = create_stars_with_count(width, height, &mut rng, args.stars);
-    let mut raindrops = if args.rain {
-        create_raindrops_with_count(width, height, &mut rng, args.raindrops)
-    } else {
-        Vec::new()
-    };
-    let mut snowflakes = if args.snow {
-        create_snowflakes_with_count(width, height, &mut rng, args.snowflakes)
-    } else {
-        Vec::new()
-    };
-    let mut clouds = create_clouds_with_count(width, height, &mut rng, args.clouds);
+    /// Test that the aurora's band flows smoothly from one column to the next
+    #[test]
+    fn test_aurora_offset_is_continuous() {
+        for step in 0..200 {
+            let phase = step as f32 * AURORA_SPEED * 7.0;
+            for x in 0..199 {
+                let jump = (aurora_offset(x + 1, phase) - aurora_offset(x, phase)).abs();
+                assert!(jump < 1.0, "jump of {jump} at column {x}");
+            }
+        }
+    }
 
-    // FPS tracking
-    let mut frame_count = 0;
-    let mut last_fps_update = Instant::now();
-    let mut fps = 0.0;
+    /// Test that steam puffs start on their manhole's row and rise from there
+    #[test]
+    fn test_street_steam_rises_from_manholes() {
+        let mut rng = ThreadRng::default();
+        let mut puffs = ParticlePool::default();
+        while puffs.is_empty() {
+            update_street_steam(&mut puffs, &[20], 22, 0.0, 1.0, &mut rng);
+        }
+        assert!(puffs.iter().all(|p| p.y == 22.0 && p.x == 20.0));
 
-    let result = (|| -> io::Result<()> {
-        let mut running = true;
-        while running {
-            let frame_start = Instant::now();
+        update_street_steam(&mut puffs, &[], 22, 0.0, 1.0, &mut rng);
+        assert!(!puffs.is_empty());
+        assert!(puffs.iter().all(|p| p.y < 22.0));
+    }
 
-            if event::poll(Duration::from_millis(args.interval))? {
-                if let Event::Key(_) = event::read()? {
-                    running = false;
-                }
-            }
+    /// Test that resizing up and back down keeps every entity and keeps it on screen
+    #[test]
+    fn test_resize_keeps_entities_in_bounds() {
+        let args = parse_args(["city", "--rain", "--spawn-rate", "1.0"]);
+        let mut rng = StdRng::seed_from_u64(BENCH_SEED);
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        for _ in 0..50 {
+            scene.update(Duration::from_millis(50), &mut rng);
+        }
+        let counts = |s: &Scene| (s.stars.len(), s.clouds.len(), s.raindrops.len());
+        let expected = counts(&scene);
 
-            if rng.random_bool(0.1) {
-                vehicles.push(spawn_vehicle(width, height, &mut rng));
+        for (width, height) in [(160, 48), (60, 20)] {
+            scene.resize(width, height, &mut rng);
+            assert_eq!(counts(&scene), expected);
+            assert!(!scene.vehicles.is_empty());
+            assert!(scene.buildings.iter().all(|b| b.x < width));
+            assert!(scene.stars.iter().all(|s| s.x < width && s.y < height));
+            assert!(
+                scene
+                    .clouds
+                    .iter()
+                    .all(|c| c.x < width as f32 && c.y < height)
+            );
+            assert!(scene.raindrops.iter().all(|d| d.x < width && d.y < height));
+            for vehicle in &scene.vehicles {
+                assert!(vehicle.x < width as f32);
+                let road = road_layout(height);
+                assert!(vehicle.y == road.near_lane || vehicle.y == road.far_lane);
             }
+            draw_scene(&mut Vec::new(), &scene).unwrap();
+        }
+    }
 
-            update_windows(&mut buildings, &mut rng);
-            update_vehicles(&mut vehicles, width);
-            update_stars(&mut stars, &mut rng);
-            if args.rain {
-                update_raindrops(&mut raindrops, width, height, &mut rng);
-            }
-            if args.snow {
-                update_snowflakes(&mut snowflakes, width, height, &mut rng);
+    /// Test that a reflection keeps its source column and lands in the water, swaying only
+    /// a little from frame to frame
+    #[test]
+    fn test_sky_reflection_mirrors_into_water() {
+        for (x, y) in [(0, 0), (30, 5), (79, 20)] {
+            let (rx, ry) = reflect_point(x, y, road_layout(24).far_sidewalk, 20, 24);
+            assert_eq!(rx, x);
+            assert!((20..24).contains(&ry));
+        }
+        for tick in 0..200 {
+            let jitter = reflection_jitter(3, tick);
+            assert!(jitter.abs() <= REFLECTION_JITTER as i32);
+            assert!((jitter - reflection_jitter(3, tick + 1)).abs() <= 1);
+        }
+    }
+
+    /// Test that toggling the weather creates the precipitation lazily and clearing it
+    /// empties whatever was falling
+    #[test]
+    fn test_weather_toggles_create_and_clear_precipitation() {
+        let args = parse_args(["city", "--rain=false"]);
+        let mut rng = ThreadRng::default();
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        assert_eq!(scene.weather, WeatherState::Clear);
+        assert!(scene.raindrops.is_empty() && scene.snowflakes.is_empty());
+
+        scene.set_weather(scene.weather.toggle_rain(), &args, &mut rng);
+        assert_eq!(scene.weather, WeatherState::Rain);
+        assert!(!scene.raindrops.is_empty());
+
+        scene.set_weather(scene.weather.toggle_snow(), &args, &mut rng);
+        assert_eq!(scene.weather, WeatherState::Snow);
+        assert!(scene.raindrops.is_empty() && !scene.snowflakes.is_empty());
+        assert_eq!(scene.weather.toggle_snow(), WeatherState::Clear);
+
+        scene.set_weather(WeatherState::Rain, &args, &mut rng);
+        scene.set_weather(WeatherState::Clear, &args, &mut rng);
+        assert!(scene.raindrops.is_empty() && scene.snowflakes.is_empty());
+    }
+
+    /// Test that the frame hash is the same for identical scenes and changes with the
+    /// rendered output
+    #[test]
+    fn test_frame_hash_is_stable_and_sensitive() {
+        let args = parse_args(["city"]);
+        assert_eq!(
+            frame_hash_after(&args, &VEHICLE_STYLES, 20),
+            frame_hash_after(&args, &VEHICLE_STYLES, 20)
+        );
+
+        let mut rng = StdRng::seed_from_u64(BENCH_SEED);
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        let before = frame_hash(&scene);
+        scene.buildings[0].color = Color::Rgb { r: 1, g: 2, b: 3 };
+        assert_ne!(frame_hash(&scene), before);
+    }
+
+    /// Test that a vehicle enters slower than its cruising speed and speeds up toward it
+    #[test]
+    fn test_vehicle_accelerates_toward_target_speed() {
+        let args = parse_args(["city"]);
+        let mut rng = ThreadRng::default();
+        let ctx = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng).ctx();
+        for _ in 0..20 {
+            let mut vehicle = spawn_vehicle(80, &road_layout(24), &VEHICLE_STYLES, &mut rng);
+            assert!(vehicle.speed.abs() < vehicle.target_speed.abs());
+            let mut gap = (vehicle.target_speed - vehicle.speed).abs();
+            for _ in 0..100 {
+                Entity::update(&mut vehicle, &ctx, &mut rng);
+                let new_gap = (vehicle.target_speed - vehicle.speed).abs();
+                assert!(new_gap < gap);
+                gap = new_gap;
             }
-            update_clouds(&mut clouds, width);
+            assert!(gap < vehicle.target_speed.abs() * 0.01);
+        }
+    }
 
-            // Calculate and display FPS
-            frame_count += 1;
-            let elapsed = last_fps_update.elapsed();
-            if elapsed.as_secs() >= 1 {
-                fps = frame_count as f64 / elapsed.as_secs_f64();
-                frame_count = 0;
-                last_fps_update = Instant::now();
+    /// Test that traffic peaks in the morning and evening rush and is light otherwise
+    #[test]
+    fn test_traffic_multiplier_peaks_at_rush_hour() {
+        for rush in [8.0, 18.0] {
+            assert!(traffic_multiplier(rush) > 2.0);
+        }
+        for quiet in [3.0, 14.0] {
+            assert!(traffic_multiplier(quiet) < 1.0);
+        }
+        assert!(traffic_multiplier(3.0) < traffic_multiplier(14.0));
+    }
 
-                // Display FPS for debugging (only when not in terminal)
-                // In a terminal screensaver, we typically don't show FPS overlay
+    /// Test that an explicit seed is kept and a random one is remembered once chosen
+    #[test]
+    fn test_choose_seed_is_stored() {
+        let mut args = parse_args(["city", "--seed", "42"]);
+        assert_eq!(choose_seed(&mut args), 42);
+
+        let mut args = parse_args(["city"]);
+        let seed = choose_seed(&mut args);
+        assert_eq!(args.seed, Some(seed));
+        assert_eq!(choose_seed(&mut args), seed);
+    }
+
+    impl<T> ParticlePool<T> {
+        fn len(&self) -> usize {
+            self.slots.len() - self.free.len()
+        }
+
+        fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        /// Number of slots allocated, live or free
+        fn capacity(&self) -> usize {
+            self.slots.len()
+        }
+    }
+
+    /// Test that a pool refills freed slots instead of growing while particles churn
+    #[test]
+    fn test_particle_pool_reuses_slots() {
+        let mut pool = ParticlePool::default();
+        for round in 0..1000 {
+            for i in 0..10 {
+                pool.spawn(round * 10 + i);
             }
+            pool.retain(|&p| p % 3 == 0 && p > round * 10 - 20);
+            assert!(pool.capacity() <= 30);
+        }
+        assert!(pool.len() <= pool.capacity());
 
-            draw_scene(
-                &mut stdout,
-                &buildings,
-                &vehicles,
-                &stars,
-                &raindrops,
-                &snowflakes,
-                &clouds,
-                width,
-                height,
-                args.snow,
-            )?;
+        pool.clear();
+        let capacity = pool.capacity();
+        for i in 0..capacity {
+            pool.spawn(i as i32);
+        }
+        assert_eq!(pool.capacity(), capacity);
+        assert_eq!(pool.len(), capacity);
+    }
 
-            // Calculate frame time for FPS display purposes
-            let frame_time = frame_start.elapsed();
-            let target_frame_time = Duration::from_millis(args.interval);
-            if frame_time < target_frame_time {
-                std::thread::sleep(target_frame_time - frame_time);
+    /// Test that at dusk buildings turn near-black while lit windows keep their warm color
+    #[test]
+    fn test_dusk_draws_building_silhouettes() {
+        assert!(is_dusk(18.5));
+        assert!(!is_dusk(21.0) && !is_dusk(12.0));
+
+        let lit = Window {
+            on: true,
+            tv: false,
+            flicker: 1.0,
+            brightness: 1.0,
+            lamp: WINDOW_ON_COLOR,
+        };
+        let building = Building {
+            kind: BuildingKind::MidRise,
+            x: 10,
+            width: 3,
+            height: 3,
+            color: BUILDING_COLORS[0],
+            windows: vec![vec![lit]],
+            window_layout: BLOCK_WINDOWS,
+            has_antenna: false,
+            antenna_char: '|',
+            roof_snow: 0.0,
+            beacon: None,
+        };
+        let mut frame = Frame::new(80, 24);
+        draw_buildings(
+            &mut frame,
+            &[building],
+            24,
+            0.0,
+            true,
+            &Theme::Night.palette(),
+            false,
+        );
+        let top_y = road_layout(24).far_sidewalk - 3;
+        let body = frame.get(10, top_y).unwrap();
+        assert_eq!((body.ch, body.color), ('█', SILHOUETTE_COLOR));
+        let window = frame.get(11, top_y + 1).unwrap();
+        assert_eq!((window.ch, window.color), ('■', WINDOW_ON_COLOR));
+    }
+
+    /// Test that a snowplow leaves the road it has passed over barer than the rest
+    #[test]
+    fn test_snowplow_clears_a_track() {
+        let args = parse_args(["city", "--snow", "--spawn-rate", "0"]);
+        let mut rng = ThreadRng::default();
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        scene.road_snow.fill(1.0);
+        let mut plow = spawn_plow(80, &road_layout(24), &mut rng);
+        plow.x = 10.0;
+        plow.speed = PLOW_SPEED;
+        plow.target_speed = PLOW_SPEED;
+        scene.vehicles.push(plow);
+        for _ in 0..30 {
+            scene.update(Duration::from_millis(50), &mut rng);
+        }
+        for x in 10..20 {
+            assert!(scene.road_snow[x] < scene.road_snow[60]);
+        }
+    }
+
+    /// Test that a wide terminal is letterboxed to a centered rectangle of the aspect
+    #[test]
+    fn test_fit_aspect_centers_inner_rect() {
+        let aspect = parse_aspect("2:1").unwrap();
+        assert_eq!(
+            fit_aspect(200, 30, aspect),
+            Rect {
+                x: 70,
+                y: 0,
+                width: 60,
+                height: 30,
+            }
+        );
+        assert_eq!(
+            fit_aspect(60, 100, aspect),
+            Rect {
+                x: 0,
+                y: 35,
+                width: 60,
+                height: 30,
             }
+        );
+        assert!(parse_aspect("2:0").is_err() && parse_aspect("wide").is_err());
+    }
+
+    /// Test that two birds flying too close together are pushed apart
+    #[test]
+    fn test_flock_separates_close_birds() {
+        let bird = |x| Bird {
+            x,
+            y: 3.0,
+            vx: 0.0,
+            vy: 0.0,
+        };
+        let mut birds = [bird(10.0), bird(10.5)];
+        update_flock(&mut birds, 1.0..8.0, 1.0);
+        assert!(birds[1].x - birds[0].x > 0.5);
+    }
+
+    /// Test that a new flock starts on screen and stays in the scene as it sets off
+    #[test]
+    fn test_flock_sets_off_from_the_edge() {
+        let mut rng = ThreadRng::default();
+        let mut birds = create_flock(80, 24, 8, &mut rng);
+        assert_eq!(birds.len(), 8);
+        assert!(birds.iter().all(|b| (0.0..80.0).contains(&b.x)));
+        update_flock(&mut birds, flock_band(24), 1.0);
+        assert!(birds.iter().any(|b| (0.0..80.0).contains(&b.x)));
+    }
+
+    /// Test that wider gaps leave more empty columns and an inverted width range is fixed
+    #[test]
+    fn test_building_gaps_and_width_validation() {
+        let mut rng = ThreadRng::default();
+        let mut empty_columns = |gap_max| {
+            let layout = BuildingLayout {
+                gap_max,
+                ..BuildingLayout::default()
+            };
+            (0..50)
+                .map(|_| {
+                    let buildings = create_buildings(
+                        80,
+                        24,
+                        &BUILDING_COLORS,
+                        WINDOW_ON_COLOR,
+                        &layout,
+                        &mut rng,
+                    );
+                    (0..80)
+                        .filter(|&x| !buildings.iter().any(|b| (b.x..b.x + b.width).contains(&x)))
+                        .count()
+                })
+                .sum::<usize>()
+        };
+        assert!(empty_columns(12) > empty_columns(1));
+        assert_eq!(empty_columns(0), 0);
+
+        assert_eq!(validate_building_widths(10, 4, &mut Vec::new()), (10, 10));
+        assert_eq!(
+            validate_building_widths(1, 8, &mut Vec::new()),
+            (MIN_BUILDING_WIDTH, 8)
+        );
+        assert_eq!(validate_building_widths(5, 14, &mut Vec::new()), (5, 14));
+    }
+
+    /// Test that a storm brings more and faster clouds, stronger gusts and rain
+    #[test]
+    fn test_storm_speeds_up_clouds_and_wind() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let args = Args::parse_from(["city-screensaver", "--wind", "0.5"]);
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        let calm_speeds: Vec<f32> = scene.clouds.iter().map(|c| c.speed).collect();
+        let calm_gust = scene.max_gust(&args);
+
+        scene.set_storm(true, &args, &mut rng);
+        assert!(scene.clouds.len() > calm_speeds.len());
+        for (cloud, calm) in scene.clouds.iter().zip(&calm_speeds) {
+            assert!(cloud.speed > *calm);
         }
-        Ok(())
-    })();
+        assert!(scene.max_gust(&args) > calm_gust);
+        assert_eq!(scene.weather, WeatherState::Rain);
+        assert_eq!(scene.rain_intensity, RainIntensity::Heavy);
 
-    // Always restore terminal
-    if let Err(e) = restore_terminal(&mut stdout) {
-        eprintln!("Error restoring terminal: {}", e);
+        scene.set_storm(false, &args, &mut rng);
+        assert_eq!(scene.clouds.len(), calm_speeds.len());
+        assert_eq!(scene.max_gust(&args), calm_gust);
+        assert_eq!(scene.rain_intensity, args.rain_intensity);
     }
 
-    result
-}
+    /// Test that vehicles turn off only as they cross the intersection, at about the turn chance
+    #[test]
+    fn test_vehicles_turn_at_intersection_within_chance() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut vehicle = spawn_vehicle(80, &road_layout(24), &VEHICLE_STYLES, &mut rng);
+        vehicle.speed = 1.0;
+        vehicle.x = 40.5;
+        vehicle.y = road_layout(24).near_lane;
+        // Only the frame it crosses the intersection counts
+        assert!(!turns_at_intersection(&vehicle, 40.2, 40.0, 1.0, &mut rng));
+        assert!(turns_at_intersection(&vehicle, 39.5, 40.0, 1.0, &mut rng));
+        assert!(!turns_at_intersection(&vehicle, 39.5, 40.0, 0.0, &mut rng));
 
-fn create_buildings(term_width: u16, term_height: u16, rng: &mut ThreadRng) -> Vec<Building> {
-    let mut buildings = Vec::new();
-    let mut x = 0;
+        let trials = 10_000;
+        let turned = (0..trials)
+            .filter(|_| turns_at_intersection(&vehicle, 39.5, 40.0, 0.3, &mut rng))
+            .count();
+        let rate = turned as f64 / trials as f64;
+        assert!((0.27..0.33).contains(&rate), "turned {rate} of the time");
 
-    while x < term_width {
-        let width = rng.random_range(5..15);
-        let height = rng.random_range(5..(term_height - 5));
-        let color = BUILDING_COLORS[rng.random_range(0..BUILDING_COLORS.len())];
-        let mut windows = Vec::new();
+        let turned_on =
+            spawn_turning_vehicle(&vehicle, 40.0, &road_layout(24), &VEHICLE_STYLES, &mut rng)
+                .expect("the default styles drive both ways");
+        assert!(turned_on.speed < 0.0);
+        assert_ne!(turned_on.y, vehicle.y);
+        assert_eq!(turned_on.x, 40.0);
+    }
 
-        for y in 1..height - 1 {
-            let mut row = Vec::new();
-            for wx in 1..width - 1 {
-                if (y % 2 != 0) && (wx % 2 != 0) {
-                    row.push(Window {
-                        on: rng.random_bool(0.3),
-                    });
+    /// Test that a scene with nothing moving reports no change once its animations are gone
+    #[test]
+    fn test_still_scene_is_not_dirty() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let args = Args::parse_from([
+            "city-screensaver",
+            "--rain=false",
+            "--no-stars",
+            "--no-clouds",
+            "--no-vehicles",
+        ]);
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        assert!(scene.update(Duration::from_millis(50), &mut rng));
+
+        scene.planes.clear();
+        scene.balloons.clear();
+        scene.road_wetness = 0.0;
+        for building in &mut scene.buildings {
+            building.windows.clear();
+        }
+        assert!(!scene.update(Duration::from_millis(50), &mut rng));
+    }
+
+    /// Test that lamp-lit windows leave the scene still between the times one of them fades,
+    /// while a lit TV changes it whenever its flicker shows
+    #[test]
+    fn test_settled_windows_are_not_dirty() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let args = Args::parse_from([
+            "city-screensaver",
+            "--rain=false",
+            "--no-stars",
+            "--no-clouds",
+            "--no-vehicles",
+        ]);
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        scene.planes.clear();
+        scene.balloons.clear();
+        scene.road_wetness = 0.0;
+        // One building's worth, so there are stretches with none of them fading
+        for building in &mut scene.buildings[1..] {
+            building.windows.clear();
+        }
+        fn windows(scene: &mut Scene) -> impl Iterator<Item = &mut Window> {
+            scene
+                .buildings
+                .iter_mut()
+                .flat_map(|b| &mut b.windows)
+                .flatten()
+        }
+        for window in windows(&mut scene) {
+            window.tv = false;
+        }
+        assert!(windows(&mut scene).next().is_some());
+        let changed: Vec<bool> = (0..100)
+            .map(|_| scene.update(Duration::from_millis(50), &mut rng))
+            .collect();
+        assert!(changed.contains(&false), "windows never settled");
+
+        let tv = windows(&mut scene).next().unwrap();
+        tv.tv = true;
+        tv.on = true;
+        tv.brightness = 1.0;
+        assert!(scene.update(Duration::from_millis(50), &mut rng));
+    }
+
+    /// Test that the street's rows run top to bottom in order and fit on the screen
+    #[test]
+    fn test_road_layout_rows_are_ordered_and_on_screen() {
+        for height in [MIN_HEIGHT, 24, 60] {
+            let road = road_layout(height);
+            let rows = [
+                road.far_sidewalk,
+                road.far_lane,
+                road.center_line,
+                road.near_lane,
+                road.near_sidewalk,
+            ];
+            assert!(rows.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(rows.iter().all(|&row| row < height));
+        }
+    }
+
+    /// Whether every channel of `color` is within `amount` of `base`'s
+    fn within_jitter(color: Color, base: Color, amount: u8) -> bool {
+        let (
+            Color::Rgb { r, g, b },
+            Color::Rgb {
+                r: br,
+                g: bg,
+                b: bb,
+            },
+        ) = (color, base)
+        else {
+            return color == base;
+        };
+        [(r, br), (g, bg), (b, bb)]
+            .iter()
+            .all(|&(c, base)| c.abs_diff(base) <= amount)
+    }
+
+    /// Test that a jittered color stays within the jitter amount of the original
+    #[test]
+    fn test_jitter_color_stays_within_amount() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let bright = Color::Rgb {
+            r: 250,
+            g: 5,
+            b: 128,
+        };
+        for _ in 0..1000 {
+            assert!(within_jitter(
+                jitter_color(bright, 10, &mut rng),
+                bright,
+                10
+            ));
+        }
+        assert_eq!(jitter_color(bright, 0, &mut rng), bright);
+        assert_eq!(jitter_color(Color::Reset, 10, &mut rng), Color::Reset);
+    }
+
+    /// Test that the first flush writes every cell and later ones only the changed cells
+    #[test]
+    fn test_first_flush_writes_every_cell() {
+        let frame = Frame::new(12, 4);
+        let mut out = Vec::new();
+        frame
+            .flush_to(&mut out, Color::Black, None, (12, 4))
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.matches(' ').count(), 12 * 4);
+        assert!(!text.contains("\x1b[2J"), "a full frame needs no clear");
+
+        let mut frame = Frame::new(12, 4);
+        frame.put(Layer::Front, 3, 1, '#', CLOUD_COLOR);
+        let mut out = Vec::new();
+        frame
+            .flush_to(&mut out, Color::Black, None, (12, 4))
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.matches(' ').count(), 12 * 4 - 1);
+        assert!(text.contains('#'));
+
+        // Letterboxed, the margins are written out cell by cell too, rather than cleared
+        let viewport = Rect {
+            x: 3,
+            y: 1,
+            width: 12,
+            height: 4,
+        };
+        let mut out = Vec::new();
+        frame
+            .flush_to(&mut out, Color::Black, Some(viewport), (18, 6))
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.matches(' ').count(), 18 * 6 - 1);
+        assert!(
+            !text.contains("\x1b[2J"),
+            "a letterboxed frame needs no clear"
+        );
+    }
+
+    /// Test that each frame written to `--output` starts with the frame separator
+    #[test]
+    fn test_frames_written_to_output_are_separated() {
+        let args = parse_args(["city"]);
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        let mut out = Vec::new();
+        for _ in 0..2 {
+            scene.update(Duration::from_millis(50), &mut rng);
+            write_frame(&mut out, &scene).unwrap();
+        }
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.matches(FRAME_SEPARATOR).count(), 2);
+        assert!(text.starts_with(FRAME_SEPARATOR));
+    }
+
+    /// Test that the moon's halo is centered on the moon and fades away from it
+    #[test]
+    fn test_moon_halo_is_centered_and_fades_outward() {
+        let center = (40, 10);
+        let cells = moon_halo_cells(center, MOON_HALO_RADIUS);
+        assert!(!cells.is_empty());
+        let n = cells.len() as f32;
+        let mean_x = cells.iter().map(|((x, _), _)| *x as f32).sum::<f32>() / n;
+        let mean_y = cells.iter().map(|((_, y), _)| *y as f32).sum::<f32>() / n;
+        assert!((mean_x - 40.0).abs() < 1e-3 && (mean_y - 10.0).abs() < 1e-3);
+
+        let distance = |(x, y): (u16, u16)| ((x as f32 - 40.0) / 2.0).hypot(y as f32 - 10.0);
+        for &(a, wa) in &cells {
+            assert!(wa > 0.0 && wa < 1.0);
+            for &(b, wb) in &cells {
+                if distance(a) < distance(b) {
+                    assert!(wa > wb);
                 }
             }
-            windows.push(row);
         }
 
-        let has_antenna = rng.random_bool(0.3);
-        let antenna_char = if has_antenna {
-            ANTENNA_CHARS[rng.random_range(0..ANTENNA_CHARS.len())]
-        } else {
-            ' '
+        // Near the corner the halo is cut off rather than wrapping around
+        assert!(
+            moon_halo_cells((1, 1), MOON_HALO_RADIUS)
+                .iter()
+                .all(|((x, y), _)| *x < 1 + 2 * MOON_HALO_RADIUS && *y < 1 + MOON_HALO_RADIUS)
+        );
+    }
+
+    /// Test that sending out a chosen style spawns a vehicle of that style
+    #[test]
+    fn test_spawn_vehicle_style_uses_requested_style() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for (index, (style, color, speed)) in VEHICLE_STYLES.iter().enumerate() {
+            let vehicle =
+                spawn_vehicle_style(80, &road_layout(24), &VEHICLE_STYLES, index, &mut rng);
+            assert_eq!((vehicle.style, vehicle.color), (style.clone(), *color));
+            assert_eq!(vehicle.target_speed.signum(), speed.signum());
+        }
+    }
+
+    /// Test that a cloud bobs a little above and below its row without wandering off
+    #[test]
+    fn test_cloud_bobs_about_its_row() {
+        let mut cloud = Cloud {
+            x: 10.0,
+            y: 3,
+            shape: SMALL_CLOUD_SPRITES[0],
+            speed: 0.0,
+            phase: 0.0,
         };
+        let mut rows = std::collections::HashSet::new();
+        for _ in 0..1000 {
+            update_clouds(std::slice::from_mut(&mut cloud), 80, 0.0, 1.0);
+            let y = cloud_display_y(&cloud, 24 / 4);
+            assert!(y.abs_diff(cloud.y) as f32 <= CLOUD_BOB_AMPLITUDE);
+            rows.insert(y);
+        }
+        assert_eq!(cloud.y, 3);
+        assert!(
+            rows.contains(&2) && rows.contains(&4),
+            "the cloud should bob both ways"
+        );
 
-        buildings.push(Building {
-            x,
-            width,
-            height,
-            color,
-            windows,
-            has_antenna,
-            antenna_char,
-        });
-        x += width + rng.random_range(1..5);
+        // Never below the bottom of the upper quarter
+        cloud.y = 24 / 4;
+        cloud.phase = std::f32::consts::FRAC_PI_2;
+        assert_eq!(cloud_display_y(&cloud, 24 / 4), 24 / 4);
     }
-    buildings
-}
 
-fn create_vehicles(_term_height: u16) -> Vec<Vehicle> {
-    Vec::new()
-}
+    /// Test that panning across a wide city draws only the buildings in view
+    #[test]
+    fn test_panning_shows_only_buildings_in_view() {
+        let building = |x: u16| Building {
+            kind: BuildingKind::MidRise,
+            x,
+            width: 5,
+            height: 5,
+            color: BUILDING_COLORS[0],
+            windows: Vec::new(),
+            window_layout: BLOCK_WINDOWS,
+            has_antenna: false,
+            antenna_char: ' ',
+            roof_snow: 0.0,
+            beacon: None,
+        };
+        let mut city = Frame::new(100, 24);
+        draw_buildings(
+            &mut city,
+            &[building(0), building(50)],
+            24,
+            0.0,
+            false,
+            &Theme::Night.palette(),
+            false,
+        );
 
-fn spawn_vehicle(term_width: u16, term_height: u16, rng: &mut ThreadRng) -> Vehicle {
-    let road_y = term_height - 3;
+        let mut frame = Frame::new(40, 24);
+        frame.pan_over(&city, 45);
+        let base_y = road_layout(24).far_sidewalk - 1;
+        let drawn: Vec<u16> = (0..40)
+            .filter(|&x| frame.get(x, base_y).is_some())
+            .collect();
+        // Only the building at 50 is in view, five columns in from the left edge
+        assert_eq!(drawn, vec![5, 6, 7, 8, 9]);
 
-    let (style, color, speed) = VEHICLE_STYLES[rng.random_range(0..VEHICLE_STYLES.len())];
-    let y = if rng.random_bool(0.5) {
-        road_y
-    } else {
-        road_y - 1
-    };
-    let x = if speed > 0.0 { 0.0 } else { term_width as f32 };
+        // Panning past the end wraps back around to the start of the city
+        let mut frame = Frame::new(40, 24);
+        frame.pan_over(&city, 98);
+        let drawn: Vec<u16> = (0..40)
+            .filter(|&x| frame.get(x, base_y).is_some())
+            .collect();
+        assert_eq!(drawn, vec![2, 3, 4, 5, 6]);
+    }
 
-    Vehicle {
-        x,
-        y,
-        style,
-        color,
-        speed,
+    /// Test that a vehicles file with a bad color is reported as a config error
+    #[test]
+    fn test_bad_vehicles_file_is_a_config_error() {
+        let path = std::env::temp_dir().join(format!("city-vehicles-{}.txt", std::process::id()));
+        fs::write(&path, "car,notacolor,2\n").unwrap();
+        let err = load_vehicle_styles(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        let Error::Config(inner) = &err else {
+            panic!("expected a config error, got {:?}", err);
+        };
+        assert_eq!(inner.kind(), io::ErrorKind::InvalidData);
+        assert!(inner.to_string().contains("unknown color `notacolor`"));
+        assert!(err.to_string().starts_with("invalid configuration"));
+        assert!(std::error::Error::source(&err).is_some());
     }
-}
 
-/// Creates a specified number of stars with random positions and characters
-fn create_stars_with_count(
-    term_width: u16,
-    term_height: u16,
-    rng: &mut ThreadRng,
-    count: u16,
-) -> Vec<Star> {
-    let mut stars = Vec::new();
-    for _ in 0..count {
-        stars.push(Star {
-            x: rng.random_range(0..term_width),
-            y: rng.random_range(0..term_height / 2),
-            char: STAR_CHARS[rng.random_range(0..STAR_CHARS.len())],
-        });
+    /// Test that the drift offset wanders over time but stays within its range
+    #[test]
+    fn test_drift_offset_moves_within_range() {
+        assert_eq!(drift_offset(0.0), (0, 0));
+        let mut offsets = std::collections::HashSet::new();
+        for second in 0..3600 {
+            let (dx, dy) = drift_offset(second as f32);
+            assert!(dx.unsigned_abs() as f32 <= DRIFT_RANGE.0);
+            assert!(dy.unsigned_abs() as f32 <= DRIFT_RANGE.1);
+            offsets.insert((dx, dy));
+        }
+        assert!(
+            offsets.len() > 4,
+            "the picture should wander, not sit still"
+        );
+
+        let mut frame = Frame::new(10, 5);
+        frame.put(Layer::Front, 9, 0, '#', CLOUD_COLOR);
+        let moved = frame.shifted(2, -1);
+        assert_eq!(moved.get(1, 4).map(|cell| cell.ch), Some('#'));
     }
-    stars
-}
 
-/// Creates 50 stars with random positions and characters
-fn create_stars(term_width: u16, term_height: u16, rng: &mut ThreadRng) -> Vec<Star> {
-    create_stars_with_count(term_width, term_height, rng, 50) // Default to 50 for backward compatibility
-}
+    /// Test that every window style keeps its windows inside the building's walls
+    #[test]
+    fn test_window_styles_stay_inside_building() {
+        for style in [WindowStyle::Block, WindowStyle::Dot, WindowStyle::Grid] {
+            for (width, height) in [(3, 3), (4, 6), (7, 9), (10, 10), (13, 20), (16, 31)] {
+                let layout = style.layout(width, height);
+                for wy in 0..layout.rows(height) {
+                    for wx in 0..layout.cols(width) {
+                        let (x, y) = layout.origin(wx, wy);
+                        let (right, bottom) = (x + layout.size - 1, y + layout.size - 1);
+                        assert!(x >= 1 && right <= width - 2, "{style:?} {width}x{height}");
+                        assert!(y >= 1 && bottom <= height - 2, "{style:?} {width}x{height}");
+                    }
+                }
+            }
+        }
+        assert_eq!(WindowStyle::Block.layout(16, 31).size, 2);
+        assert_eq!(WindowStyle::Block.layout(4, 6), BLOCK_WINDOWS);
+    }
 
-fn create_raindrops_with_count(
-    term_width: u16,
-    term_height: u16,
-    rng: &mut ThreadRng,
-    count: u16,
-) -> Vec<RainDrop> {
-    let mut raindrops = Vec::new();
-    for _ in 0..count {
-        raindrops.push(RainDrop {
-            x: rng.random_range(0..term_width),
-            y: rng.random_range(0..term_height),
-            speed: rng.random_range(1..3),
-        });
+    /// Test that the session summary lists each of its metrics
+    #[test]
+    fn test_format_stats_includes_each_metric() {
+        let stats = SessionStats {
+            frames: 600,
+            vehicles_spawned: 42,
+            peak_vehicles: 7,
+            duration: Duration::from_secs(20),
+        };
+        let summary = format_stats(&stats);
+        assert!(summary.contains("frames rendered:  600"));
+        assert!(summary.contains("vehicles spawned: 42"));
+        assert!(summary.contains("peak vehicles:    7"));
+        assert!(summary.contains("average fps:      30.0"));
+        assert!(summary.contains("duration:         20.0s"));
     }
-    raindrops
-}
 
-fn create_raindrops(term_width: u16, term_height: u16, rng: &mut ThreadRng) -> Vec<RainDrop> {
-    create_raindrops_with_count(term_width, term_height, rng, 100) // Default to 100 for backward compatibility
-}
+    /// Test that star drift turns every star the same way about the pivot
+    #[test]
+    fn test_star_drift_turns_stars_the_same_way() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let (width, height) = (120, 40);
+        let pivot = (60.0, road_layout(height).far_sidewalk as f32);
+        let mut stars =
+            create_stars_with_count(width, height, &mut rng, 50, StarDistribution::Uniform);
+        let before: Vec<(f32, f32)> = stars.iter().map(|star| star.exact).collect();
+        for _ in 0..100 {
+            drift_stars(&mut stars, pivot, 0.0005, width, height, &mut rng);
+        }
 
-/// Updates the state of windows in all buildings, randomly toggling them on/off
-fn update_windows(buildings: &mut [Building], rng: &mut ThreadRng) {
-    for building in buildings {
-        for row in &mut building.windows {
-            for window in row {
-                if rng.random_bool(0.01) {
-                    window.on = !window.on;
+        // Bearing of a point from the pivot with rows doubled, anticlockwise on screen
+        let bearing = |(x, y): (f32, f32)| (-(y - pivot.1) * 2.0).atan2(x - pivot.0);
+        let radius = |(x, y): (f32, f32)| (x - pivot.0).hypot((y - pivot.1) * 2.0);
+        let mut turned = 0;
+        for (&start, star) in before.iter().zip(&stars) {
+            // Stars that left the sky were replaced and no longer share a circle
+            if (radius(start) - radius(star.exact)).abs() > 0.01 {
+                continue;
+            }
+            let swept = bearing(star.exact) - bearing(start);
+            assert!((swept - 0.05).abs() < 0.001, "swept {swept}");
+            turned += 1;
+        }
+        assert!(turned > 40);
+    }
+
+    /// Test that clouds sit on the bands and those sharing a band don't overlap
+    #[test]
+    fn test_clouds_on_the_same_band_do_not_overlap() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let clouds = create_clouds_with_count(200, 40, &mut rng, 6, CloudSize::Medium);
+        let bands: Vec<u16> = (0..CLOUD_BANDS)
+            .map(|band| band * 10 / CLOUD_BANDS)
+            .collect();
+        for (i, cloud) in clouds.iter().enumerate() {
+            assert!(bands.contains(&cloud.y));
+            for other in &clouds[i + 1..] {
+                if other.y == cloud.y {
+                    assert!(!clouds_overlap(other, cloud.x, cloud.shape));
                 }
             }
         }
     }
-}
 
-fn update_vehicles(vehicles: &mut Vec<Vehicle>, term_width: u16) {
-    let mut i = 0;
-    while i < vehicles.len() {
-        vehicles[i].x += vehicles[i].speed * 0.1;
+    /// Test that a bus stops at the bus stop, waits there, then pulls away
+    #[test]
+    fn test_bus_waits_at_the_stop_then_pulls_away() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let args = parse_args(["city"]);
+        let scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        let ctx = scene.ctx();
+        let mut bus = spawn_vehicle(
+            80,
+            &road_layout(24),
+            &[(BUS_GLYPH.into(), Color::Green, 2.8)],
+            &mut rng,
+        );
+        bus.speed = bus.target_speed;
+        bus.x = ctx.bus_stop as f32 - 0.1;
+        let original = bus.speed;
 
-        let vehicle_width = vehicles[i].style.len() as f32; // Assuming ASCII chars have width 1
+        bus.update(&ctx, &mut rng);
+        assert_eq!(bus.speed, 0.0);
+        let stopped_at = bus.x;
+        for _ in 1..BUS_STOP_TIME.as_millis() / NOMINAL_FRAME.as_millis() {
+            bus.update(&ctx, &mut rng);
+            assert_eq!(bus.speed, 0.0);
+            assert_eq!(bus.x, stopped_at);
+        }
+        bus.update(&ctx, &mut rng);
+        assert_eq!(bus.speed, original);
 
-        // Remove vehicle if it's off-screen
-        if (vehicles[i].speed > 0.0 && vehicles[i].x > term_width as f32)
-            || (vehicles[i].speed < 0.0 && vehicles[i].x < -vehicle_width)
-        {
-            vehicles.remove(i);
-        } else {
-            i += 1;
+        // Other vehicles drive straight past
+        let mut car = spawn_vehicle(
+            80,
+            &road_layout(24),
+            &[("o-o-o".into(), Color::Cyan, 4.0)],
+            &mut rng,
+        );
+        car.x = ctx.bus_stop as f32 - 0.1;
+        car.update(&ctx, &mut rng);
+        assert!(car.speed > 0.0);
+    }
+
+    /// Perceived brightness of an RGB color, from 0.0 for black to 1.0 for white
+    fn luminance(color: Color) -> f32 {
+        let Color::Rgb { r, g, b } = color else {
+            panic!("expected an RGB color, got {color:?}");
+        };
+        (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) / 255.0
+    }
+
+    /// Test that the high-contrast palette's colors are distinct and far apart in brightness
+    #[test]
+    fn test_high_contrast_palette_is_distinct_and_contrasting() {
+        let palette = Theme::HighContrast.palette();
+        let outline = palette.outline.unwrap();
+        let colors = [
+            palette.sky,
+            palette.buildings[0],
+            palette.window_on,
+            outline,
+        ];
+        for (i, a) in colors.iter().enumerate() {
+            for b in &colors[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+
+        // Sky meets outline, outline meets wall, and wall surrounds windows
+        let neighbours = [
+            (palette.sky, outline),
+            (outline, palette.buildings[0]),
+            (palette.buildings[0], palette.window_on),
+        ];
+        for (a, b) in neighbours {
+            assert!(
+                (luminance(a) - luminance(b)).abs() >= 0.5,
+                "{a:?} next to {b:?}"
+            );
         }
+        assert!(palette.bold);
+        assert!(parse_args(["city", "--high-contrast"]).high_contrast);
     }
-}
 
-fn update_stars(stars: &mut [Star], rng: &mut ThreadRng) {
-    for star in stars {
-        if rng.random_bool(0.05) {
-            star.char = STAR_CHARS[rng.random_range(0..STAR_CHARS.len())];
+    /// Test that a blink timer toggles once per period of the time it is stepped by
+    #[test]
+    fn test_blink_timer_toggles_once_per_period_of_dt() {
+        let mut timer = BlinkTimer::new(Duration::from_millis(200));
+        for _ in 0..37 {
+            timer.advance(Duration::from_millis(30));
         }
+        assert_eq!(timer.toggles, 5); // 1110ms
+        timer.advance(Duration::from_millis(690));
+        assert_eq!(timer.toggles, 9);
+
+        // The scene's emergency lights follow the time it is stepped by, not the clock
+        let mut rng = StdRng::seed_from_u64(7);
+        let args = parse_args(["city", "--no-vehicles"]);
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        for _ in 0..10 {
+            scene.update(FLASH_PERIOD / 2, &mut rng);
+        }
+        assert_eq!(scene.ctx().flash_phase, 5);
     }
-}
 
-fn update_raindrops(
-    raindrops: &mut [RainDrop],
-    term_width: u16,
-    term_height: u16,
-    rng: &mut ThreadRng,
-) {
-    for drop in raindrops {
-        drop.y += drop.speed;
-        if drop.y >= term_height {
-            drop.y = 0;
-            drop.x = rng.random_range(0..term_width);
+    /// Test that a frame twice the nominal length moves a vehicle twice as far
+    #[test]
+    fn test_vehicle_motion_scales_with_dt() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let args = parse_args(["city"]);
+        let scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        let ctx = scene.ctx();
+        let mut car = spawn_vehicle(
+            80,
+            &road_layout(24),
+            &[("o-o-o".into(), Color::Cyan, 4.0)],
+            &mut rng,
+        );
+        car.speed = car.target_speed;
+        car.x = 10.0;
+
+        car.update(&ctx, &mut rng);
+        let short = car.x - 10.0;
+        let long_ctx = SceneCtx {
+            dt: NOMINAL_FRAME * 2,
+            ..ctx
+        };
+        car.update(&long_ctx, &mut rng);
+        let long = car.x - 10.0 - short;
+        assert!(short > 0.0);
+        assert!((long - 2.0 * short).abs() < 1e-4);
+    }
+
+    /// Test that `--depth-layers` adds rows of buildings that get brighter toward the street
+    #[test]
+    fn test_depth_layers_brighten_toward_the_street() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let args = parse_args(["city", "--depth-layers", "3"]);
+        let scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        assert_eq!(scene.backdrops.len(), 2);
+
+        // Layers in the order they are drawn, each averaged over its buildings
+        let brightness = |buildings: &[Building]| {
+            let total: u32 = buildings
+                .iter()
+                .map(|b| {
+                    let Color::Rgb { r, g, b } = b.color else {
+                        panic!("expected an RGB color");
+                    };
+                    r as u32 + g as u32 + b as u32
+                })
+                .sum();
+            total as f32 / buildings.len() as f32
+        };
+        let mut layers: Vec<&[Building]> = scene.backdrops.iter().map(Vec::as_slice).collect();
+        layers.push(&scene.buildings);
+        let levels: Vec<f32> = layers.iter().map(|layer| brightness(layer)).collect();
+        assert!(
+            levels.windows(2).all(|pair| pair[0] < pair[1]),
+            "{levels:?}"
+        );
+
+        // The street's buildings come down to the sidewalk
+        let mut frame = Frame::new(80, 24);
+        draw_buildings(
+            &mut frame,
+            &scene.buildings,
+            24,
+            0.0,
+            false,
+            &scene.palette,
+            false,
+        );
+        let base = road_layout(24).far_sidewalk - 1;
+        for building in scene.buildings.iter().filter(|b| b.x < 80) {
+            let cell = frame.get(building.x, base).unwrap();
+            assert_eq!((cell.ch, cell.color), ('█', building.color));
         }
     }
-}
 
-fn create_snowflakes_with_count(
-    term_width: u16,
-    term_height: u16,
-    rng: &mut ThreadRng,
-    count: u16,
-) -> Vec<Snowflake> {
-    let mut snowflakes = Vec::new();
-    for _ in 0..count {
-        snowflakes.push(Snowflake {
-            x: rng.random_range(0..term_width),
-            y: rng.random_range(0..term_height),
-            speed_y: rng.random_range(1..2),
-            speed_x: rng.random_range(-1..2),
-            char: SNOWFLAKE_CHARS[rng.random_range(0..SNOWFLAKE_CHARS.len())],
-        });
+    /// Test that info messages and validation warnings are silent with `--quiet`
+    #[test]
+    fn test_info_is_silent_when_quiet() {
+        let mut out = Vec::new();
+        write_info(&mut out, true, "seed: 42");
+        assert!(out.is_empty());
+        write_info(&mut out, false, "seed: 42");
+        assert_eq!(out, b"seed: 42\n");
+
+        // Validation warnings go out the same way
+        let mut warnings = Vec::new();
+        assert_eq!(validate_interval(0, &mut warnings), MIN_INTERVAL_MS);
+        assert_eq!(warnings.len(), 1);
+        let mut out = Vec::new();
+        write_info(&mut out, true, &warnings[0]);
+        assert!(out.is_empty());
+        write_info(&mut out, false, &warnings[0]);
+        assert!(String::from_utf8(out).unwrap().contains("interval 0ms"));
     }
-    snowflakes
-}
 
-fn create_snowflakes(term_width: u16, term_height: u16, rng: &mut ThreadRng) -> Vec<Snowflake> {
-    create_snowflakes_with_count(term_width, term_height, rng, 50) // Default to 50 for backward compatibility
-}
+    /// Test that a firework burst sends its sparks out evenly in every direction
+    #[test]
+    fn test_burst_sparks_spread_evenly_all_round() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let sparks = burst_sparks(40.0, 5.0, 48, &mut rng);
+        assert_eq!(sparks.len(), 48);
 
-fn update_snowflakes(
-    snowflakes: &mut [Snowflake],
-    term_width: u16,
-    term_height: u16,
-    rng: &mut ThreadRng,
-) {
-    for flake in snowflakes {
-        flake.y += flake.speed_y;
-        if flake.y >= term_height {
-            flake.y = 0;
-            flake.x = rng.random_range(0..term_width);
+        // Count the sparks heading into each quarter of the compass, undoing the squash
+        let mut quarters = [0; 4];
+        for spark in &sparks {
+            assert_eq!((spark.x, spark.y), (40.0, 5.0));
+            let angle = (spark.vy * 2.0)
+                .atan2(spark.vx)
+                .rem_euclid(std::f32::consts::TAU);
+            quarters[(angle / std::f32::consts::FRAC_PI_2) as usize % 4] += 1;
+        }
+        for count in quarters {
+            assert!((10..=14).contains(&count), "{quarters:?}");
         }
 
-        flake.x = (flake.x as i16 + flake.speed_x as i16) as u16;
-        if flake.x >= term_width {
-            flake.x = 0;
-        } else if flake.x == 0 && flake.speed_x < 0 {
-            flake.x = term_width - 1;
+        // A shell climbs, bursts at the top of its arc, and is gone once its sparks are
+        let mut fireworks = vec![launch_firework(80, 24, &mut rng)];
+        let mut burst = false;
+        for _ in 0..500 {
+            update_fireworks(&mut fireworks, &mut rng);
+            burst |= fireworks
+                .first()
+                .is_some_and(|f| f.state == FireworkState::Bursting);
         }
+        assert!(burst && fireworks.is_empty());
     }
-}
 
-fn create_clouds_with_count(
-    term_width: u16,
-    term_height: u16,
-    rng: &mut ThreadRng,
-    count: u16,
-) -> Vec<Cloud> {
-    let mut clouds = Vec::new();
-    for _ in 0..count {
-        // Create count clouds
-        clouds.push(Cloud {
-            x: rng.random_range(0..term_width) as f32,
-            y: rng.random_range(0..term_height / 4), // Upper quarter of the screen
-            shape: CLOUD_SHAPES[rng.random_range(0..CLOUD_SHAPES.len())],
-            speed: rng.random_range(0.5..1.5),
-        });
+    /// Test that a snapshot is the same for the same seed and differs for another
+    #[test]
+    fn test_snapshot_is_the_same_for_the_same_seed() {
+        let args = parse_args(["city", "--snapshot", "--seed", "42"]);
+        let first = snapshot_frame(&args, &VEHICLE_STYLES, 80, 24, 42);
+        assert!(!first.is_empty());
+        assert_eq!(first, snapshot_frame(&args, &VEHICLE_STYLES, 80, 24, 42));
+        assert_ne!(first, snapshot_frame(&args, &VEHICLE_STYLES, 80, 24, 43));
     }
-    clouds
-}
 
-fn create_clouds(term_width: u16, term_height: u16, rng: &mut ThreadRng) -> Vec<Cloud> {
-    create_clouds_with_count(term_width, term_height, rng, 5) // Default to 5 for backward compatibility
-}
+    /// Test that a vehicle casts its shadow on the row beneath it
+    #[test]
+    fn test_vehicle_casts_shadow_below_itself() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut vehicle = spawn_vehicle(
+            80,
+            &road_layout(24),
+            &[("o-o-o".into(), Color::Cyan, 4.0)],
+            &mut rng,
+        );
+        vehicle.x = 20.0;
+        let below = vehicle.y + 1;
+        let cells = vehicle_shadow_cells(&vehicle, 80);
+        let columns: Vec<u16> = cells.iter().map(|&(x, _)| x).collect();
+        assert_eq!(columns, (19..24).collect::<Vec<_>>());
+        assert!(cells.iter().all(|&(_, y)| y == below));
 
-fn update_clouds(clouds: &mut [Cloud], term_width: u16) {
-    for cloud in clouds {
-        cloud.x += cloud.speed * 0.1;
-        if cloud.x > term_width as f32 {
-            cloud.x = -(cloud.shape.len() as f32); // Wrap around
+        // The road under the shadow is darkened, the same stretch of road further on left alone
+        let mut frame = Frame::new(80, 24);
+        draw_road(&mut frame, 80, &road_layout(24), 0.0, &[]);
+        let road = frame.get(29, below).unwrap();
+        draw_vehicle(&mut frame, &vehicle, 80, 0, false);
+        let shaded = frame.get(21, below).unwrap();
+        assert_eq!(shaded.ch, road.ch);
+        assert_ne!(shaded.color, road.color);
+        assert_eq!(frame.get(29, below), Some(road));
+    }
+
+    /// Test that buildings stand on the `--horizon` row with the ground strip below them
+    #[test]
+    fn test_buildings_stand_on_the_configured_horizon() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let args = parse_args(["city", "--horizon", "9", "--ground", "grass"]);
+        let scene = Scene::new(&args, &VEHICLE_STYLES, 80, 30, &mut rng);
+        assert_eq!(scene.horizon_y(), 30 - 9);
+        assert_eq!(scene.ground_rows, 4);
+
+        let mut frame = Frame::new(80, 30);
+        draw_buildings(
+            &mut frame,
+            &scene.buildings,
+            scene.height,
+            0.0,
+            false,
+            &scene.palette,
+            false,
+        );
+        for building in scene.buildings.iter().filter(|b| b.x < 80) {
+            let base = frame.get(building.x, scene.horizon_y() - 1).unwrap();
+            assert_eq!(base.color, building.color);
+            assert_eq!(frame.get(building.x, scene.horizon_y()), None);
         }
+
+        // The street sits on the horizon and the ground fills the rows below it
+        let road = road_layout(scene.height);
+        assert_eq!(road.far_sidewalk, scene.horizon_y());
+        assert_eq!(road.near_sidewalk + 1 + scene.ground_rows, 30);
+
+        // Too high a horizon still leaves the minimum height above the ground
+        assert_eq!(ground_rows(100, DEFAULT_LANES, 30), 30 - MIN_HEIGHT);
+        assert_eq!(ground_rows(STREET_ROWS, DEFAULT_LANES, 30), 0);
     }
-}
 
-/// Draws the entire scene by calling individual drawing functions
-fn draw_scene(
-    stdout: &mut io::Stdout,
-    buildings: &[Building],
-    vehicles: &[Vehicle],
-    stars: &[Star],
-    raindrops: &[RainDrop],
-    snowflakes: &[Snowflake],
-    clouds: &[Cloud],
-    term_width: u16,
-    term_height: u16,
-    is_snow: bool,
-) -> io::Result<()> {
-    stdout.queue(Clear(ClearType::All))?;
+    /// Test that ASCII vehicles are drawn facing the way they drive
+    #[test]
+    fn test_vehicles_face_the_way_they_drive() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut car = spawn_vehicle(
+            80,
+            &road_layout(24),
+            &[("o-o-o".into(), Color::Cyan, 4.0)],
+            &mut rng,
+        );
+        car.x = 10.0;
+        assert_eq!(car.glyph(), "o-o-o");
+        let drawn = |car: &Vehicle| {
+            let mut frame = Frame::new(80, 24);
+            draw_vehicle(&mut frame, car, 80, 0, false);
+            (0..80)
+                .filter_map(|x| frame.get(x, car.y).map(|cell| cell.ch))
+                .collect::<String>()
+        };
+        assert_eq!(drawn(&car), "o-o-o");
 
-    // Draw background elements first
-    draw_clouds(stdout, clouds)?;
-    draw_stars(stdout, stars)?;
-    draw_moon(stdout, term_width)?;
-    draw_buildings(stdout, buildings, term_height)?;
-    draw_road(stdout, term_width, term_height)?;
-    draw_weather_effects(stdout, raindrops, snowflakes, is_snow)?;
-    draw_vehicles(stdout, vehicles)?;
+        car.speed = -car.speed;
+        car.target_speed = -car.target_speed;
+        assert_eq!(car.glyph(), "<o-o-o");
+        assert_eq!(drawn(&car), "<o-o-o");
 
-    stdout.flush()
-}
+        // Emoji have no mirror image, so they look the same either way
+        assert_eq!(directional_glyph("🚓", -3.5), "🚓");
+    }
 
-/// Draws all clouds in the scene
-fn draw_clouds(stdout: &mut io::Stdout, clouds: &[Cloud]) -> io::Result<()> {
-    for cloud in clouds {
-        stdout
-            .queue(cursor::MoveTo(cloud.x as u16, cloud.y))?
-            .queue(style::SetForegroundColor(CLOUD_COLOR))?
-            .queue(Print(cloud.shape))?;
+    /// Test that each star distribution gathers the stars where it says
+    #[test]
+    fn test_star_distributions_gather_where_asked() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut mean_y = |distribution| {
+            let samples: Vec<u16> = (0..2000)
+                .map(|_| sample_star_y(distribution, 40, &mut rng))
+                .collect();
+            assert!(samples.iter().all(|&y| y < 20));
+            samples.iter().map(|&y| y as f32).sum::<f32>() / samples.len() as f32
+        };
+        let zenith = mean_y(StarDistribution::Zenith);
+        let uniform = mean_y(StarDistribution::Uniform);
+        let horizon = mean_y(StarDistribution::Horizon);
+        assert!(
+            zenith < uniform && uniform < horizon,
+            "{zenith} {uniform} {horizon}"
+        );
     }
-    Ok(())
-}
 
-/// Draws all stars in the scene
-fn draw_stars(stdout: &mut io::Stdout, stars: &[Star]) -> io::Result<()> {
-    for star in stars {
-        stdout
-            .queue(cursor::MoveTo(star.x, star.y))?
-            .queue(style::SetForegroundColor(STAR_COLOR))?
-            .queue(Print(star.char))?;
+    /// Test that a raindrop hitting the road splashes into a ripple that fades
+    #[test]
+    fn test_raindrop_splashes_then_ripple_fades() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let road_y = road_layout(24).near_lane;
+        let mut drops = vec![RainDrop {
+            x: 30,
+            y: road_y - 1,
+            speed: 1,
+        }];
+        let mut ripples = ParticlePool::default();
+        update_raindrops(&mut drops, &mut ripples, 80, road_y, 0, &[], &mut rng);
+        assert_eq!(drops[0].y, 0);
+        let ripple = ripples.iter().next().unwrap();
+        assert_eq!((ripple.x, ripple.y, ripple_radius(ripple)), (30, road_y, 0));
+
+        let mut radii = vec![0];
+        loop {
+            let radius = ripples.iter().next().map(ripple_radius);
+            let Some(radius) = radius else {
+                break;
+            };
+            radii.push(radius);
+            update_ripples(&mut ripples);
+        }
+        assert!(radii.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(radii.last(), Some(&(RIPPLE_GLYPHS.len() - 1)));
     }
-    Ok(())
-}
 
-/// Draws the moon in the scene
-fn draw_moon(stdout: &mut io::Stdout, term_width: u16) -> io::Result<()> {
-    stdout
-        .queue(cursor::MoveTo(term_width - 15, 1))?
-        .queue(style::SetForegroundColor(MOON_COLOR))?
-        .queue(Print("  ,'.'."))?
-        .queue(cursor::MoveTo(term_width - 15, 2))?
-        .queue(Print(" ,'. ..'."))?
-        .queue(cursor::MoveTo(term_width - 15, 3))?
-        .queue(Print(".' .. '. '."))?;
-    Ok(())
-}
+    /// Test that light pollution glows more above tall, lit buildings than over the gaps
+    /// between them
+    #[test]
+    fn test_sky_glows_more_above_tall_lit_buildings_than_over_gaps() {
+        let window = || Window {
+            on: true,
+            tv: false,
+            flicker: 0.0,
+            brightness: 1.0,
+            lamp: WINDOW_ON_COLOR,
+        };
+        let buildings = vec![Building {
+            kind: BuildingKind::Skyscraper,
+            x: 0,
+            width: 5,
+            height: 20,
+            color: BUILDING_COLORS[0],
+            windows: (0..9).map(|_| vec![window(), window()]).collect(),
+            window_layout: BLOCK_WINDOWS,
+            has_antenna: false,
+            antenna_char: ' ',
+            roof_snow: 0.0,
+            beacon: None,
+        }];
 
-/// Draws all buildings in the scene
-fn draw_buildings(
-    stdout: &mut io::Stdout,
-    buildings: &[Building],
-    term_height: u16,
-) -> io::Result<()> {
-    for building in buildings {
-        // Draw building structure
-        for y in 0..building.height {
-            for x in 0..building.width {
-                stdout
-                    .queue(cursor::MoveTo(
-                        building.x + x,
-                        term_height - building.height - 3 + y,
-                    ))?
-                    .queue(style::SetForegroundColor(building.color))?
-                    .queue(Print("█"))?;
+        let above = glow_intensity(2, &buildings);
+        let gap = glow_intensity(60, &buildings);
+        assert!(above > gap, "{above} should exceed {gap}");
+        assert_eq!(gap, 0.0);
+        assert!(glow_intensity(4 + GLOW_REACH as u16, &buildings) < above);
+    }
+
+    /// Test that colors blend between RGB values and named colors, returning the ends
+    /// unchanged
+    #[test]
+    fn test_lerp_color_blends_rgb_and_named_colors() {
+        let (a, b) = (
+            Color::Rgb {
+                r: 0,
+                g: 100,
+                b: 200,
+            },
+            Color::Rgb {
+                r: 200,
+                g: 100,
+                b: 0,
+            },
+        );
+        assert_eq!(lerp_color(a, b, 0.0), a);
+        assert_eq!(lerp_color(a, b, 1.0), b);
+        assert_eq!(
+            lerp_color(a, b, 0.5),
+            Color::Rgb {
+                r: 100,
+                g: 100,
+                b: 100
             }
-        }
+        );
+        // Out-of-range amounts clamp to the ends
+        assert_eq!(lerp_color(a, b, 2.0), b);
 
-        // Draw antenna if present
-        if building.has_antenna {
-            stdout
-                .queue(cursor::MoveTo(
-                    building.x + building.width / 2,
-                    term_height - building.height - 4,
-                ))?
-                .queue(style::SetForegroundColor(building.color))?
-                .queue(Print(building.antenna_char))?;
+        // A named color blends by its approximate RGB value
+        assert_eq!(
+            lerp_color(Color::Red, Color::Rgb { r: 0, g: 0, b: 255 }, 0.5),
+            Color::Rgb {
+                r: 128,
+                g: 0,
+                b: 128
+            }
+        );
+        assert_eq!(lerp_color(Color::Red, b, 0.0), Color::Red);
+    }
+
+    /// Test that radio tower beacons blink on their cadence and stay on screen
+    #[test]
+    fn test_radio_tower_beacons_blink_on_cadence_within_bounds() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let args = parse_args(["city", "--radio-towers", "2"]);
+        let scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        let base_y = scene.horizon_y();
+        let towers: Vec<&Building> = scene
+            .buildings
+            .iter()
+            .filter(|b| b.kind == BuildingKind::RadioTower)
+            .collect();
+        assert_eq!(towers.len(), 2);
+        for tower in towers {
+            let (x, y) = beacon_position(tower, base_y).unwrap();
+            assert!(
+                x < scene.city_width && y < scene.height,
+                "beacon at {x},{y}"
+            );
         }
 
-        // Draw windows
-        for (wy, row) in building.windows.iter().enumerate() {
-            for (wx, window) in row.iter().enumerate() {
-                let color = if window.on {
-                    WINDOW_ON_COLOR
-                } else {
-                    WINDOW_OFF_COLOR
-                };
-                stdout
-                    .queue(cursor::MoveTo(
-                        building.x + (wx as u16 * 2) + 1,
-                        term_height - building.height - 2 + (wy as u16 * 2),
-                    ))?
-                    .queue(style::SetForegroundColor(color))?
-                    .queue(Print("■"))?;
-            }
+        let mut beacon = BeaconState::new(Duration::ZERO);
+        assert!(beacon.lit());
+        beacon
+            .timer
+            .advance(BEACON_PERIOD - Duration::from_millis(1));
+        assert!(beacon.lit());
+        beacon.timer.advance(Duration::from_millis(1));
+        assert!(!beacon.lit());
+        beacon.timer.advance(BEACON_PERIOD);
+        assert!(beacon.lit());
+    }
+
+    /// Test that fast-forward runs several scene updates for each frame drawn
+    #[test]
+    fn test_fast_forward_runs_several_updates_per_frame() {
+        let args = parse_args(["city", "--day-length", "600", "--fast-forward-steps", "4"]);
+        let dt = Duration::from_millis(50);
+        let mut normal = Scene::new(
+            &args,
+            &VEHICLE_STYLES,
+            80,
+            24,
+            &mut StdRng::seed_from_u64(7),
+        );
+        let mut fast = Scene::new(
+            &args,
+            &VEHICLE_STYLES,
+            80,
+            24,
+            &mut StdRng::seed_from_u64(7),
+        );
+        fast.fast_forward = true;
+        let start = normal.time_of_day;
+
+        normal.advance(dt, &mut StdRng::seed_from_u64(7));
+        fast.advance(dt, &mut StdRng::seed_from_u64(7));
+        let ratio = (fast.time_of_day - start) / (normal.time_of_day - start);
+        assert!((ratio - 4.0).abs() < 1e-3, "fast-forward ran {ratio}x");
+        assert_eq!(fast.tick, 4 * normal.tick);
+    }
+
+    /// Test that a weather schedule parses and cycles through its entries
+    #[test]
+    fn test_weather_schedule_parses_and_cycles() {
+        let WeatherSchedule(schedule) =
+            parse_weather_schedule("clear:120, rain:60,Fog:30").unwrap();
+        assert_eq!(
+            schedule,
+            vec![
+                (Weather::Clear, Duration::from_secs(120)),
+                (Weather::Rain, Duration::from_secs(60)),
+                (Weather::Fog, Duration::from_secs(30)),
+            ]
+        );
+        for bad in ["clear", "hail:10", "rain:soon", "snow:0", ""] {
+            assert!(parse_weather_schedule(bad).is_err(), "{bad} parsed");
         }
+
+        let at = |secs| current_weather(&schedule, Duration::from_secs(secs));
+        assert_eq!(at(0), Weather::Clear);
+        assert_eq!(at(119), Weather::Clear);
+        assert_eq!(at(120), Weather::Rain);
+        assert_eq!(at(185), Weather::Fog);
+        // Past the end it starts over from the first step
+        assert_eq!(at(210), Weather::Clear);
+        assert_eq!(at(210 + 150), Weather::Rain);
+
+        let args = parse_args(["city", "--weather-schedule", "rain:1,fog:1"]);
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        scene.update(Duration::from_millis(500), &mut rng);
+        assert_eq!(scene.weather, WeatherState::Rain);
+        scene.update(Duration::from_millis(600), &mut rng);
+        assert_eq!(scene.weather, WeatherState::Clear);
+        assert_eq!(scene.fog, SCHEDULED_FOG);
     }
-    Ok(())
-}
 
-/// Draws the road at the bottom of the scene
-fn draw_road(stdout: &mut io::Stdout, term_width: u16, term_height: u16) -> io::Result<()> {
-    let road_y = term_height - 3;
-    stdout.queue(cursor::MoveTo(0, road_y))?;
-    stdout.queue(style::SetForegroundColor(ROAD_COLOR))?;
-    for _ in 0..term_width {
-        stdout.queue(Print("="))?;
+    /// Test that the long version names the crate version and the build
+    #[test]
+    fn test_long_version_names_the_crate_version_and_build() {
+        let version = Args::command().render_long_version();
+        assert!(version.contains(env!("CARGO_PKG_VERSION")), "{version}");
+        assert!(version.contains(env!("CITY_GIT_HASH")), "{version}");
     }
-    stdout.queue(cursor::MoveTo(0, road_y + 1))?;
-    for _ in 0..term_width {
-        stdout.queue(Print("="))?;
+
+    /// Test that the render pacer draws no more than `--max-fps` frames a second
+    #[test]
+    fn test_max_fps_caps_renders_over_a_window() {
+        let mut pacer = RenderPacer::new(5);
+        let start = Instant::now();
+        // Polling every 7ms for two seconds asks far more often than the cap allows
+        let renders = (0..=2000 / 7)
+            .filter(|step| pacer.due(start + Duration::from_millis(step * 7)))
+            .count();
+        assert!(
+            (9..=11).contains(&renders),
+            "{renders} renders in 2s at 5 fps"
+        );
+
+        // After a long stall it starts afresh rather than bursting to catch up
+        let later = start + Duration::from_secs(10);
+        assert!(pacer.due(later));
+        assert!(!pacer.due(later + Duration::from_millis(100)));
     }
-    Ok(())
-}
 
-/// Draws weather effects (either rain or snow based on the is_snow flag)
-fn draw_weather_effects(
-    stdout: &mut io::Stdout,
-    raindrops: &[RainDrop],
-    snowflakes: &[Snowflake],
-    is_snow: bool,
-) -> io::Result<()> {
-    if is_snow {
-        // Draw snowflakes
-        for flake in snowflakes {
-            stdout
-                .queue(cursor::MoveTo(flake.x, flake.y))?
-                .queue(style::SetForegroundColor(SNOW_COLOR))?
-                .queue(Print(flake.char))?;
+    /// Test that polling input often with `--max-fps` leaves the scene moving at the same
+    /// pace per second as the plain interval does
+    #[test]
+    fn test_max_fps_keeps_the_scene_pace() {
+        let args = parse_args(["city", "--spawn-rate", "0.5"]);
+        let paced_args = parse_args(["city", "--spawn-rate", "0.5", "--max-fps", "5"]);
+        let interval = Duration::from_millis(args.interval);
+        let positions = |scene: &Scene| {
+            let vehicles = scene.vehicles.iter().map(|v| v.x);
+            vehicles
+                .chain(scene.clouds.iter().map(|c| c.x))
+                .collect::<Vec<f32>>()
+        };
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut plain = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        // A second's worth of loop passes, one per interval
+        for _ in 0..1000 / args.interval {
+            plain.advance(interval, &mut rng);
         }
-    } else {
-        // Draw raindrops
-        for drop in raindrops {
-            stdout
-                .queue(cursor::MoveTo(drop.x, drop.y))?
-                .queue(style::SetForegroundColor(RAIN_COLOR))?
-                .queue(Print("|"))?;
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut paced = Scene::new(&paced_args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        let mut clock = StepClock::new(interval);
+        // The same second, with the loop polling input every few milliseconds
+        for _ in 0..1000 / INPUT_POLL_MS {
+            for _ in 0..clock.steps(Duration::from_millis(INPUT_POLL_MS)) {
+                paced.advance(clock.step, &mut rng);
+            }
         }
+        assert!(!plain.vehicles.is_empty());
+        assert_eq!(positions(&paced), positions(&plain));
     }
-    Ok(())
-}
 
-/// Draws all vehicles in the scene
-fn draw_vehicles(stdout: &mut io::Stdout, vehicles: &[Vehicle]) -> io::Result<()> {
-    for vehicle in vehicles {
-        stdout
-            .queue(cursor::MoveTo(vehicle.x as u16, vehicle.y))?
-            .queue(style::SetForegroundColor(vehicle.color))?
-            .queue(Print(vehicle.style))?;
+    /// Test that matrix streams fall down the screen with fading trails behind them
+    #[test]
+    fn test_matrix_streams_fall_with_fading_trails() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut streams = create_matrix_streams(40, 24, &mut rng);
+        for stream in &mut streams {
+            stream.head = 5.0;
+        }
+        update_matrix_streams(&mut streams, 24, &mut rng);
+        for stream in &streams {
+            assert!(stream.head > 5.0);
+            let trail = matrix_trail(stream, 24);
+            assert_eq!(trail[0], (stream.head.floor() as u16, 1.0));
+            for pair in trail.windows(2) {
+                assert_eq!(pair[1].0 + 1, pair[0].0, "trail runs up from the head");
+                assert!(pair[1].1 < pair[0].1, "trail fades away from the head");
+            }
+        }
+
+        // Once the trail has left the bottom the stream starts again above the screen
+        streams[0].head = 24.0 + streams[0].trail as f32;
+        update_matrix_streams(&mut streams, 24, &mut rng);
+        assert!(streams[0].head < 0.0);
+        assert!(matrix_trail(&streams[0], 24).len() <= streams[0].trail as usize + 1);
     }
-    Ok(())
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rand::rngs::ThreadRng;
+    /// Test that spawning a vehicle tells the event callback about it
+    #[test]
+    fn test_spawning_a_vehicle_delivers_an_event() {
+        let args = parse_args(["city", "--spawn-rate", "1.0"]);
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        scene.vehicles.clear();
+        let mut events = Vec::new();
+        scene.update_with_events(Duration::from_millis(50), &mut rng, &mut |event| {
+            events.push(event)
+        });
+        assert!(events.contains(&SceneEvent::VehicleSpawned), "{events:?}");
+        assert!(!events.contains(&SceneEvent::Lightning));
+    }
 
-    /// Test that create_stars_with_count creates the correct number of stars
+    /// Test that a cursor move off the screen is skipped without writing anything
     #[test]
-    fn test_create_stars_with_count() {
-        let mut rng = ThreadRng::default();
-        let stars = create_stars_with_count(80, 24, &mut rng, 10);
-        assert_eq!(stars.len(), 10);
+    fn test_move_to_checked_skips_positions_off_screen() {
+        for (x, y) in [(-1, 0), (0, -3), (80, 0), (0, 24), (i32::MAX, 5)] {
+            let mut out = Vec::new();
+            assert!(!move_to_checked(&mut out, x, y, (80, 24)).unwrap());
+            assert!(out.is_empty(), "({x}, {y}) wrote {out:?}");
+        }
 
-        // Verify all stars are within the specified bounds
-        for star in &stars {
-            assert!(star.x < 80);
-            assert!(star.y < 24 / 2); // Stars only in the top half of the screen
-            assert!(STAR_CHARS.contains(&star.char));
+        let mut out = Vec::new();
+        assert!(move_to_checked(&mut out, 3, 4, (80, 24)).unwrap());
+        out.flush().unwrap();
+        // Terminal rows and columns count from one
+        assert_eq!(out, b"\x1b[5;4H");
+
+        // Rows of a frame taller than the terminal it's flushed to are skipped
+        let mut out = Vec::new();
+        Frame::new(12, 4)
+            .flush_to(&mut out, Color::Black, None, (12, 2))
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().matches('H').count(), 2);
+
+        // A moon on a terminal narrower than its offset stays on screen
+        assert_eq!(moon_origin(10), (0, 1));
+    }
+
+    /// Test that with `--cloud-rain` a drop starts again beneath a cloud
+    #[test]
+    fn test_cloud_rain_restarts_drops_beneath_the_cloud() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut clouds = create_clouds_with_count(80, 24, &mut rng, 1, CloudSize::Small);
+        clouds[0].x = 20.0;
+        let span = 20..20 + cloud_width(clouds[0].shape);
+        let sources = rain_sources(&clouds, 80);
+        assert_eq!(sources[0].0, span);
+
+        let road_y = road_layout(24).near_lane;
+        let mut drops: Vec<RainDrop> = (0..50)
+            .map(|x| RainDrop {
+                x,
+                y: road_y - 1,
+                speed: 1,
+            })
+            .collect();
+        let mut ripples = ParticlePool::default();
+        update_raindrops(&mut drops, &mut ripples, 80, road_y, 0, &sources, &mut rng);
+        for drop in &drops {
+            assert!(span.contains(&drop.x), "drop restarted at {}", drop.x);
+            assert_eq!(drop.y, sources[0].1);
         }
+
+        // A cloud drifting off the left edge only rains from the part still on screen
+        clouds[0].x = -3.0;
+        let sources = rain_sources(&clouds, 80);
+        assert_eq!(sources[0].0.start, 0);
     }
 
-    /// Test that create_buildings creates buildings with valid properties
+    /// Test that the beacon's beam turns, wraps around and lights the cells along it
     #[test]
-    fn test_create_buildings() {
-        let mut rng = ThreadRng::default();
-        let buildings = create_buildings(80, 24, &mut rng);
+    fn test_beacon_sweep_advances_wraps_and_lights_its_arc() {
+        let tau = std::f32::consts::TAU;
+        let mut angle = 0.0;
+        let mut wrapped = false;
+        for _ in 0..200 {
+            let next = advance_sweep(angle, 0.05);
+            assert!((0.0..tau).contains(&next));
+            if next < angle {
+                wrapped = true;
+                assert!(
+                    angle + SWEEP_SPEED * 0.05 >= tau,
+                    "wrapped early at {angle}"
+                );
+            } else {
+                assert!(next > angle);
+            }
+            angle = next;
+        }
+        assert!(wrapped);
 
-        for building in &buildings {
-            assert!(building.width >= 5 && building.width < 15);
-            assert!(building.height >= 5 && building.height < 24 - 5);
-            assert!(building.x < 80);
-            assert!(BUILDING_COLORS.contains(&building.color));
+        let source = (40, 15);
+        let angle = 1.0;
+        let cells = sweep_cells(source, angle, 80);
+        assert!(!cells.is_empty());
+        for (x, y, weight) in cells {
+            assert!(y < source.1 && (0.0..=1.0).contains(&weight));
+            let heading = (((source.1 - y) * 2) as f32).atan2(x as f32 - source.0 as f32);
+            assert!(
+                (heading - angle).abs() <= SWEEP_SPREAD + 1e-4,
+                "({x}, {y}) off the arc"
+            );
         }
+        // Pointing down into the city lights nothing
+        assert!(sweep_cells(source, 4.7, 80).is_empty());
     }
 
-    /// Test that spawn_vehicle creates valid vehicles
+    /// Test that lanes alternate direction and vehicles spawn into each of them, heading
+    /// its way
     #[test]
-    fn test_spawn_vehicle() {
-        let mut rng = ThreadRng::default();
-        let vehicle = spawn_vehicle(80, 24, &mut rng);
+    fn test_lanes_alternate_direction_and_each_get_traffic() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let args = parse_args(["city", "--lanes", "3"]);
+        let scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        let road = scene.road();
+        assert_eq!(road.lanes, 3);
+        assert_eq!(road.near_sidewalk, 23);
+        assert_eq!(road.dividers().count(), 2);
 
-        // Check that the vehicle properties are from our valid set
-        let valid_styles: Vec<&str> = VEHICLE_STYLES.iter().map(|(style, _, _)| *style).collect();
-        assert!(valid_styles.contains(&vehicle.style));
+        let mut rows = std::collections::BTreeMap::new();
+        for _ in 0..200 {
+            let vehicle = spawn_vehicle(80, &road, &VEHICLE_STYLES, &mut rng);
+            let lane = road.lane_at(vehicle.y).expect("vehicles drive in a lane");
+            assert_eq!(vehicle.speed.signum(), road.lane_direction(lane));
+            rows.insert(vehicle.y, vehicle.speed.signum());
+        }
+        assert_eq!(rows.len(), 3);
+        let directions: Vec<f32> = rows.into_values().collect();
+        assert_eq!(directions, [1.0, -1.0, 1.0]);
 
-        let valid_colors: Vec<Color> = VEHICLE_STYLES.iter().map(|(_, color, _)| *color).collect();
-        assert!(valid_colors.contains(&vehicle.color));
+        assert_eq!(street_layout(24, DEFAULT_LANES), road_layout(24));
+        assert!(Args::try_parse_from(["city", "--lanes", "5"]).is_err());
+    }
 
-        let valid_speeds: Vec<f32> = VEHICLE_STYLES.iter().map(|(_, _, speed)| *speed).collect();
-        assert!(valid_speeds.contains(&vehicle.speed));
+    /// Test that `--skyline-only` leaves nothing but stars and windows changing
+    #[test]
+    fn test_skyline_only_keeps_just_stars_and_windows_moving() {
+        let mut args = parse_args([
+            "city",
+            "--skyline-only",
+            "--rain",
+            "--flock-size",
+            "12",
+            "--spawn-rate",
+            "1.0",
+            // Few enough stars, twinkling slowly enough, that some frames look the same
+            "--stars",
+            "3",
+            "--twinkle-speed",
+            "0.002",
+        ]);
+        apply_skyline_only(&mut args);
+        assert_eq!(args.interval, SKYLINE_INTERVAL_MS);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+        // Just one building, lit by lamps rather than TVs, so there are quiet spells between
+        // its windows fading
+        for building in &mut scene.buildings[1..] {
+            building.windows.clear();
+        }
+        for window in scene.buildings[0].windows.iter_mut().flatten() {
+            window.tv = false;
+        }
+        let phases: Vec<f32> = scene.stars.iter().map(|s| s.phase).collect();
+        let mut events = Vec::new();
+        let mut still = 0;
+        for _ in 0..500 {
+            let changed = scene.update_with_events(
+                Duration::from_millis(args.interval),
+                &mut rng,
+                &mut |event| events.push(event),
+            );
+            still += !changed as u32;
+            assert!(scene.vehicles.is_empty());
+            assert!(scene.clouds.is_empty());
+            assert!(scene.birds.is_empty());
+            assert!(scene.raindrops.is_empty() && scene.snowflakes.is_empty());
+        }
+        assert!(events.is_empty(), "{events:?}");
+        assert!(scene.stars.iter().zip(&phases).any(|(s, p)| s.phase != *p));
+        assert!(still > 0, "every frame was redrawn");
     }
 
-    /// Test that vehicles spawn with appropriate y positions
+    /// Test that `--building-outlines` darkens a building's edge columns and roof while
+    /// its interior keeps the body color
     #[test]
-    fn test_spawn_vehicle_y_position() {
-        let mut rng = ThreadRng::default();
-        let road_y = 24 - 3; // term_height - 3
-        let vehicle1 = spawn_vehicle(80, 24, &mut rng);
-        let vehicle2 = spawn_vehicle(80, 24, &mut rng);
+    fn test_building_outlines_darken_the_edges() {
+        let building = || Building {
+            kind: BuildingKind::MidRise,
+            x: 2,
+            width: 6,
+            height: 8,
+            color: BUILDING_COLORS[0],
+            windows: Vec::new(),
+            window_layout: BLOCK_WINDOWS,
+            has_antenna: false,
+            antenna_char: '|',
+            roof_snow: 0.0,
+            beacon: None,
+        };
+        let body = BUILDING_COLORS[0];
+        let outline = lerp_color(body, Color::Black, OUTLINE_SHADE);
+        assert_ne!(outline, body);
+        let top_y = road_layout(24).far_sidewalk - 8;
 
-        // Vehicle y position should be either road_y or road_y - 1
-        assert!(vehicle1.y == road_y || vehicle1.y == road_y - 1);
-        assert!(vehicle2.y == road_y || vehicle2.y == road_y - 1);
+        let draw = |outlines| {
+            let mut frame = Frame::new(80, 24);
+            let palette = Theme::Night.palette();
+            draw_buildings(
+                &mut frame,
+                &[building()],
+                24,
+                0.0,
+                false,
+                &palette,
+                outlines,
+            );
+            frame
+        };
+        let frame = draw(true);
+        let color = |x, y| frame.get(x, y).unwrap().color;
+        let y = top_y + 4;
+        assert_eq!(color(2, y), outline);
+        assert_eq!(color(7, y), outline);
+        assert_eq!(color(4, top_y), outline);
+        for x in 3..7 {
+            assert_eq!(color(x, y), body);
+        }
+        assert_eq!(draw(false).get(2, y).unwrap().color, body);
     }
 
-    /// Test that building windows are created with the right pattern
+    /// Test that a light background gets a light sky over dark buildings, and that the
+    /// terminal's reply is read correctly
     #[test]
-    fn test_building_windows_pattern() {
-        let mut rng = ThreadRng::default();
-        let buildings = create_buildings(80, 24, &mut rng);
+    fn test_light_background_gets_a_light_sky_over_dark_buildings() {
+        let palette = palette_for_background(Background::Light);
+        assert!(luminance(palette.sky) > 0.6);
+        for building in palette.buildings {
+            assert!(luminance(building) < 0.3, "{building:?}");
+        }
+        assert_eq!(
+            palette_for_background(Background::Dark),
+            Theme::Night.palette()
+        );
 
-        // Verify buildings have windows created
-        for building in &buildings {
-            assert!(!building.windows.is_empty());
+        let reply = |body: &str| parse_background_reply(body.as_bytes());
+        assert_eq!(
+            reply("\x1b]11;rgb:ffff/ffff/dddd\x1b\\\x1b[?62;c"),
+            Some(Background::Light)
+        );
+        assert_eq!(reply("\x1b]11;rgb:1e/1e/2e\x07"), Some(Background::Dark));
+        assert_eq!(reply("\x1b[?62;c"), None);
+        assert_eq!(reply("\x1b]11;rgb:zz/00/00\x07"), None);
+
+        assert!(background_reply_complete(
+            b"\x1b]11;rgb:cccc/cccc/cccc\x07\x1b[?62;22c"
+        ));
+        assert!(!background_reply_complete(
+            b"\x1b]11;rgb:cccc/cccc/cccc\x07\x1b[?62;2"
+        ));
+    }
+
+    /// Test that `--vehicle-jitter` spreads vehicles over more rows than the lanes, onto the
+    /// lines beside them heading those lanes' way, while without it they keep to the lanes
+    #[test]
+    fn test_vehicle_jitter_loosens_the_lane_rows() {
+        let spawned = |lanes: &str, jitter: bool| {
+            let mut argv = vec!["city", "--spawn-rate", "1.0", "--lanes", lanes];
+            if jitter {
+                argv.push("--vehicle-jitter");
+            }
+            let args = parse_args(argv);
+            let mut rng = StdRng::seed_from_u64(7);
+            let mut scene = Scene::new(&args, &VEHICLE_STYLES, 80, 24, &mut rng);
+            let mut rows = std::collections::BTreeSet::new();
+            for _ in 0..200 {
+                scene.vehicles.clear();
+                scene.update(Duration::from_millis(50), &mut rng);
+                let vehicles = scene.vehicles.iter().filter(|v| !v.is_plow);
+                rows.extend(vehicles.map(|v| (v.y, v.speed > 0.0)));
+            }
+            (scene.road(), rows)
+        };
+        let ys = |rows: &std::collections::BTreeSet<(u16, bool)>| {
+            rows.iter()
+                .map(|&(y, _)| y)
+                .collect::<std::collections::BTreeSet<_>>()
+        };
+
+        let (road, rows) = spawned("2", false);
+        assert_eq!(
+            ys(&rows).into_iter().collect::<Vec<_>>(),
+            [road.far_lane, road.near_lane]
+        );
+        let (_, rows) = spawned("2", true);
+        assert!(ys(&rows).len() > 2, "{rows:?}");
+
+        // A vehicle nudged onto a line heads the way of one of the lanes beside it
+        let (road, rows) = spawned("3", true);
+        let dividers: Vec<u16> = road.dividers().collect();
+        assert!(ys(&rows).iter().any(|y| dividers.contains(y)));
+        for (y, rightward) in rows {
+            let beside = [y.checked_sub(1), Some(y), Some(y + 1)];
+            let heading = beside
+                .into_iter()
+                .flatten()
+                .filter_map(|row| road.lane_at(row))
+                .any(|lane| (road.lane_direction(lane) > 0.0) == rightward);
+            assert!(heading, "row {y} heading right: {rightward}");
+            assert!((road.far_lane..=road.near_lane).contains(&y));
         }
     }
 }